@@ -1,15 +1,81 @@
+use crate::colors::{
+    contrast_ratio, dim_blend, hsl_to_rgb, lerp_color, parse_hex_color, resolve_named, rgb_to_hsl,
+    shift_temperature, to_rgb, MIN_CONTRAST,
+};
+use crate::export::{ExportFormat, ExportOptions, ProgressExport, TerminalProfile};
+use crate::import::ProgressImport;
+use crate::palette::Palette;
+use crate::recent::RecentFiles;
+use crate::transform::TransformRegistry;
+use ratatui::layout::Alignment;
 use ratatui::style::Color;
+use std::collections::{HashMap, HashSet};
+use unicode_width::UnicodeWidthChar;
+
+/// Default columns between tab stops when measuring rendered width
+pub const TAB_WIDTH: usize = 8;
+/// Widest tab stop a document may ask for
+pub const MAX_TAB_WIDTH: usize = 16;
+
+/// Characters per "pixel" in grid mode, so cells look roughly square
+pub const GRID_CELL: usize = 2;
+
+/// How far one warm/cool key press shifts the buffer's colors
+pub const TEMPERATURE_STEP: i32 = 8;
+
+/// How much one +/- press in a color picker changes lightness
+pub const LIGHTNESS_STEP: f32 = 0.05;
+
+/// Furthest the editor/controls split can move either way
+pub const MAX_EDITOR_EXTRA_ROWS: i16 = 24;
+
+/// Zero-width characters (joiners, zero-width spaces, variation selectors, combining marks)
+/// attach to the character before them and take no column
+fn is_zero_width(ch: char) -> bool {
+    !ch.is_control() && ch.width() == Some(0)
+}
+
+/// Columns a character takes for cursor movement; tabs and other controls count as one
+fn column_width(ch: char) -> usize {
+    if ch.is_control() {
+        1
+    } else {
+        ch.width().unwrap_or(1)
+    }
+}
+
+/// How a character is underlined
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UnderlineStyle {
+    #[default]
+    None,
+    Single,
+    Double,
+}
+
+impl UnderlineStyle {
+    /// Any kind of underline
+    pub fn is_underlined(self) -> bool {
+        self != UnderlineStyle::None
+    }
+}
 
 /// Represents styling for a single character
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct CharStyle {
     pub fg: Color,
     pub bg: Color,
     pub bold: bool,
     pub italic: bool,
-    pub underline: bool,
+    pub underline: UnderlineStyle,
+    pub overline: bool,
     pub strikethrough: bool,
+    pub blink: bool,
+    pub reverse: bool,
+    pub hidden: bool,
     pub dim_level: u8, // 0-3: 0 = none, 1-3 = increasing dimness
+    /// OSC 8 hyperlink target
+    pub link: Option<String>,
 }
 
 impl Default for CharStyle {
@@ -19,9 +85,14 @@ impl Default for CharStyle {
             bg: Color::Reset,
             bold: false,
             italic: false,
-            underline: false,
+            underline: UnderlineStyle::None,
+            overline: false,
             strikethrough: false,
+            blink: false,
+            reverse: false,
+            hidden: false,
             dim_level: 0,
+            link: None,
         }
     }
 }
@@ -83,6 +154,96 @@ impl Panel {
     }
 }
 
+/// What makes two lines duplicates in the audit overlay
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateAudit {
+    /// Same characters, whatever their styles
+    PlainText,
+    /// Same characters with the same styles
+    Styled,
+}
+
+/// Textual representation shown while editing the buffer's source
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceFormat {
+    Ron,
+    Ansi,
+}
+
+impl SourceFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SourceFormat::Ron => "RON",
+            SourceFormat::Ansi => "ANSI",
+        }
+    }
+}
+
+/// When picking a color in the color panels recolors the text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorApply {
+    /// Every color key and navigation step recolors the selection immediately, as a live preview
+    #[default]
+    Live,
+    /// Only Enter applies the highlighted color
+    OnCommit,
+}
+
+/// Truecolor entry opened from a color picker with `#`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbEntry {
+    /// Editing the foreground (otherwise the background)
+    pub is_foreground: bool,
+    /// Red, green and blue
+    pub rgb: [u8; 3],
+    /// Hex digits typed so far; the sixth one (or Enter after three) replaces `rgb`
+    pub typed: String,
+    /// Channel the arrow keys adjust (0 red, 1 green, 2 blue)
+    pub channel: usize,
+}
+
+impl RgbEntry {
+    /// The color being edited
+    pub fn color(&self) -> Color {
+        let [r, g, b] = self.rgb;
+        Color::Rgb(r, g, b)
+    }
+
+    /// Add a typed hex digit, ignoring anything else and digits past the sixth
+    pub fn type_digit(&mut self, ch: char) -> bool {
+        if !ch.is_ascii_hexdigit() || self.typed.len() >= 6 {
+            return false;
+        }
+        self.typed.push(ch.to_ascii_lowercase());
+        if self.typed.len() == 6 {
+            self.resolve_typed();
+        }
+        true
+    }
+
+    /// Take the typed digits (three as shorthand, or six) as the color. False when
+    /// they aren't a color yet; nothing typed is fine.
+    pub fn resolve_typed(&mut self) -> bool {
+        if self.typed.is_empty() {
+            return true;
+        }
+        match parse_hex_color(&format!("#{}", self.typed)).and_then(to_rgb) {
+            Some((r, g, b)) => {
+                self.rgb = [r, g, b];
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Nudge the active channel, dropping any partly typed digits
+    pub fn adjust(&mut self, delta: i16) {
+        let value = &mut self.rgb[self.channel];
+        *value = (*value as i16 + delta).clamp(0, 255) as u8;
+        self.typed.clear();
+    }
+}
+
 /// How to display selection highlighting
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum SelectionHighlightMode {
@@ -93,16 +254,103 @@ pub enum SelectionHighlightMode {
     Underline,
 }
 
+/// Which current style attributes were changed since the last reset or style load
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TouchedFields {
+    pub fg: bool,
+    pub bg: bool,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub overline: bool,
+    pub strikethrough: bool,
+    pub blink: bool,
+    pub reverse: bool,
+    pub hidden: bool,
+    pub dim: bool,
+}
+
+impl TouchedFields {
+    /// Every attribute, for when the whole current style should be applied
+    pub fn all() -> Self {
+        Self {
+            fg: true,
+            bg: true,
+            bold: true,
+            italic: true,
+            underline: true,
+            overline: true,
+            strikethrough: true,
+            blink: true,
+            reverse: true,
+            hidden: true,
+            dim: true,
+        }
+    }
+}
+
+/// How an attribute is set across a selection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriState {
+    AllOn,
+    AllOff,
+    Mixed,
+}
+
+/// What a pending prompt is asking for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromptKind {
+    /// Stripe period and optional offset, e.g. "2" or "3,1"
+    StripePeriod,
+    /// Text to search for
+    Search,
+    /// Alignment and width, e.g. "c,40"
+    Align,
+    /// Line reordering: "s" sort, "S" case-sensitive sort, "r" reverse
+    LineOrder,
+    /// 1-based line number to jump to, or a command such as `transform NAME` or `dim N`
+    Command,
+    /// Sequence expression, e.g. `seq(1..=5, "Item {} ")`
+    Sequence,
+    /// Name to save the current style under
+    PresetName,
+}
+
+impl PromptKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptKind::StripePeriod => "Stripe period[,offset]",
+            PromptKind::Search => "Search",
+            PromptKind::Align => "Align l|c|r,width",
+            PromptKind::Sequence => "seq(start..=end, \"text {}\")",
+            PromptKind::LineOrder => "Lines: s sort, S sort case-sensitive, r reverse",
+            PromptKind::Command => "Line number, transform NAME or dim 0-3",
+            PromptKind::PresetName => "Save current style as preset",
+        }
+    }
+}
+
+/// A single-line text prompt shown in the status bar
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
+
 /// Main application state
 pub struct App {
     /// The styled text buffer
     pub text: Vec<StyledChar>,
     /// Current cursor position
     pub cursor_pos: usize,
+    /// Column vertical movement aims for, kept while passing through shorter lines
+    pub goal_column: Option<usize>,
     /// Optional selection range (start, end) - inclusive
     pub selection: Option<(usize, usize)>,
     /// Selection anchor when in selecting mode
     pub selection_anchor: Option<usize>,
+    /// Buffer as it was when the selection started, restored if the selection is cancelled
+    pub selection_snapshot: Option<Vec<StyledChar>>,
     /// Currently selected foreground color
     pub current_fg: Color,
     /// Currently selected background color  
@@ -112,15 +360,25 @@ pub struct App {
     /// Italic toggle
     pub current_italic: bool,
     /// Underline toggle
-    pub current_underline: bool,
+    pub current_underline: UnderlineStyle,
+    /// Overline toggle
+    pub current_overline: bool,
     /// Strikethrough toggle
     pub current_strikethrough: bool,
+    /// Blink toggle
+    pub current_blink: bool,
+    /// Reverse video toggle
+    pub current_reverse: bool,
+    /// Conceal toggle
+    pub current_hidden: bool,
     /// Dim level (0-3)
     pub current_dim: u8,
     /// Current input mode
     pub mode: Mode,
     /// Currently focused panel
     pub active_panel: Panel,
+    /// Panel focused before the current one, for toggling back
+    pub previous_panel: Panel,
     /// Color picker index for foreground
     pub fg_color_index: usize,
     /// Color picker index for background
@@ -131,6 +389,92 @@ pub struct App {
     pub should_quit: bool,
     /// Selection highlight display mode
     pub selection_highlight_mode: SelectionHighlightMode,
+    /// Horizontal movement wraps across line ends (otherwise it stops, vim-style)
+    pub cursor_wrap: bool,
+    /// Columns between tab stops
+    pub tab_width: usize,
+    /// Brush mode: paint the current style onto every character the cursor moves onto
+    pub brush_active: bool,
+    /// Dim every line except the cursor's (display only)
+    pub line_focus: bool,
+    /// Pixel-art grid: horizontal movement snaps to two-column cells
+    pub grid_mode: bool,
+    /// Highlight duplicate lines (display only)
+    pub duplicate_audit: Option<DuplicateAudit>,
+    /// Mark the right edge of soft-wrapped editor rows
+    pub show_wrap_markers: bool,
+    /// Draw a checkerboard behind cells with no background color
+    pub show_transparency: bool,
+    /// Leave the editor content on the terminal's own background instead of the app theme
+    pub terminal_bg_preview: bool,
+    /// Buffer holds the plain-text source of the styled buffer, applied on commit
+    pub source_edit: Option<SourceFormat>,
+    /// Show the full keybinding list in a two-line footer
+    pub help_expanded: bool,
+    /// Rows moved from the controls to the editor (negative gives the controls more)
+    pub editor_extra_rows: i16,
+    /// Fewest and most extra editor rows the current layout can show
+    pub editor_rows_bounds: (i16, i16),
+    /// First text line shown in the editor
+    pub scroll_offset: usize,
+    /// Buffer changed since the last autosave
+    pub dirty: bool,
+    /// Buffer recovered from a crash, waiting for the user to accept or discard it
+    pub pending_recovery: Option<Vec<StyledChar>>,
+    /// Pending text prompt, if any
+    pub prompt: Option<Prompt>,
+    /// Recently opened files
+    pub recent_files: RecentFiles,
+    /// Absolute path of the opened file, which names its recovery file; None for a new or piped buffer
+    pub document_path: Option<String>,
+    /// Highlighted entry while the recent files overlay is open
+    pub recent_overlay: Option<usize>,
+    /// Recent file waiting for a second Enter because opening it would drop unsaved changes
+    pub confirm_open: Option<usize>,
+    /// Open truecolor entry in a color picker
+    pub rgb_entry: Option<RgbEntry>,
+    /// Colors offered by the color pickers
+    pub palette: Palette,
+    /// Named styles, saved to the config directory
+    pub presets: HashMap<String, CharStyle>,
+    /// Why the presets file failed to load; it isn't saved over while this is set
+    pub presets_error: Option<String>,
+    /// Highlighted entry while the presets overlay is open
+    pub preset_overlay: Option<usize>,
+    /// Enter in a style panel during a selection finishes it and refocuses the editor
+    pub enter_returns_to_editor: bool,
+    /// Whether moving through the color panels applies colors or waits for Enter
+    pub color_apply: ColorApply,
+    /// How the echo export lays out its escape sequences
+    pub export_options: ExportOptions,
+    /// What the `e` export copies
+    pub export_format: ExportFormat,
+    /// Large export being produced in chunks
+    pub export_job: Option<ProgressExport>,
+    /// Large file still being loaded into the buffer
+    pub import_job: Option<ProgressImport>,
+    /// Attributes merge-apply will override
+    pub touched: TouchedFields,
+    /// Styled characters yanked from selections, ready to paste
+    pub yank_buffer: Vec<StyledChar>,
+    /// Style copied with the format painter, kept apart from the current style
+    pub stored_style: Option<CharStyle>,
+    /// Numeric prefix typed before a movement (vim-style `5j`)
+    pub pending_count: Option<usize>,
+    /// `M` was pressed and the next key picks the mark command
+    pub pending_mark: bool,
+    /// Positions marked with `Ma` and `Mb` for style interpolation, kept on their characters through edits
+    pub marks: (Option<usize>, Option<usize>),
+    /// Ranges (start, end) - inclusive - matched by the last search
+    pub search_matches: Vec<(usize, usize)>,
+    /// Play an ambient border effect while idle
+    pub ambient_effect: bool,
+    /// Transforms runnable by name from the command prompt
+    pub transforms: TransformRegistry,
+    /// Copy through the terminal with OSC 52 (also the fallback when the clipboard is unreachable)
+    pub osc52_clipboard: bool,
+    /// Terminal that PowerShell exports are downgraded for
+    pub terminal_profile: TerminalProfile,
 }
 
 impl Default for App {
@@ -138,22 +482,72 @@ impl Default for App {
         Self {
             text: Vec::new(),
             cursor_pos: 0,
+            goal_column: None,
             selection: None,
             selection_anchor: None,
+            selection_snapshot: None,
             current_fg: Color::Reset,
             current_bg: Color::Reset,
             current_bold: false,
             current_italic: false,
-            current_underline: false,
+            current_underline: UnderlineStyle::None,
+            current_overline: false,
             current_strikethrough: false,
+            current_blink: false,
+            current_reverse: false,
+            current_hidden: false,
             current_dim: 0,
             mode: Mode::Normal,
             active_panel: Panel::Editor,
+            previous_panel: Panel::Editor,
             fg_color_index: 0, // None/Reset
             bg_color_index: 0, // None/Reset
             status_message: None,
             should_quit: false,
             selection_highlight_mode: SelectionHighlightMode::default(),
+            cursor_wrap: true,
+            tab_width: TAB_WIDTH,
+            brush_active: false,
+            line_focus: false,
+            grid_mode: false,
+            duplicate_audit: None,
+            show_wrap_markers: false,
+            show_transparency: false,
+            terminal_bg_preview: false,
+            source_edit: None,
+            help_expanded: false,
+            editor_extra_rows: 0,
+            editor_rows_bounds: (-MAX_EDITOR_EXTRA_ROWS, MAX_EDITOR_EXTRA_ROWS),
+            scroll_offset: 0,
+            dirty: false,
+            pending_recovery: None,
+            recent_files: RecentFiles::default(),
+            document_path: None,
+            recent_overlay: None,
+            confirm_open: None,
+            rgb_entry: None,
+            palette: Palette::default(),
+            presets: HashMap::new(),
+            presets_error: None,
+            preset_overlay: None,
+            prompt: None,
+            enter_returns_to_editor: true,
+            color_apply: ColorApply::default(),
+            export_options: ExportOptions::default(),
+            export_format: ExportFormat::default(),
+            export_job: None,
+            import_job: None,
+            touched: TouchedFields::default(),
+            yank_buffer: Vec::new(),
+            stored_style: None,
+            pending_count: None,
+            pending_mark: false,
+            marks: (None, None),
+            search_matches: Vec::new(),
+            ambient_effect: false,
+            transforms: TransformRegistry::default(),
+            osc52_clipboard: false,
+            terminal_profile: TerminalProfile::default(),
         }
     }
 }
@@ -163,36 +557,109 @@ impl App {
         Self::default()
     }
 
+    /// Create an app whose buffer holds `text` with default styling, cursor at the end
+    #[cfg(test)]
+    pub fn from_text(text: &str) -> Self {
+        Self::from_styled(text.chars().map(StyledChar::new).collect())
+    }
+
+    /// Create an app with a preset styled buffer, cursor at the end
+    #[cfg(test)]
+    pub fn from_styled(text: Vec<StyledChar>) -> Self {
+        let cursor_pos = text.len();
+        Self {
+            text,
+            cursor_pos,
+            ..Self::default()
+        }
+    }
+
+    /// Swap in a freshly loaded document, dropping everything tied to the old buffer
+    pub fn replace_document(&mut self, text: Vec<StyledChar>) {
+        self.text = text;
+        self.cursor_pos = self.text.len();
+        self.goal_column = None;
+        self.scroll_offset = 0;
+        self.clear_selection();
+        self.search_matches.clear();
+        self.marks = (None, None);
+        self.pending_mark = false;
+        self.source_edit = None;
+        self.import_job = None;
+        self.export_job = None;
+        self.dirty = false;
+    }
+
+    /// Build a CharStyle from the current style settings
+    pub fn current_style(&self) -> CharStyle {
+        CharStyle {
+            fg: self.current_fg,
+            bg: self.current_bg,
+            bold: self.current_bold,
+            italic: self.current_italic,
+            underline: self.current_underline,
+            overline: self.current_overline,
+            strikethrough: self.current_strikethrough,
+            blink: self.current_blink,
+            reverse: self.current_reverse,
+            hidden: self.current_hidden,
+            dim_level: self.current_dim,
+            link: None,
+        }
+    }
+
     /// Insert a character at the cursor position
     pub fn insert_char(&mut self, ch: char) {
-        let styled = StyledChar::with_style(
-            ch,
-            CharStyle {
-                fg: self.current_fg,
-                bg: self.current_bg,
-                bold: self.current_bold,
-                italic: self.current_italic,
-                underline: self.current_underline,
-                strikethrough: self.current_strikethrough,
-                dim_level: self.current_dim,
-            },
-        );
+        let styled = StyledChar::with_style(ch, self.current_style());
 
         if self.cursor_pos >= self.text.len() {
             self.text.push(styled);
         } else {
             self.text.insert(self.cursor_pos, styled);
         }
+        self.shift_selection_for_insert(self.cursor_pos, 1);
         self.cursor_pos += 1;
-        self.clear_selection();
+        self.goal_column = None;
+        self.dirty = true;
+        self.search_matches.clear();
+    }
+
+    /// Open an empty line below the cursor's line and start typing on it (vim `o`)
+    pub fn open_line_below(&mut self) {
+        self.cursor_pos = self.get_line_boundaries(self.cursor_pos).1;
+        self.insert_char('\n');
+        self.mode = Mode::Typing;
+    }
+
+    /// Open an empty line above the cursor's line and start typing on it (vim `O`)
+    pub fn open_line_above(&mut self) {
+        self.cursor_pos = self.get_line_boundaries(self.cursor_pos).0;
+        self.insert_char('\n');
+        self.cursor_pos -= 1;
+        self.mode = Mode::Typing;
+    }
+
+    /// Insert already-styled characters at the cursor position
+    pub fn insert_chars(&mut self, chars: Vec<StyledChar>) {
+        let pos = self.cursor_pos.min(self.text.len());
+        let count = chars.len();
+        self.text.splice(pos..pos, chars);
+        self.shift_selection_for_insert(pos, count);
+        self.cursor_pos = pos + count;
+        self.goal_column = None;
+        self.dirty = true;
+        self.search_matches.clear();
     }
 
     /// Delete the character before the cursor
     pub fn delete_char(&mut self) {
         if self.cursor_pos > 0 && !self.text.is_empty() {
             self.cursor_pos -= 1;
+            self.goal_column = None;
             self.text.remove(self.cursor_pos);
-            self.clear_selection();
+            self.shift_selection_for_delete(self.cursor_pos, 1);
+            self.dirty = true;
+            self.search_matches.clear();
         }
     }
 
@@ -200,35 +667,281 @@ impl App {
     pub fn delete_char_forward(&mut self) {
         if self.cursor_pos < self.text.len() {
             self.text.remove(self.cursor_pos);
-            self.clear_selection();
+            self.shift_selection_for_delete(self.cursor_pos, 1);
+            self.goal_column = None;
+            self.dirty = true;
+            self.search_matches.clear();
+        }
+    }
+
+    /// Keep the selection and marks on the same characters after `count` were inserted
+    /// at `pos`; an insertion inside the selection grows it
+    fn shift_selection_for_insert(&mut self, pos: usize, count: usize) {
+        let shift = |i: usize| if i >= pos { i + count } else { i };
+        self.marks = (self.marks.0.map(shift), self.marks.1.map(shift));
+        if let Some((start, end)) = self.selection {
+            self.selection = Some((shift(start), if end >= pos { end + count } else { end }));
+        }
+        self.selection_anchor = self.selection_anchor.map(shift);
+    }
+
+    /// Keep the selection and marks on the same characters after `count` were removed at
+    /// `pos`, dropping the selection if every selected character was removed. A mark on a
+    /// removed character moves to the character after the gap
+    fn shift_selection_for_delete(&mut self, pos: usize, count: usize) {
+        let removed = pos..pos + count;
+        let shift = |i: usize| if i >= removed.end { i - count } else { i.min(pos) };
+        self.marks = (self.marks.0.map(shift), self.marks.1.map(shift));
+        if let Some((start, end)) = self.selection {
+            if removed.contains(&start) && removed.contains(&end) {
+                self.clear_selection();
+                return;
+            }
+            let new_end = if removed.contains(&end) { pos - 1 } else { shift(end) };
+            self.selection = Some((shift(start), new_end));
         }
+        self.selection_anchor = self.selection_anchor.map(shift);
     }
 
     /// Move cursor left
     pub fn move_left(&mut self) {
+        self.goal_column = None;
+        if self.grid_mode {
+            let line_start = self.get_line_boundaries(self.cursor_pos).0;
+            let col = self.cursor_pos - line_start;
+            if col > 0 {
+                self.cursor_pos = line_start + (col - 1) / GRID_CELL * GRID_CELL;
+                self.update_selection();
+                self.brush_paint();
+                return;
+            }
+        }
+        if !self.cursor_wrap && self.cursor_pos == self.get_line_boundaries(self.cursor_pos).0 {
+            return;
+        }
         if self.cursor_pos > 0 {
             self.cursor_pos -= 1;
+            // Zero-width characters belong to the character before them
+            while self.cursor_pos > 0 && is_zero_width(self.text[self.cursor_pos].ch) {
+                self.cursor_pos -= 1;
+            }
             self.update_selection();
+            self.brush_paint();
         }
     }
 
     /// Move cursor right
     pub fn move_right(&mut self) {
+        self.goal_column = None;
+        if self.grid_mode {
+            let (line_start, line_end) = self.get_line_boundaries(self.cursor_pos);
+            if self.cursor_pos < line_end {
+                let col = self.cursor_pos - line_start;
+                self.cursor_pos = (line_start + (col / GRID_CELL + 1) * GRID_CELL).min(line_end);
+                self.update_selection();
+                self.brush_paint();
+                return;
+            }
+        }
+        if !self.cursor_wrap && self.cursor_pos == self.get_line_boundaries(self.cursor_pos).1 {
+            return;
+        }
         if self.cursor_pos < self.text.len() {
             self.cursor_pos += 1;
+            while self.text.get(self.cursor_pos).is_some_and(|c| is_zero_width(c.ch)) {
+                self.cursor_pos += 1;
+            }
+            self.update_selection();
+            self.brush_paint();
+        }
+    }
+
+    /// Insert `template` once for each number in `start..=end`, with `{}` replaced by the
+    /// number, all in the current style. Returns the number of characters inserted
+    pub fn insert_sequence(&mut self, start: i64, end: i64, template: &str) -> usize {
+        let style = self.current_style();
+        let chars: Vec<StyledChar> = (start..=end)
+            .flat_map(|n| template.replacen("{}", &n.to_string(), 1).chars().collect::<Vec<_>>())
+            .map(|ch| StyledChar::with_style(ch, style.clone()))
+            .collect();
+        let count = chars.len();
+        self.insert_chars(chars);
+        count
+    }
+
+    /// Interpolate every attribute from the style at one mark to the style at the other:
+    /// colors blend through RGB, booleans and colors without RGB values switch at the midpoint.
+    /// Returns the number of characters styled, or None unless both marks are set and distinct
+    pub fn interpolate_styles_between_marks(&mut self) -> Option<usize> {
+        let (Some(a), Some(b)) = self.marks else {
+            return None;
+        };
+        let (start, end) = (a.min(b), a.max(b));
+        if start == end || end >= self.text.len() {
+            return None;
+        }
+
+        let from = self.text[start].style.clone();
+        let to = self.text[end].style.clone();
+        let mut count = 0;
+        for i in start..=end {
+            if self.text[i].ch == '\n' {
+                continue;
+            }
+            let t = (i - start) as f32 / (end - start) as f32;
+            let nearer = if t < 0.5 { &from } else { &to };
+            self.text[i].style = CharStyle {
+                fg: lerp_color(from.fg, to.fg, t).unwrap_or(nearer.fg),
+                bg: lerp_color(from.bg, to.bg, t).unwrap_or(nearer.bg),
+                bold: nearer.bold,
+                italic: nearer.italic,
+                underline: nearer.underline,
+                overline: nearer.overline,
+                strikethrough: nearer.strikethrough,
+                blink: nearer.blink,
+                reverse: nearer.reverse,
+                hidden: nearer.hidden,
+                dim_level: (from.dim_level as f32 + (to.dim_level as f32 - from.dim_level as f32) * t)
+                    .round() as u8,
+                link: nearer.link.clone(),
+            };
+            count += 1;
+        }
+        self.dirty = true;
+        Some(count)
+    }
+
+    /// Derive a quick theme from the current foreground: a dark background and a light accent
+    /// for text that has no color yet, applied to the selection or the whole buffer.
+    /// Returns the (background, accent) pair, or None when the foreground has no RGB value
+    pub fn theme_from_current_fg(&mut self) -> Option<(Color, Color)> {
+        let background = dim_blend(self.current_fg, Color::Black, 3)?;
+        let accent = dim_blend(self.current_fg, Color::White, 2)?;
+
+        let (start, end) = self.selection.unwrap_or((0, self.text.len().saturating_sub(1)));
+        for styled_char in self.text.iter_mut().take(end + 1).skip(start) {
+            if styled_char.ch == '\n' {
+                continue;
+            }
+            styled_char.style.bg = background;
+            if styled_char.style.fg == Color::Reset {
+                styled_char.style.fg = accent;
+            }
+        }
+        self.dirty = true;
+        Some((background, accent))
+    }
+
+    /// Toggle grid mode, snapping the cursor to the start of its cell
+    pub fn toggle_grid_mode(&mut self) {
+        self.grid_mode = !self.grid_mode;
+        if self.grid_mode {
+            let line_start = self.get_line_boundaries(self.cursor_pos).0;
+            let col = self.cursor_pos - line_start;
+            self.cursor_pos = line_start + col / GRID_CELL * GRID_CELL;
+            self.goal_column = None;
             self.update_selection();
         }
     }
 
+    /// Paint the grid cell at the cursor as a solid block of the current background color,
+    /// overwriting characters on the line and appending past its end
+    pub fn fill_grid_cell(&mut self) {
+        let style = CharStyle {
+            fg: self.current_bg,
+            bg: self.current_bg,
+            ..Default::default()
+        };
+        for _ in 0..GRID_CELL {
+            let block = StyledChar::with_style('█', style.clone());
+            match self.text.get(self.cursor_pos) {
+                Some(c) if c.ch != '\n' => self.text[self.cursor_pos] = block,
+                _ => self.text.insert(self.cursor_pos, block),
+            }
+            self.cursor_pos += 1;
+        }
+        self.goal_column = None;
+        self.dirty = true;
+        self.search_matches.clear();
+        self.clear_selection();
+    }
+
     /// Move cursor to start
+    #[cfg(test)]
     pub fn move_to_start(&mut self) {
         self.cursor_pos = 0;
+        self.goal_column = None;
         self.update_selection();
     }
 
     /// Move cursor to end
+    #[cfg(test)]
     pub fn move_to_end(&mut self) {
         self.cursor_pos = self.text.len();
+        self.goal_column = None;
+        self.update_selection();
+    }
+
+    /// Indices of lines that appear more than once, compared as `audit` says.
+    /// Blank and whitespace-only lines are spacing, not duplicates, so they're skipped
+    pub fn duplicate_lines(&self, audit: DuplicateAudit) -> HashSet<usize> {
+        let mut seen: HashMap<Vec<(char, Option<&CharStyle>)>, Vec<usize>> = HashMap::new();
+        for (i, line) in self.text.split(|c| c.ch == '\n').enumerate() {
+            if line.iter().all(|c| c.ch.is_whitespace()) {
+                continue;
+            }
+            let key = line
+                .iter()
+                .map(|c| (c.ch, (audit == DuplicateAudit::Styled).then_some(&c.style)))
+                .collect();
+            seen.entry(key).or_default().push(i);
+        }
+        seen.into_values()
+            .filter(|lines| lines.len() > 1)
+            .flatten()
+            .collect()
+    }
+
+    /// Rendered (columns, lines) of the buffer as a terminal would print it: tabs advance
+    /// to the next tab stop and wide characters take two columns. A trailing newline
+    /// doesn't add a line.
+    pub fn rendered_dimensions(&self) -> (usize, usize) {
+        if self.text.is_empty() {
+            return (0, 0);
+        }
+
+        let mut lines: Vec<&[StyledChar]> = self.text.split(|c| c.ch == '\n').collect();
+        if self.text.last().is_some_and(|c| c.ch == '\n') {
+            lines.pop();
+        }
+        let width = lines
+            .iter()
+            .map(|line| {
+                line.iter().fold(0, |col, c| match c.ch {
+                    '\t' => (col / self.tab_width.max(1) + 1) * self.tab_width.max(1),
+                    ch => col + ch.width().unwrap_or(0),
+                })
+            })
+            .max()
+            .unwrap_or(0);
+        (width, lines.len())
+    }
+
+    /// Jump to the start of 1-based line `n`, clamped to the last line (extends any selection)
+    pub fn goto_line(&mut self, n: usize) {
+        self.cursor_pos = match n.saturating_sub(1) {
+            0 => 0,
+            target => self
+                .text
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.ch == '\n')
+                .map(|(i, _)| i + 1)
+                .take(target)
+                .last()
+                .unwrap_or(0),
+        };
+        self.goal_column = None;
         self.update_selection();
     }
 
@@ -256,10 +969,25 @@ impl App {
         (line_start, line_end)
     }
 
-    /// Get column position within current line
+    /// Get the display column of the cursor within the current line
     fn get_column(&self) -> usize {
         let (line_start, _) = self.get_line_boundaries(self.cursor_pos);
-        self.cursor_pos - line_start
+        self.text[line_start..self.cursor_pos]
+            .iter()
+            .map(|c| column_width(c.ch))
+            .sum()
+    }
+
+    /// Index of the character at display column `col` in the line `line_start..line_end`,
+    /// or `line_end` when the line is shorter
+    fn index_at_column(&self, line_start: usize, line_end: usize, col: usize) -> usize {
+        let mut pos = line_start;
+        let mut width = 0;
+        while pos < line_end && width + column_width(self.text[pos].ch) <= col {
+            width += column_width(self.text[pos].ch);
+            pos += 1;
+        }
+        pos
     }
 
     /// Move cursor up one line
@@ -271,17 +999,18 @@ impl App {
             return;
         }
 
-        // Current column position
-        let col = self.get_column();
+        // Aim for the remembered column, so empty and short lines don't lose it
+        let col = self.goal_column.unwrap_or_else(|| self.get_column());
+        self.goal_column = Some(col);
 
         // Find the previous line (line_start - 1 points to \n of previous line)
         let prev_line_end = line_start - 1;
         let (prev_line_start, _) = self.get_line_boundaries(prev_line_end);
-        let prev_line_len = prev_line_end - prev_line_start;
 
         // Move to same column or end of previous line if shorter
-        self.cursor_pos = prev_line_start + col.min(prev_line_len);
+        self.cursor_pos = self.index_at_column(prev_line_start, prev_line_end, col);
         self.update_selection();
+        self.brush_paint();
     }
 
     /// Move cursor down one line
@@ -293,23 +1022,41 @@ impl App {
             return;
         }
 
-        // Current column position
-        let col = self.get_column();
+        // Aim for the remembered column, so empty and short lines don't lose it
+        let col = self.goal_column.unwrap_or_else(|| self.get_column());
+        self.goal_column = Some(col);
 
         // Move past the \n to next line
         let next_line_start = line_end + 1;
         let (_, next_line_end) = self.get_line_boundaries(next_line_start);
-        let next_line_len = next_line_end - next_line_start;
 
         // Move to same column or end of next line if shorter
-        self.cursor_pos = next_line_start + col.min(next_line_len);
+        self.cursor_pos = self.index_at_column(next_line_start, next_line_end, col);
         self.update_selection();
+        self.brush_paint();
     }
 
-    /// Move cursor to start of current line
+    /// Smart home: jump to the line's first non-blank character, or to column 0 when
+    /// already there, so repeated presses alternate between the two
     pub fn move_to_line_start(&mut self) {
+        let (line_start, line_end) = self.get_line_boundaries(self.cursor_pos);
+        let first_non_blank = (line_start..line_end)
+            .find(|&i| !matches!(self.text[i].ch, ' ' | '\t'))
+            .unwrap_or(line_start);
+        self.cursor_pos = if self.cursor_pos == first_non_blank {
+            line_start
+        } else {
+            first_non_blank
+        };
+        self.goal_column = None;
+        self.update_selection();
+    }
+
+    /// Move cursor to column 0 of the current line (vim `0`)
+    pub fn move_to_column_zero(&mut self) {
         let (line_start, _) = self.get_line_boundaries(self.cursor_pos);
         self.cursor_pos = line_start;
+        self.goal_column = None;
         self.update_selection();
     }
 
@@ -317,11 +1064,32 @@ impl App {
     pub fn move_to_line_end(&mut self) {
         let (_, line_end) = self.get_line_boundaries(self.cursor_pos);
         self.cursor_pos = line_end;
+        self.goal_column = None;
         self.update_selection();
     }
 
-    /// Start selection mode
+    /// Text line (0-based) containing the cursor
+    pub fn cursor_line(&self) -> usize {
+        let end = self.cursor_pos.min(self.text.len());
+        self.text[..end].iter().filter(|c| c.ch == '\n').count()
+    }
+
+    /// Adjust `scroll_offset` so the cursor's line is within a viewport of `viewport_height` lines.
+    /// Works on text lines, so soft-wrapped lines may still push the cursor below the fold.
+    pub fn ensure_cursor_visible(&mut self, viewport_height: usize) {
+        let line = self.cursor_line();
+        let height = viewport_height.max(1);
+        if line < self.scroll_offset {
+            self.scroll_offset = line;
+        } else if line >= self.scroll_offset + height {
+            self.scroll_offset = line + 1 - height;
+        }
+    }
+
+    /// Start selection mode; only attributes changed from here on are applied to it
     pub fn start_selection(&mut self) {
+        self.touched = TouchedFields::default();
+        self.selection_snapshot = Some(self.text.clone());
         self.mode = Mode::Selecting;
         self.selection_anchor = Some(self.cursor_pos);
         self.selection = Some((self.cursor_pos, self.cursor_pos));
@@ -339,104 +1107,823 @@ impl App {
     }
 
     /// Clear selection
+    /// Only edits, yanks, applying a finished selection and Esc should call this;
+    /// switching panels keeps the selection so it can still be styled
     pub fn clear_selection(&mut self) {
         self.selection = None;
         self.selection_anchor = None;
+        self.selection_snapshot = None;
         if self.mode == Mode::Selecting {
             self.mode = Mode::Normal;
         }
     }
 
-    /// Apply current style to selection or character at cursor
-    pub fn apply_style(&mut self) {
-        let style = CharStyle {
-            fg: self.current_fg,
-            bg: self.current_bg,
-            bold: self.current_bold,
-            italic: self.current_italic,
-            underline: self.current_underline,
-            strikethrough: self.current_strikethrough,
-            dim_level: self.current_dim,
+    /// Leave the selection undoing the style changes previewed on it. Styles are only
+    /// restored when the text hasn't been edited since. Returns whether anything was reverted
+    pub fn cancel_selection(&mut self) -> bool {
+        let reverted = match self.selection_snapshot.take() {
+            Some(snapshot)
+                if snapshot.len() == self.text.len()
+                    && snapshot.iter().zip(&self.text).all(|(old, new)| old.ch == new.ch) =>
+            {
+                let changed = snapshot.iter().zip(&self.text).any(|(old, new)| old.style != new.style);
+                if changed {
+                    self.text = snapshot;
+                    self.dirty = true;
+                }
+                changed
+            }
+            _ => false,
         };
+        self.clear_selection();
+        reverted
+    }
 
-        if let Some((start, end)) = self.selection {
-            for i in start..=end.min(self.text.len().saturating_sub(1)) {
-                self.text[i].style = style.clone();
+    /// Apply only the touched attributes to the selection or character at cursor,
+    /// keeping everything else each character already has
+    pub fn apply_style_merge(&mut self) {
+        let range = match self.selection {
+            Some((start, end)) => start..end.saturating_add(1).min(self.text.len()),
+            None => self.cursor_pos..(self.cursor_pos + 1).min(self.text.len()),
+        };
+        let touched = self.touched;
+        for i in range {
+            let style = &mut self.text[i].style;
+            if touched.fg {
+                style.fg = self.current_fg;
+            }
+            if touched.bg {
+                style.bg = self.current_bg;
+            }
+            if touched.bold {
+                style.bold = self.current_bold;
+            }
+            if touched.italic {
+                style.italic = self.current_italic;
+            }
+            if touched.underline {
+                style.underline = self.current_underline;
+            }
+            if touched.overline {
+                style.overline = self.current_overline;
+            }
+            if touched.strikethrough {
+                style.strikethrough = self.current_strikethrough;
+            }
+            if touched.blink {
+                style.blink = self.current_blink;
+            }
+            if touched.reverse {
+                style.reverse = self.current_reverse;
+            }
+            if touched.hidden {
+                style.hidden = self.current_hidden;
+            }
+            if touched.dim {
+                style.dim_level = self.current_dim;
             }
-        } else if self.cursor_pos < self.text.len() {
-            self.text[self.cursor_pos].style = style;
         }
+        self.dirty = true;
     }
 
-    /// Toggle bold
-    pub fn toggle_bold(&mut self) {
-        self.current_bold = !self.current_bold;
-        self.apply_style();
+    /// Delete the selected characters and return to normal mode at the selection start
+    pub fn delete_selection(&mut self) {
+        if let Some((start, end)) = self.selection {
+            let end = (end + 1).min(self.text.len());
+            let start = start.min(end);
+            self.text.drain(start..end);
+            self.shift_selection_for_delete(start, end - start);
+            self.cursor_pos = start.min(self.text.len());
+            self.goal_column = None;
+            self.dirty = true;
+            self.search_matches.clear();
+        }
+        self.clear_selection();
     }
 
-    /// Toggle italic
-    pub fn toggle_italic(&mut self) {
-        self.current_italic = !self.current_italic;
-        self.apply_style();
+    /// Apply current style to the character at cursor. A selection only gets the attributes
+    /// touched since it started, so making mixed text bold keeps each char's colors
+    pub fn apply_style(&mut self) {
+        if self.selection.is_some() {
+            self.apply_style_merge();
+        } else if self.cursor_pos < self.text.len() {
+            self.restyle_cursor_char();
+            self.dirty = true;
+        }
     }
 
-    /// Toggle underline
-    pub fn toggle_underline(&mut self) {
-        self.current_underline = !self.current_underline;
-        self.apply_style();
+    /// Give the character at the cursor the current style, keeping any hyperlink it has,
+    /// since the style panels have no way to set one
+    fn restyle_cursor_char(&mut self) {
+        let link = self.text[self.cursor_pos].style.link.take();
+        self.text[self.cursor_pos].style = CharStyle {
+            link,
+            ..self.current_style()
+        };
     }
 
-    /// Toggle strikethrough
-    pub fn toggle_strikethrough(&mut self) {
-        self.current_strikethrough = !self.current_strikethrough;
-        self.apply_style();
-    }
+    /// Color the selection's foreground once around the hue wheel, in even steps per
+    /// visible char (newlines don't take a step). Returns how many chars were colored
+    pub fn apply_rainbow(&mut self) -> usize {
+        let Some((start, end)) = self.selection else {
+            return 0;
+        };
+        if self.text.is_empty() {
+            return 0;
+        }
+        let range = start..=end.min(self.text.len() - 1);
+        let visible = self.text[range.clone()].iter().filter(|c| c.ch != '\n').count();
+        let step = 360.0 / visible.max(1) as f32;
 
-    /// Cycle dim level
-    pub fn cycle_dim(&mut self) {
-        self.current_dim = (self.current_dim + 1) % 4;
-        self.apply_style();
+        let mut index = 0;
+        for styled_char in &mut self.text[range] {
+            if styled_char.ch == '\n' {
+                continue;
+            }
+            styled_char.style.fg = hsl_to_rgb(index as f32 * step, 1.0, 0.5);
+            index += 1;
+        }
+        if index > 0 {
+            self.dirty = true;
+        }
+        index
     }
 
-    /// Toggle selection highlight mode
-    pub fn toggle_selection_highlight_mode(&mut self) {
-        self.selection_highlight_mode = match self.selection_highlight_mode {
-            SelectionHighlightMode::Reversed => SelectionHighlightMode::Underline,
-            SelectionHighlightMode::Underline => SelectionHighlightMode::Reversed,
-        };
+    /// Shift every explicit foreground and background color in the buffer warmer (positive)
+    /// or cooler (negative); default colors stay default. Returns how many characters changed
+    pub fn adjust_temperature(&mut self, amount: i32) -> usize {
+        let mut changed = 0;
+        for c in self.text.iter_mut() {
+            let (fg, bg) = (shift_temperature(c.style.fg, amount), shift_temperature(c.style.bg, amount));
+            if (fg, bg) != (c.style.fg, c.style.bg) {
+                c.style.fg = fg;
+                c.style.bg = bg;
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.dirty = true;
+        }
+        changed
     }
 
-    /// Load style from character at cursor position into current settings
-    pub fn load_style_from_cursor(&mut self) {
-        use crate::colors::color_index_from_color;
-        
-        if self.cursor_pos < self.text.len() {
-            let style = &self.text[self.cursor_pos].style;
-            self.current_fg = style.fg;
-            self.current_bg = style.bg;
-            self.current_bold = style.bold;
-            self.current_italic = style.italic;
-            self.current_underline = style.underline;
-            self.current_strikethrough = style.strikethrough;
-            self.current_dim = style.dim_level;
-            
-            // Update color picker indices
-            self.fg_color_index = color_index_from_color(style.fg);
-            self.bg_color_index = color_index_from_color(style.bg);
+    /// Set every character's dim level to `level` (clamped to 0-3), leaving other
+    /// attributes alone; returns how many characters changed
+    pub fn set_all_dim(&mut self, level: u8) -> usize {
+        let level = level.min(3);
+        let mut changed = 0;
+        for c in self.text.iter_mut().filter(|c| c.style.dim_level != level) {
+            c.style.dim_level = level;
+            changed += 1;
         }
+        if changed > 0 {
+            self.dirty = true;
+        }
+        changed
     }
 
-    /// Reset current style to defaults
-    pub fn reset_style(&mut self) {
-        self.current_fg = Color::Reset;
-        self.current_bg = Color::Reset;
-        self.current_bold = false;
-        self.current_italic = false;
-        self.current_underline = false;
-        self.current_strikethrough = false;
-        self.current_dim = 0;
-        self.fg_color_index = 0; // None/Reset
-        self.bg_color_index = 0; // None/Reset
+    /// Style the character at the cursor with the current style and step to the next one,
+    /// staying put on the last character; returns false when there is nothing to style
+    pub fn apply_and_advance(&mut self) -> bool {
+        if self.cursor_pos >= self.text.len() {
+            return false;
+        }
+        self.restyle_cursor_char();
+        self.dirty = true;
+        if self.cursor_pos + 1 < self.text.len() {
+            self.move_right();
+        }
+        true
+    }
+
+    /// Find every non-overlapping occurrence of `query`, jumping to the first one at or after the cursor
+    pub fn search(&mut self, query: &str) -> usize {
+        let needle: Vec<char> = query.chars().collect();
+        self.search_matches.clear();
+        if needle.is_empty() {
+            return 0;
+        }
+
+        let mut i = 0;
+        while i + needle.len() <= self.text.len() {
+            if self.text[i..i + needle.len()].iter().map(|c| c.ch).eq(needle.iter().copied()) {
+                self.search_matches.push((i, i + needle.len() - 1));
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+
+        self.next_match();
+        self.search_matches.len()
+    }
+
+    /// Move the cursor to the next search match after the cursor, wrapping to the first
+    pub fn next_match(&mut self) {
+        let next = self
+            .search_matches
+            .iter()
+            .find(|(start, _)| *start > self.cursor_pos)
+            .or(self.search_matches.first());
+        if let Some(&(start, _)) = next {
+            self.cursor_pos = start;
+            self.goal_column = None;
+        }
+    }
+
+    /// Is the position inside a search match?
+    pub fn is_search_match(&self, pos: usize) -> bool {
+        self.search_matches
+            .iter()
+            .any(|&(start, end)| pos >= start && pos <= end)
+    }
+
+    /// Apply current style to every search match at once, returning how many were styled
+    pub fn style_all_matches(&mut self) -> usize {
+        let style = self.current_style();
+        let mut count = 0;
+        for &(start, end) in &self.search_matches {
+            if end >= self.text.len() {
+                continue;
+            }
+            for c in &mut self.text[start..=end] {
+                c.style = style.clone();
+            }
+            count += 1;
+        }
+        if count > 0 {
+            self.dirty = true;
+        }
+        count
+    }
+
+    /// Re-pad every line with leading spaces so it sits left, centered or right within `width`.
+    /// Existing leading spaces count as padding; lines wider than `width` are left as is.
+    pub fn align_lines(&mut self, width: usize, alignment: Alignment) {
+        let mut aligned = Vec::with_capacity(self.text.len());
+        // New index of every old position (plus the end), so the cursor can follow its character
+        let mut new_index = vec![0; self.text.len() + 1];
+
+        let mut line_start = 0;
+        while line_start <= self.text.len() {
+            let line_end = self.text[line_start..]
+                .iter()
+                .position(|c| c.ch == '\n')
+                .map_or(self.text.len(), |p| line_start + p);
+            let indent = self.text[line_start..line_end]
+                .iter()
+                .take_while(|c| c.ch == ' ')
+                .count();
+            // Wide characters take two columns
+            let content_width: usize = self.text[line_start + indent..line_end]
+                .iter()
+                .map(|c| c.ch.width().unwrap_or(0))
+                .sum();
+
+            let (skip, pad) = if content_width > width {
+                (0, 0)
+            } else {
+                let free = width - content_width;
+                let pad = match alignment {
+                    Alignment::Left => 0,
+                    Alignment::Center => free / 2,
+                    Alignment::Right => free,
+                };
+                (indent, pad)
+            };
+
+            aligned.extend((0..pad).map(|_| StyledChar::new(' ')));
+            new_index[line_start..line_start + skip].fill(aligned.len());
+            // Copy the line's content and its newline
+            let content = line_start + skip..(line_end + 1).min(self.text.len());
+            for (slot, c) in new_index[content.clone()].iter_mut().zip(&self.text[content]) {
+                *slot = aligned.len();
+                aligned.push(c.clone());
+            }
+            line_start = line_end + 1;
+        }
+        new_index[self.text.len()] = aligned.len();
+
+        self.cursor_pos = new_index[self.cursor_pos.min(self.text.len())];
+        self.goal_column = None;
+        self.text = aligned;
+        self.search_matches.clear();
+        self.clear_selection();
+        self.dirty = true;
+    }
+
+    /// Reverse the order of lines, keeping each line's characters and styles
+    pub fn reverse_lines(&mut self) {
+        self.reorder_lines(|lines| lines.reverse());
+    }
+
+    /// Sort lines by their plain text (stable), optionally ignoring case
+    pub fn sort_lines(&mut self, case_insensitive: bool) {
+        self.reorder_lines(|lines| {
+            lines.sort_by_cached_key(|line| {
+                let text: String = line.iter().map(|c| c.ch).collect();
+                if case_insensitive {
+                    text.to_lowercase()
+                } else {
+                    text
+                }
+            })
+        });
+    }
+
+    /// Rearrange whole lines. Newlines stay where they were, and a trailing empty line
+    /// stays last. The cursor follows its line.
+    fn reorder_lines(&mut self, reorder: impl FnOnce(&mut Vec<&[StyledChar]>)) {
+        let mut lines: Vec<&[StyledChar]> = self.text.split(|c| c.ch == '\n').collect();
+        let newlines: Vec<StyledChar> = self.text.iter().filter(|c| c.ch == '\n').cloned().collect();
+        if self.text.last().is_some_and(|c| c.ch == '\n') {
+            lines.pop();
+        }
+
+        // Remember which line (by address) and column the cursor is in
+        let (cursor_line, cursor_col) = {
+            let line = self.cursor_line();
+            let start = self.text[..self.cursor_pos.min(self.text.len())]
+                .iter()
+                .rposition(|c| c.ch == '\n')
+                .map_or(0, |p| p + 1);
+            (line, self.cursor_pos - start)
+        };
+        let cursor_ptr = lines.get(cursor_line).map(|line| line.as_ptr());
+
+        reorder(&mut lines);
+
+        let mut reordered = Vec::with_capacity(self.text.len());
+        let mut cursor_pos = None;
+        for (i, line) in lines.iter().enumerate() {
+            if Some(line.as_ptr()) == cursor_ptr && cursor_pos.is_none() {
+                cursor_pos = Some(reordered.len() + cursor_col.min(line.len()));
+            }
+            reordered.extend_from_slice(line);
+            if let Some(newline) = newlines.get(i) {
+                reordered.push(newline.clone());
+            }
+        }
+
+        self.cursor_pos = cursor_pos.unwrap_or(reordered.len());
+        self.goal_column = None;
+        self.text = reordered;
+        self.search_matches.clear();
+        self.clear_selection();
+        self.dirty = true;
+    }
+
+    /// Apply current style to every `period`-th character of the selection (or whole buffer),
+    /// starting at `offset`. Newlines are skipped when counting so stripes line up across lines.
+    pub fn apply_style_striped(&mut self, period: usize, offset: usize) {
+        if period == 0 || self.text.is_empty() {
+            return;
+        }
+        let style = self.current_style();
+        let (start, end) = self
+            .selection
+            .map(|(start, end)| (start, end.min(self.text.len() - 1)))
+            .unwrap_or((0, self.text.len() - 1));
+
+        let mut count = 0;
+        for styled_char in &mut self.text[start..=end] {
+            if styled_char.ch == '\n' {
+                continue;
+            }
+            if count % period == offset % period {
+                styled_char.style = style.clone();
+            }
+            count += 1;
+        }
+        self.dirty = true;
+    }
+
+    /// Replace the buffer with a recovered one
+    pub fn accept_recovery(&mut self) {
+        if let Some(text) = self.pending_recovery.take() {
+            self.text = text;
+            self.cursor_pos = self.text.len();
+            self.clear_selection();
+            self.dirty = true;
+        }
+    }
+
+    /// Open a text prompt in the status bar
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        self.prompt = Some(Prompt {
+            kind,
+            input: String::new(),
+        });
+    }
+
+    /// Toggle brush mode, painting the character under the cursor when turned on
+    pub fn toggle_brush(&mut self) {
+        self.brush_active = !self.brush_active;
+        self.brush_paint();
+    }
+
+    /// Paint the current style onto the character under the cursor if the brush is active
+    fn brush_paint(&mut self) {
+        if self.brush_active && self.cursor_pos < self.text.len() {
+            self.restyle_cursor_char();
+            self.dirty = true;
+        }
+    }
+
+    /// How `attr` is set across the selection's visible chars; None without a selection
+    pub fn selection_state(&self, attr: impl Fn(&CharStyle) -> bool) -> Option<TriState> {
+        let (start, end) = self.selection?;
+        let chars = self.text.get(start..=end.min(self.text.len().checked_sub(1)?))?;
+        let (mut on, mut off) = (false, false);
+        for styled_char in chars.iter().filter(|c| c.ch != '\n') {
+            if attr(&styled_char.style) {
+                on = true;
+            } else {
+                off = true;
+            }
+        }
+        Some(match (on, off) {
+            (true, true) => TriState::Mixed,
+            (true, false) => TriState::AllOn,
+            _ => TriState::AllOff,
+        })
+    }
+
+    /// Whether the panels should show `attr` as mixed: it differs across the selection
+    /// and hasn't been set for it yet
+    pub fn is_mixed(&self, touched: bool, attr: impl Fn(&CharStyle) -> bool) -> bool {
+        !touched && self.selection_state(attr) == Some(TriState::Mixed)
+    }
+
+    /// Toggled value of an attribute; the first toggle of a mixed one turns it on everywhere
+    fn toggled(&self, current: bool, touched: bool, attr: impl Fn(&CharStyle) -> bool) -> bool {
+        self.is_mixed(touched, attr) || !current
+    }
+
+    /// Toggle bold
+    pub fn toggle_bold(&mut self) {
+        self.current_bold = self.toggled(self.current_bold, self.touched.bold, |s| s.bold);
+        self.touched.bold = true;
+        self.apply_style();
+    }
+
+    /// Toggle italic
+    pub fn toggle_italic(&mut self) {
+        self.current_italic = self.toggled(self.current_italic, self.touched.italic, |s| s.italic);
+        self.touched.italic = true;
+        self.apply_style();
+    }
+
+    /// Toggle single underline (a double underline is replaced by none)
+    pub fn toggle_underline(&mut self) {
+        self.set_underline(UnderlineStyle::Single);
+    }
+
+    /// Toggle double underline
+    pub fn toggle_double_underline(&mut self) {
+        self.set_underline(UnderlineStyle::Double);
+    }
+
+    fn set_underline(&mut self, underline: UnderlineStyle) {
+        let mixed = self.is_mixed(self.touched.underline, |s| s.underline == underline);
+        self.current_underline = if !mixed && self.current_underline == underline { UnderlineStyle::None } else { underline };
+        self.touched.underline = true;
+        self.apply_style();
+    }
+
+    /// Toggle overline
+    pub fn toggle_overline(&mut self) {
+        self.current_overline = self.toggled(self.current_overline, self.touched.overline, |s| s.overline);
+        self.touched.overline = true;
+        self.apply_style();
+    }
+
+    /// Toggle strikethrough
+    pub fn toggle_strikethrough(&mut self) {
+        self.current_strikethrough =
+            self.toggled(self.current_strikethrough, self.touched.strikethrough, |s| s.strikethrough);
+        self.touched.strikethrough = true;
+        self.apply_style();
+    }
+
+    /// Toggle blink
+    pub fn toggle_blink(&mut self) {
+        self.current_blink = self.toggled(self.current_blink, self.touched.blink, |s| s.blink);
+        self.touched.blink = true;
+        self.apply_style();
+    }
+
+    /// Toggle reverse video
+    pub fn toggle_reverse(&mut self) {
+        self.current_reverse = self.toggled(self.current_reverse, self.touched.reverse, |s| s.reverse);
+        self.touched.reverse = true;
+        self.apply_style();
+    }
+
+    /// Toggle conceal
+    pub fn toggle_hidden(&mut self) {
+        self.current_hidden = self.toggled(self.current_hidden, self.touched.hidden, |s| s.hidden);
+        self.touched.hidden = true;
+        self.apply_style();
+    }
+
+    /// Cycle dim level
+    pub fn cycle_dim(&mut self) {
+        self.current_dim = (self.current_dim + 1) % 4;
+        self.touched.dim = true;
+        self.apply_style();
+    }
+
+    /// Toggle selection highlight mode
+    pub fn toggle_selection_highlight_mode(&mut self) {
+        self.selection_highlight_mode = match self.selection_highlight_mode {
+            SelectionHighlightMode::Reversed => SelectionHighlightMode::Underline,
+            SelectionHighlightMode::Underline => SelectionHighlightMode::Reversed,
+        };
+    }
+
+    /// Save the current style under `name`, replacing any preset of that name
+    pub fn save_preset(&mut self, name: &str) {
+        self.presets.insert(name.to_string(), self.current_style());
+    }
+
+    /// Make preset `name` the current style and apply it to the selection or cursor char.
+    /// False when there's no such preset.
+    pub fn apply_preset(&mut self, name: &str) -> bool {
+        let Some(style) = self.presets.get(name).cloned() else {
+            return false;
+        };
+        self.set_current_style(&style);
+        self.touched = TouchedFields::all();
+        self.apply_style();
+        true
+    }
+
+    /// Preset names in the order the overlay lists them
+    pub fn preset_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.presets.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Copy the style at the cursor for the format painter; false past the end of the text
+    pub fn yank_style(&mut self) -> bool {
+        match self.text.get(self.cursor_pos) {
+            Some(c) => {
+                self.stored_style = Some(c.style.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Paint the copied style over the selection or the character at the cursor, leaving
+    /// the current style alone. Returns how many characters were painted, or None when
+    /// nothing was copied.
+    pub fn paint_style(&mut self) -> Option<usize> {
+        let style = self.stored_style.clone()?;
+        let range = match self.selection {
+            Some((start, end)) => start..(end + 1).min(self.text.len()),
+            None if self.cursor_pos < self.text.len() => self.cursor_pos..self.cursor_pos + 1,
+            None => return Some(0),
+        };
+        let count = range.len();
+        for c in &mut self.text[range] {
+            c.style = style.clone();
+        }
+        if count > 0 {
+            self.dirty = true;
+        }
+        Some(count)
+    }
+
+    /// Load style from character at cursor position into current settings
+    pub fn load_style_from_cursor(&mut self) {
+        if self.cursor_pos < self.text.len() {
+            let style = self.text[self.cursor_pos].style.clone();
+            self.set_current_style(&style);
+        }
+    }
+
+    /// Make `style` the current style, with nothing touched yet
+    pub fn set_current_style(&mut self, style: &CharStyle) {
+        self.current_fg = style.fg;
+        self.current_bg = style.bg;
+        self.current_bold = style.bold;
+        self.current_italic = style.italic;
+        self.current_underline = style.underline;
+        self.current_overline = style.overline;
+        self.current_strikethrough = style.strikethrough;
+        self.current_blink = style.blink;
+        self.current_reverse = style.reverse;
+        self.current_hidden = style.hidden;
+        self.current_dim = style.dim_level;
+        self.touched = TouchedFields::default();
+
+        // Update color picker indices
+        self.fg_color_index = self.palette.color_index_from_color(style.fg);
+        self.bg_color_index = self.palette.color_index_from_color(style.bg);
+    }
+
+    /// Grow (positive) or shrink (negative) the editor at the expense of the controls,
+    /// within what the layout can show so every press moves the split
+    pub fn resize_editor(&mut self, delta: i16) {
+        let (min, max) = self.editor_rows_bounds;
+        self.editor_extra_rows = (self.editor_extra_rows.clamp(min, max) + delta).clamp(min, max);
+    }
+
+    /// Focus `panel`, remembering the previously focused one
+    pub fn focus_panel(&mut self, panel: Panel) {
+        if panel != self.active_panel {
+            self.previous_panel = self.active_panel;
+            self.active_panel = panel;
+        }
+    }
+
+    /// Swap focus between the current and the previously focused panel
+    pub fn toggle_last_panel(&mut self) {
+        self.focus_panel(self.previous_panel);
+    }
+
+    /// Start truecolor entry for the foreground or background, from the current color
+    pub fn open_rgb_entry(&mut self, is_foreground: bool) {
+        let (current, fallback) = if is_foreground {
+            (self.current_fg, (255, 255, 255))
+        } else {
+            (self.current_bg, (0, 0, 0))
+        };
+        let (r, g, b) = to_rgb(current).unwrap_or(fallback);
+        self.rgb_entry = Some(RgbEntry {
+            is_foreground,
+            rgb: [r, g, b],
+            typed: String::new(),
+            channel: 0,
+        });
+    }
+
+    /// Close truecolor entry, making its color current. Returns the color, or None when
+    /// no entry was open.
+    pub fn commit_rgb_entry(&mut self) -> Option<Color> {
+        let entry = self.rgb_entry.take()?;
+        let color = entry.color();
+        if entry.is_foreground {
+            self.current_fg = color;
+            self.touched.fg = true;
+        } else {
+            self.current_bg = color;
+            self.touched.bg = true;
+        }
+        Some(color)
+    }
+
+    /// Brighten (positive `delta`) or darken the current foreground or background color,
+    /// turning a named color into its RGB value first. A default background counts as black,
+    /// as in `contrast_ratio`. Returns the new lightness
+    pub fn adjust_lightness(&mut self, is_foreground: bool, delta: f32) -> f32 {
+        let (r, g, b) = if is_foreground {
+            resolve_named(self.current_fg)
+        } else {
+            to_rgb(self.current_bg).unwrap_or((0, 0, 0))
+        };
+        let (h, s, l) = rgb_to_hsl(Color::Rgb(r, g, b)).unwrap_or((0.0, 0.0, 0.0));
+        let l = (l + delta).clamp(0.0, 1.0);
+        let color = hsl_to_rgb(h, s, l);
+        if is_foreground {
+            self.current_fg = color;
+            self.touched.fg = true;
+        } else {
+            self.current_bg = color;
+            self.touched.bg = true;
+        }
+        l
+    }
+
+    /// Change the foreground's lightness until it reaches `MIN_CONTRAST` against the
+    /// background, trying away from the background first. Returns the final ratio
+    pub fn auto_contrast(&mut self) -> f32 {
+        let ratio = contrast_ratio(self.current_fg, self.current_bg);
+        if ratio >= MIN_CONTRAST {
+            return ratio;
+        }
+        let original = self.current_fg;
+        // The terminal's default background is assumed dark
+        let (r, g, b) = to_rgb(self.current_bg).unwrap_or((0, 0, 0));
+        let bg_lightness = rgb_to_hsl(Color::Rgb(r, g, b)).map_or(0.0, |(_, _, l)| l);
+        let first = if bg_lightness < 0.5 { LIGHTNESS_STEP } else { -LIGHTNESS_STEP };
+
+        let mut best = (ratio, original);
+        for step in [first, -first] {
+            self.current_fg = original;
+            for _ in 0..=(1.0 / LIGHTNESS_STEP) as usize {
+                self.adjust_lightness(true, step);
+                let ratio = contrast_ratio(self.current_fg, self.current_bg);
+                if ratio > best.0 {
+                    best = (ratio, self.current_fg);
+                }
+                if ratio >= MIN_CONTRAST {
+                    return ratio;
+                }
+            }
+        }
+        self.current_fg = best.1;
+        best.0
+    }
+
+    /// Swap the current foreground and background colors (existing text is untouched)
+    pub fn swap_current_colors(&mut self) {
+        std::mem::swap(&mut self.current_fg, &mut self.current_bg);
+        self.touched.fg = true;
+        self.touched.bg = true;
+        self.fg_color_index = self.palette.color_index_from_color(self.current_fg);
+        self.bg_color_index = self.palette.color_index_from_color(self.current_bg);
+    }
+
+    /// Swap the current colors, and each selected char's own foreground and background
+    /// (or the cursor char's). Returns how many chars were swapped
+    pub fn swap_fg_bg(&mut self) -> usize {
+        self.swap_current_colors();
+        let range = match self.selection {
+            Some((start, end)) if !self.text.is_empty() => start..=end.min(self.text.len() - 1),
+            None if self.cursor_pos < self.text.len() => self.cursor_pos..=self.cursor_pos,
+            _ => return 0,
+        };
+        let mut count = 0;
+        for styled_char in &mut self.text[range] {
+            let style = &mut styled_char.style;
+            std::mem::swap(&mut style.fg, &mut style.bg);
+            count += 1;
+        }
+        self.dirty = true;
+        count
+    }
+
+    /// The selected chars, or the whole buffer when nothing is selected
+    fn selection_or_buffer(&mut self) -> &mut [StyledChar] {
+        match self.selection {
+            Some((start, end)) if !self.text.is_empty() => {
+                let end = end.min(self.text.len() - 1);
+                &mut self.text[start..=end]
+            }
+            _ => &mut self.text,
+        }
+    }
+
+    /// Set foreground and background back to the default on the selection, or the whole
+    /// buffer. Returns how many chars changed
+    pub fn strip_colors(&mut self) -> usize {
+        let mut changed = 0;
+        for styled_char in self.selection_or_buffer() {
+            let style = &mut styled_char.style;
+            if (style.fg, style.bg) != (Color::Reset, Color::Reset) {
+                style.fg = Color::Reset;
+                style.bg = Color::Reset;
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.dirty = true;
+        }
+        changed
+    }
+
+    /// Remove every attribute but the colors (and links) from the selection, or the whole
+    /// buffer. Returns how many chars changed
+    pub fn clear_formatting(&mut self) -> usize {
+        let mut changed = 0;
+        for styled_char in self.selection_or_buffer() {
+            let cleared = CharStyle {
+                fg: styled_char.style.fg,
+                bg: styled_char.style.bg,
+                link: styled_char.style.link.clone(),
+                ..CharStyle::default()
+            };
+            if cleared != styled_char.style {
+                changed += 1;
+            }
+            styled_char.style = cleared;
+        }
+        if changed > 0 {
+            self.dirty = true;
+        }
+        changed
+    }
+
+    /// Reset current style to defaults
+    pub fn reset_style(&mut self) {
+        self.current_fg = Color::Reset;
+        self.current_bg = Color::Reset;
+        self.current_bold = false;
+        self.current_italic = false;
+        self.current_underline = UnderlineStyle::None;
+        self.current_overline = false;
+        self.current_strikethrough = false;
+        self.current_blink = false;
+        self.current_reverse = false;
+        self.current_hidden = false;
+        self.current_dim = 0;
+        self.touched = TouchedFields::default();
+        self.fg_color_index = 0; // None/Reset
+        self.bg_color_index = 0; // None/Reset
     }
 
     /// Set status message
@@ -449,6 +1936,93 @@ impl App {
         self.status_message = None;
     }
 
+    /// The buffer's text without styling
+    pub fn plain_text(&self) -> String {
+        self.text.iter().map(|c| c.ch).collect()
+    }
+
+    /// The selected characters' text without styling, if there is a selection
+    pub fn selected_plain_text(&self) -> Option<String> {
+        let (start, end) = self.selection?;
+        if self.text.is_empty() {
+            return Some(String::new());
+        }
+        let end = end.min(self.text.len() - 1);
+        Some(self.text[start..=end].iter().map(|c| c.ch).collect())
+    }
+
+    /// The rectangle spanned by the selection's two ends, one line of characters per row.
+    /// Lines that end before the rectangle's right edge are cut short rather than padded.
+    pub fn cropped_region(&self) -> Vec<StyledChar> {
+        let Some((start, end)) = self.selection else {
+            return Vec::new();
+        };
+        let line_col = |pos: usize| {
+            let line_start = self.get_line_boundaries(pos).0;
+            let line = self.text[..line_start].iter().filter(|c| c.ch == '\n').count();
+            (line, pos - line_start)
+        };
+        let (first_line, start_col) = line_col(start.min(self.text.len()));
+        let (last_line, end_col) = line_col(end.min(self.text.len()));
+        let (left, right) = (start_col.min(end_col), start_col.max(end_col));
+
+        let mut region = Vec::new();
+        for (i, line) in self
+            .text
+            .split(|c| c.ch == '\n')
+            .enumerate()
+            .take(last_line + 1)
+            .skip(first_line)
+        {
+            if i > first_line {
+                region.push(StyledChar::new('\n'));
+            }
+            region.extend(line.iter().skip(left).take(right + 1 - left).cloned());
+        }
+        region
+    }
+
+    /// Selected characters with their styles
+    fn selected_chars(&self) -> Vec<StyledChar> {
+        match self.selection {
+            Some((start, end)) if start < self.text.len() => {
+                self.text[start..=end.min(self.text.len() - 1)].to_vec()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Copy the selection into the yank buffer, replacing its contents
+    pub fn yank(&mut self) -> usize {
+        self.yank_buffer = self.selected_chars();
+        self.clear_selection();
+        self.yank_buffer.len()
+    }
+
+    /// Add the selection to the end of the yank buffer
+    pub fn yank_append(&mut self) -> usize {
+        let chars = self.selected_chars();
+        self.yank_buffer.extend(chars);
+        self.clear_selection();
+        self.yank_buffer.len()
+    }
+
+    /// Insert the yank buffer at the cursor
+    pub fn paste(&mut self) {
+        self.insert_chars(self.yank_buffer.clone());
+    }
+
+    /// Number of selected characters and lines spanned by the selection
+    pub fn selection_stats(&self) -> Option<(usize, usize)> {
+        let (start, end) = self.selection?;
+        let end = end.min(self.text.len().saturating_sub(1));
+        if self.text.is_empty() || start > end {
+            return Some((0, 1));
+        }
+        let newlines = self.text[start..end].iter().filter(|c| c.ch == '\n').count();
+        Some((end - start + 1, newlines + 1))
+    }
+
     /// Check if a position is within the current selection
     pub fn is_selected(&self, pos: usize) -> bool {
         if let Some((start, end)) = self.selection {
@@ -458,3 +2032,1249 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::to_rgb;
+
+    #[test]
+    fn test_from_text() {
+        let app = App::from_text("Hi\nyo");
+        assert_eq!(app.text.len(), 5);
+        assert_eq!(app.text[2].ch, '\n');
+        assert_eq!(app.cursor_pos, 5);
+        assert!(app.text.iter().all(|c| c.style == CharStyle::default()));
+        assert!(app.selection.is_none());
+    }
+
+    #[test]
+    fn test_from_text_empty() {
+        let app = App::from_text("");
+        assert!(app.text.is_empty());
+        assert_eq!(app.cursor_pos, 0);
+    }
+
+    #[test]
+    fn test_from_styled() {
+        let style = CharStyle {
+            fg: Color::Red,
+            ..CharStyle::default()
+        };
+        let app = App::from_styled(vec![
+            StyledChar::with_style('A', style.clone()),
+            StyledChar::new('B'),
+        ]);
+        assert_eq!(app.cursor_pos, 2);
+        assert_eq!(app.text[0].style, style);
+    }
+
+    #[test]
+    fn test_from_text_matches_insert_char() {
+        let mut typed = App::new();
+        for ch in "ab".chars() {
+            typed.insert_char(ch);
+        }
+        let preset = App::from_text("ab");
+        assert_eq!(typed.cursor_pos, preset.cursor_pos);
+        assert_eq!(typed.text.len(), preset.text.len());
+    }
+
+    #[test]
+    fn test_swap_current_colors() {
+        let mut app = App::from_text("ab");
+        app.current_fg = Color::Red;
+        app.current_bg = Color::Blue;
+        app.fg_color_index = 2;
+        app.bg_color_index = 5;
+        app.swap_current_colors();
+        assert_eq!(app.current_fg, Color::Blue);
+        assert_eq!(app.current_bg, Color::Red);
+        assert_eq!(app.fg_color_index, 5);
+        assert_eq!(app.bg_color_index, 2);
+        // Existing text keeps its style
+        assert_eq!(app.text[0].style, CharStyle::default());
+    }
+
+    #[test]
+    fn test_selection_stats() {
+        let mut app = App::from_text("abc\ndef");
+        assert_eq!(app.selection_stats(), None);
+
+        app.cursor_pos = 0;
+        app.start_selection();
+        app.move_right();
+        app.move_right();
+        assert_eq!(app.selection_stats(), Some((3, 1)));
+
+        // Extend across the newline onto the second line
+        app.move_down();
+        assert_eq!(app.selection, Some((0, 6)));
+        assert_eq!(app.selection_stats(), Some((7, 2)));
+    }
+
+    #[test]
+    fn test_selection_stats_clamps_to_text_end() {
+        let mut app = App::from_text("ab");
+        app.start_selection();
+        app.move_to_start();
+        assert_eq!(app.selection, Some((0, 2)));
+        assert_eq!(app.selection_stats(), Some((2, 1)));
+    }
+
+    #[test]
+    fn test_brush_paints_while_moving() {
+        let mut app = App::from_text("abcde");
+        app.cursor_pos = 0;
+        app.current_fg = Color::Green;
+        app.toggle_brush();
+        app.move_right();
+        app.move_right();
+        app.move_right();
+        for i in 0..4 {
+            assert_eq!(app.text[i].style.fg, Color::Green);
+        }
+        assert_eq!(app.text[4].style.fg, Color::Reset);
+
+        app.toggle_brush();
+        app.move_right();
+        assert_eq!(app.text[4].style.fg, Color::Reset);
+    }
+
+    #[test]
+    fn test_brush_paints_vertically() {
+        let mut app = App::from_text("ab\ncd");
+        app.cursor_pos = 1;
+        app.current_bold = true;
+        app.brush_active = true;
+        app.move_down();
+        assert_eq!(app.cursor_pos, 4);
+        assert!(app.text[4].style.bold);
+        assert!(!app.text[1].style.bold);
+    }
+
+    #[test]
+    fn test_apply_style_keeps_hyperlink() {
+        let mut app = App::from_text("ab");
+        app.text[0].style.link = Some("https://example.com".to_string());
+        app.cursor_pos = 0;
+        app.current_bold = true;
+        app.apply_style();
+        assert!(app.text[0].style.bold);
+        assert_eq!(app.text[0].style.link.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn test_apply_style_striped() {
+        let mut app = App::from_text("abcdef");
+        app.current_fg = Color::Red;
+        app.apply_style_striped(2, 0);
+        for i in [0, 2, 4] {
+            assert_eq!(app.text[i].style.fg, Color::Red);
+        }
+        for i in [1, 3, 5] {
+            assert_eq!(app.text[i].style.fg, Color::Reset);
+        }
+    }
+
+    #[test]
+    fn test_apply_style_striped_skips_newlines() {
+        let mut app = App::from_text("ab\ncd");
+        app.current_bold = true;
+        app.apply_style_striped(2, 1);
+        let bold: Vec<bool> = app.text.iter().map(|c| c.style.bold).collect();
+        assert_eq!(bold, vec![false, true, false, false, true]);
+    }
+
+    #[test]
+    fn test_apply_style_striped_selection_only() {
+        let mut app = App::from_text("abcdef");
+        app.current_fg = Color::Blue;
+        app.selection = Some((2, 4));
+        app.apply_style_striped(2, 0);
+        let blue: Vec<bool> = app.text.iter().map(|c| c.style.fg == Color::Blue).collect();
+        assert_eq!(blue, vec![false, false, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_selected_plain_text() {
+        let mut app = App::from_text("Say Hello\nworld");
+        assert_eq!(app.selected_plain_text(), None);
+        assert_eq!(app.plain_text(), "Say Hello\nworld");
+
+        app.selection = Some((4, 8));
+        assert_eq!(app.selected_plain_text().as_deref(), Some("Hello"));
+
+        app.selection = Some((6, 20));
+        assert_eq!(app.selected_plain_text().as_deref(), Some("llo\nworld"));
+    }
+
+    #[test]
+    fn test_ensure_cursor_visible() {
+        let text: String = (0..20).map(|i| format!("{}\n", i)).collect();
+        let mut app = App::from_text(&text);
+        assert_eq!(app.cursor_line(), 20);
+
+        // Cursor at the end after an import scrolls down to show it
+        app.ensure_cursor_visible(5);
+        assert_eq!(app.scroll_offset, 16);
+
+        // Moving within the viewport keeps the offset
+        app.move_up();
+        app.ensure_cursor_visible(5);
+        assert_eq!(app.scroll_offset, 16);
+
+        // Jumping to the top scrolls back up
+        app.move_to_start();
+        app.ensure_cursor_visible(5);
+        assert_eq!(app.scroll_offset, 0);
+    }
+
+    #[test]
+    fn test_ensure_cursor_visible_zero_height() {
+        let mut app = App::from_text("a\nb\nc");
+        app.ensure_cursor_visible(0);
+        assert_eq!(app.scroll_offset, 2);
+    }
+
+    #[test]
+    fn test_cursor_wrap_crosses_line_boundary() {
+        let mut app = App::from_text("ab\ncd");
+        app.cursor_pos = 2; // on the newline
+        app.move_right();
+        assert_eq!(app.cursor_pos, 3);
+        app.move_left();
+        app.move_left();
+        assert_eq!(app.cursor_pos, 1);
+    }
+
+    #[test]
+    fn test_cursor_no_wrap_stops_at_line_ends() {
+        let mut app = App::from_text("ab\ncd");
+        app.cursor_wrap = false;
+        app.cursor_pos = 2;
+        app.move_right();
+        assert_eq!(app.cursor_pos, 2);
+
+        app.cursor_pos = 3; // start of second line
+        app.move_left();
+        assert_eq!(app.cursor_pos, 3);
+
+        // Selection obeys the same rule
+        app.start_selection();
+        app.move_left();
+        assert_eq!(app.selection, Some((3, 3)));
+        app.move_right();
+        app.move_right();
+        app.move_right();
+        assert_eq!(app.selection, Some((3, 5)));
+    }
+
+    #[test]
+    fn test_delete_selection_mid_buffer() {
+        let mut app = App::from_text("hello world");
+        app.cursor_pos = 2;
+        app.start_selection();
+        app.move_right();
+        app.move_right();
+        app.delete_selection();
+        assert_eq!(app.plain_text(), "he world");
+        assert_eq!(app.cursor_pos, 2);
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.selection.is_none());
+    }
+
+    #[test]
+    fn test_delete_selection_at_buffer_end_clamps_cursor() {
+        let mut app = App::from_text("hello");
+        app.cursor_pos = 3;
+        app.start_selection();
+        app.move_to_end();
+        app.delete_selection();
+        assert_eq!(app.plain_text(), "hel");
+        assert_eq!(app.cursor_pos, 3);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_style_all_matches_restyles_only_matches() {
+        let mut app = App::from_text("axbxc");
+        assert_eq!(app.search("x"), 2);
+        assert_eq!(app.cursor_pos, 1);
+
+        app.current_bold = true;
+        assert_eq!(app.style_all_matches(), 2);
+        let bold: Vec<bool> = app.text.iter().map(|c| c.style.bold).collect();
+        assert_eq!(bold, vec![false, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_search_matches_do_not_overlap_and_clear_on_edit() {
+        let mut app = App::from_text("aaaa");
+        assert_eq!(app.search("aa"), 2);
+        assert_eq!(app.search_matches, vec![(0, 1), (2, 3)]);
+
+        app.insert_char('b');
+        assert!(app.search_matches.is_empty());
+    }
+
+    #[test]
+    fn test_yank_append_accumulates_styled_segments() {
+        let mut app = App::from_text("abcdef");
+        app.text[0].style.bold = true;
+        app.text[4].style.italic = true;
+
+        // Yank "ab", then append "ef"
+        app.cursor_pos = 0;
+        app.start_selection();
+        app.move_right();
+        assert_eq!(app.yank(), 2);
+        app.cursor_pos = 4;
+        app.start_selection();
+        app.move_right();
+        assert_eq!(app.yank_append(), 4);
+
+        app.text.clear();
+        app.cursor_pos = 0;
+        app.paste();
+        assert_eq!(app.plain_text(), "abef");
+        let bold: Vec<bool> = app.text.iter().map(|c| c.style.bold).collect();
+        let italic: Vec<bool> = app.text.iter().map(|c| c.style.italic).collect();
+        assert_eq!(bold, vec![true, false, false, false]);
+        assert_eq!(italic, vec![false, false, true, false]);
+    }
+
+    #[test]
+    fn test_merge_apply_keeps_untouched_colors() {
+        let mut app = App::from_text("abc");
+        for c in &mut app.text {
+            c.style.fg = Color::Red;
+            c.style.bg = Color::Blue;
+        }
+        app.reset_style();
+        app.cursor_pos = 0;
+        app.text[0].style.italic = true;
+
+        // Only bold was touched; a plain apply would wipe the colors
+        app.start_selection();
+        app.current_bold = true;
+        app.touched.bold = true;
+        app.move_right();
+        app.move_right();
+        app.apply_style_merge();
+
+        assert!(app.text.iter().all(|c| c.style.bold));
+        assert!(app.text.iter().all(|c| c.style.fg == Color::Red && c.style.bg == Color::Blue));
+        assert!(app.text[0].style.italic);
+    }
+
+    #[test]
+    fn test_touched_fields_track_toggles_and_reset() {
+        let mut app = App::from_text("a");
+        app.toggle_bold();
+        app.swap_current_colors();
+        assert_eq!(
+            app.touched,
+            TouchedFields {
+                fg: true,
+                bg: true,
+                bold: true,
+                ..TouchedFields::default()
+            }
+        );
+        app.reset_style();
+        assert_eq!(app.touched, TouchedFields::default());
+    }
+
+    #[test]
+    fn test_align_lines_center() {
+        let mut app = App::from_text("ab\n  abcd\nabcdefghijkl");
+        app.text[5].style.bold = true;
+        app.cursor_pos = 7; // on 'c' of the second line
+        app.align_lines(10, Alignment::Center);
+
+        assert_eq!(app.plain_text(), "    ab\n   abcd\nabcdefghijkl");
+        // Styles travel with their characters, and so does the cursor
+        assert!(app.text[10].style.bold);
+        assert_eq!(app.cursor_pos, 12);
+        assert_eq!(app.text[app.cursor_pos].ch, 'c');
+    }
+
+    #[test]
+    fn test_align_lines_right_and_left() {
+        let mut app = App::from_text("ab\nabc");
+        app.align_lines(5, Alignment::Right);
+        assert_eq!(app.plain_text(), "   ab\n  abc");
+        assert_eq!(app.cursor_pos, app.text.len());
+
+        app.align_lines(5, Alignment::Left);
+        assert_eq!(app.plain_text(), "ab\nabc");
+    }
+
+    #[test]
+    fn test_align_lines_measures_wide_chars() {
+        let mut app = App::from_text("日本");
+        app.align_lines(8, Alignment::Right);
+        assert_eq!(app.plain_text(), "    日本");
+    }
+
+    #[test]
+    fn test_reverse_lines_keeps_styles_and_cursor() {
+        let mut app = App::from_text("one\ntwo\nthree");
+        app.text[4].style.bold = true; // 't' of "two"
+        app.cursor_pos = 9; // 'h' of "three"
+        app.reverse_lines();
+
+        assert_eq!(app.plain_text(), "three\ntwo\none");
+        assert!(app.text[6].style.bold);
+        assert_eq!(app.cursor_pos, 1);
+    }
+
+    #[test]
+    fn test_sort_lines_with_styles_following() {
+        let mut app = App::from_text("banana\napple\ncherry\n");
+        app.text[7].style.fg = Color::Red; // 'a' of "apple"
+        app.sort_lines(true);
+
+        assert_eq!(app.plain_text(), "apple\nbanana\ncherry\n");
+        assert_eq!(app.text[0].style.fg, Color::Red);
+        assert_eq!(app.text[6].style.fg, Color::Reset);
+        // Trailing newline keeps the cursor on the empty last line
+        assert_eq!(app.cursor_pos, app.text.len());
+    }
+
+    #[test]
+    fn test_sort_lines_case_sensitivity() {
+        let mut app = App::from_text("b\nA\na");
+        app.sort_lines(false);
+        assert_eq!(app.plain_text(), "A\na\nb");
+
+        let mut app = App::from_text("b\na\nA");
+        app.sort_lines(true);
+        assert_eq!(app.plain_text(), "a\nA\nb");
+    }
+
+    #[test]
+    fn test_goto_line() {
+        let mut app = App::from_text("one\ntwo\nthree");
+        app.goto_line(1);
+        assert_eq!(app.cursor_pos, 0);
+        app.goto_line(2);
+        assert_eq!(app.cursor_pos, 4);
+        // Past the end clamps to the last line's start
+        app.goto_line(99);
+        assert_eq!(app.cursor_pos, 8);
+    }
+
+    #[test]
+    fn test_goto_line_extends_selection() {
+        let mut app = App::from_text("one\ntwo\nthree");
+        app.cursor_pos = 1;
+        app.start_selection();
+        app.goto_line(3);
+        assert_eq!(app.selection, Some((1, 8)));
+    }
+
+    #[test]
+    fn test_duplicate_lines() {
+        let mut app = App::from_text("same\nunique\nsame");
+        let expected: HashSet<usize> = [0, 2].into_iter().collect();
+        assert_eq!(app.duplicate_lines(DuplicateAudit::PlainText), expected);
+
+        // A style difference only counts when comparing styles
+        app.text[0].style.bold = true;
+        assert_eq!(app.duplicate_lines(DuplicateAudit::PlainText), expected);
+        assert!(app.duplicate_lines(DuplicateAudit::Styled).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_lines_skip_blank_lines() {
+        let app = App::from_text("a\n\nb\n  \n\na");
+        let expected: HashSet<usize> = [0, 5].into_iter().collect();
+        assert_eq!(app.duplicate_lines(DuplicateAudit::PlainText), expected);
+    }
+
+    #[test]
+    fn test_open_line_below() {
+        let mut app = App::from_text("one\ntwo");
+        app.cursor_pos = 1;
+        app.open_line_below();
+        assert_eq!(app.plain_text(), "one\n\ntwo");
+        assert_eq!(app.cursor_pos, 4);
+        assert_eq!(app.mode, Mode::Typing);
+
+        // On the last line
+        app.cursor_pos = 6;
+        app.open_line_below();
+        assert_eq!(app.plain_text(), "one\n\ntwo\n");
+        assert_eq!(app.cursor_pos, 9);
+    }
+
+    #[test]
+    fn test_open_line_above() {
+        let mut app = App::from_text("one\ntwo");
+        app.cursor_pos = 5;
+        app.open_line_above();
+        assert_eq!(app.plain_text(), "one\n\ntwo");
+        assert_eq!(app.cursor_pos, 4);
+        assert_eq!(app.mode, Mode::Typing);
+
+        // On the first line
+        app.cursor_pos = 2;
+        app.open_line_above();
+        assert_eq!(app.plain_text(), "\none\n\ntwo");
+        assert_eq!(app.cursor_pos, 0);
+        app.insert_char('x');
+        assert_eq!(app.plain_text(), "x\none\n\ntwo");
+    }
+
+    #[test]
+    fn test_rendered_dimensions() {
+        // Tab stops at 8, wide characters take two columns
+        let app = App::from_text("ab\tc\n漢字!\nxy\n");
+        assert_eq!(app.rendered_dimensions(), (9, 3));
+
+        let app = App::from_text("漢字漢字漢字");
+        assert_eq!(app.rendered_dimensions(), (12, 1));
+        assert_eq!(App::new().rendered_dimensions(), (0, 0));
+    }
+
+    #[test]
+    fn test_grid_mode_moves_by_cells() {
+        let mut app = App::from_text("abcdefg\nxy");
+        app.cursor_pos = 3;
+        app.toggle_grid_mode();
+        assert_eq!(app.cursor_pos, 2); // snapped to the cell start
+
+        app.move_right();
+        assert_eq!(app.cursor_pos, 4);
+        app.move_right();
+        app.move_right();
+        assert_eq!(app.cursor_pos, 7); // odd-length line stops at its end
+        app.move_left();
+        assert_eq!(app.cursor_pos, 6);
+        app.move_left();
+        assert_eq!(app.cursor_pos, 4);
+    }
+
+    #[test]
+    fn test_fill_grid_cell() {
+        let mut app = App::from_text("ab\n");
+        app.grid_mode = true;
+        app.current_bg = Color::Blue;
+        app.cursor_pos = 0;
+        app.fill_grid_cell();
+        app.fill_grid_cell();
+
+        assert_eq!(app.plain_text(), "████\n");
+        assert_eq!(app.cursor_pos, 4);
+        assert!(app.text[..4].iter().all(|c| c.style.fg == Color::Blue && c.style.bg == Color::Blue));
+    }
+
+    #[test]
+    fn test_theme_from_current_fg() {
+        let luminance = |color| {
+            let (r, g, b) = to_rgb(color).unwrap();
+            0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32
+        };
+        let mut app = App::from_text("ab\nc");
+        app.text[1].style.fg = Color::Red;
+        app.current_fg = Color::Rgb(80, 160, 240);
+
+        let (background, accent) = app.theme_from_current_fg().unwrap();
+        assert!(luminance(background) < luminance(app.current_fg));
+        assert!(luminance(accent) > luminance(app.current_fg));
+
+        assert_eq!(app.text[0].style.bg, background);
+        assert_eq!(app.text[0].style.fg, accent);
+        assert_eq!(app.text[1].style.fg, Color::Red); // explicit colors are kept
+        assert_eq!(app.text[2].style.bg, Color::Reset); // newline untouched
+        assert_eq!(app.text[3].style.bg, background);
+
+        app.current_fg = Color::Reset;
+        assert_eq!(app.theme_from_current_fg(), None);
+    }
+
+    #[test]
+    fn test_vertical_movement_through_empty_lines() {
+        let mut app = App::from_text("abc\n\n\nxyz");
+        app.cursor_pos = 2;
+
+        // Each empty line is a stop at column 0
+        app.move_down();
+        assert_eq!(app.cursor_pos, 4);
+        app.move_down();
+        assert_eq!(app.cursor_pos, 5);
+        app.move_down();
+        assert_eq!(app.cursor_pos, 8); // remembered column on the last line
+        app.move_down();
+        assert_eq!(app.cursor_pos, 8);
+
+        app.move_up();
+        assert_eq!(app.cursor_pos, 5);
+        app.move_up();
+        assert_eq!(app.cursor_pos, 4);
+        app.move_up();
+        assert_eq!(app.cursor_pos, 2);
+
+        // Horizontal movement picks a new column
+        app.move_left();
+        app.move_down();
+        assert_eq!(app.cursor_pos, 4);
+        app.move_down();
+        app.move_down();
+        assert_eq!(app.cursor_pos, 7);
+    }
+
+    #[test]
+    fn test_vertical_movement_in_newline_only_buffer() {
+        let mut app = App::from_text("\n\n\n");
+        app.cursor_pos = 0;
+        for expected in 1..=3 {
+            app.move_down();
+            assert_eq!(app.cursor_pos, expected);
+        }
+        app.move_down();
+        assert_eq!(app.cursor_pos, 3);
+        for expected in (0..3).rev() {
+            app.move_up();
+            assert_eq!(app.cursor_pos, expected);
+        }
+        app.move_up();
+        assert_eq!(app.cursor_pos, 0);
+    }
+
+    #[test]
+    fn test_move_down_onto_shorter_last_line() {
+        let mut app = App::from_text("abcdef\n\nxy");
+        app.cursor_pos = 5;
+        app.move_down();
+        assert_eq!(app.cursor_pos, 7);
+        app.move_down();
+        assert_eq!(app.cursor_pos, 10); // clamped to the end of "xy"
+    }
+
+    #[test]
+    fn test_marks_follow_edits() {
+        let mut app = App::from_text("abcdef");
+        app.marks = (Some(1), Some(4));
+        app.cursor_pos = 0;
+        app.insert_char('x');
+        assert_eq!(app.marks, (Some(2), Some(5)));
+
+        // Deleting before a mark pulls it back; deleting its character moves it to the next
+        app.cursor_pos = 2;
+        app.delete_char_forward();
+        assert_eq!(app.marks, (Some(2), Some(4)));
+        assert_eq!(app.text[4].ch, 'e');
+    }
+
+    #[test]
+    fn test_interpolate_styles_between_marks() {
+        let mut app = App::from_text("abcde");
+        app.text[0].style = CharStyle { fg: Color::Rgb(0, 0, 0), bg: Color::Red, ..Default::default() };
+        app.text[4].style = CharStyle {
+            fg: Color::Rgb(200, 100, 40),
+            bold: true,
+            dim_level: 2,
+            ..Default::default()
+        };
+        assert_eq!(app.interpolate_styles_between_marks(), None);
+
+        app.marks = (Some(4), Some(0));
+        assert_eq!(app.interpolate_styles_between_marks(), Some(5));
+
+        // Midpoint colors
+        assert_eq!(app.text[2].style.fg, Color::Rgb(100, 50, 20));
+        assert_eq!(app.text[1].style.fg, Color::Rgb(50, 25, 10));
+        assert_eq!(app.text[2].style.dim_level, 1);
+        // Booleans and the Reset background flip at the midpoint
+        assert!(!app.text[1].style.bold);
+        assert!(app.text[2].style.bold);
+        assert_eq!(app.text[1].style.bg, Color::Red);
+        assert_eq!(app.text[2].style.bg, Color::Reset);
+        // Ends keep their styles
+        assert_eq!(app.text[0].style.fg, Color::Rgb(0, 0, 0));
+        assert_eq!(app.text[4].style.fg, Color::Rgb(200, 100, 40));
+    }
+
+    #[test]
+    fn test_insert_shifts_selection() {
+        let selected = |at: usize| {
+            let mut app = App::from_text("abcdef");
+            app.selection = Some((2, 3));
+            app.selection_anchor = Some(2);
+            app.cursor_pos = at;
+            app.insert_char('x');
+            (app.selection, app.selection_anchor)
+        };
+        // Before, inside and after the selection
+        assert_eq!(selected(1), (Some((3, 4)), Some(3)));
+        assert_eq!(selected(3), (Some((2, 4)), Some(2)));
+        assert_eq!(selected(5), (Some((2, 3)), Some(2)));
+
+        let mut app = App::from_text("abcdef");
+        app.selection = Some((2, 3));
+        app.cursor_pos = 0;
+        app.insert_chars(vec![StyledChar::new('x'), StyledChar::new('y')]);
+        assert_eq!(app.selection, Some((4, 5)));
+        let selected: String = app.text[4..=5].iter().map(|c| c.ch).collect();
+        assert_eq!(selected, "cd");
+    }
+
+    #[test]
+    fn test_delete_shifts_selection() {
+        let mut app = App::from_text("abcdef");
+        app.selection = Some((2, 4));
+        app.cursor_pos = 1;
+        app.delete_char(); // before
+        assert_eq!(app.selection, Some((1, 3)));
+
+        app.cursor_pos = 2;
+        app.delete_char_forward(); // inside
+        assert_eq!(app.selection, Some((1, 2)));
+        assert_eq!(app.plain_text(), "bcef");
+
+        app.cursor_pos = 4;
+        app.delete_char(); // after
+        assert_eq!(app.selection, Some((1, 2)));
+
+        app.cursor_pos = 2;
+        app.delete_char();
+        assert_eq!(app.selection, Some((1, 1)));
+        app.cursor_pos = 2;
+        app.delete_char(); // the last selected character
+        assert_eq!(app.selection, None);
+    }
+
+    #[test]
+    fn test_insert_sequence() {
+        let mut app = App::from_text("> ");
+        app.current_fg = Color::Green;
+        app.current_bold = true;
+
+        assert_eq!(app.insert_sequence(1, 3, "{}. "), 9);
+        assert_eq!(app.plain_text(), "> 1. 2. 3. ");
+        assert_eq!(app.cursor_pos, 11);
+        let style = app.current_style();
+        assert!(app.text[2..].iter().all(|c| c.style == style));
+        assert_eq!(app.text[0].style, CharStyle::default());
+
+        assert_eq!(app.insert_sequence(3, 1, "{}"), 0);
+    }
+
+    #[test]
+    fn test_smart_home_toggles() {
+        let mut app = App::from_text("x\n  \tindented");
+        app.cursor_pos = 10;
+
+        app.move_to_line_start();
+        assert_eq!(app.cursor_pos, 5); // first non-blank
+        app.move_to_line_start();
+        assert_eq!(app.cursor_pos, 2); // column 0
+        app.move_to_line_start();
+        assert_eq!(app.cursor_pos, 5);
+
+        // Unindented and blank lines just go to column 0
+        app.cursor_pos = 1;
+        app.move_to_line_start();
+        assert_eq!(app.cursor_pos, 0);
+        app.move_to_line_start();
+        assert_eq!(app.cursor_pos, 0);
+
+        app.cursor_pos = 10;
+        app.move_to_column_zero();
+        assert_eq!(app.cursor_pos, 2);
+    }
+
+    #[test]
+    fn test_toggle_last_panel() {
+        let mut app = App::new();
+        app.focus_panel(Panel::FgColor);
+        app.toggle_last_panel();
+        assert_eq!(app.active_panel, Panel::Editor);
+        app.toggle_last_panel();
+        assert_eq!(app.active_panel, Panel::FgColor);
+
+        // Re-focusing the same panel doesn't lose the previous one
+        app.focus_panel(Panel::FgColor);
+        app.toggle_last_panel();
+        assert_eq!(app.active_panel, Panel::Editor);
+    }
+
+    #[test]
+    fn test_apply_and_advance() {
+        let mut app = App::from_text("abcd");
+        app.cursor_pos = 0;
+        app.current_fg = Color::Red;
+        app.current_bold = true;
+
+        for expected_cursor in 1..=3 {
+            assert!(app.apply_and_advance());
+            assert_eq!(app.cursor_pos, expected_cursor);
+        }
+        for c in &app.text[..3] {
+            assert_eq!(c.style.fg, Color::Red);
+            assert!(c.style.bold);
+        }
+        assert_eq!(app.text[3].style, CharStyle::default());
+
+        // The last character is styled without moving past the end
+        assert!(app.apply_and_advance());
+        assert_eq!(app.cursor_pos, 3);
+        assert_eq!(app.text[3].style.fg, Color::Red);
+
+        app.cursor_pos = 4;
+        assert!(!app.apply_and_advance());
+    }
+
+    #[test]
+    fn test_zero_width_chars_take_no_column() {
+        let mut app = App::from_text("a\u{200B}bc\nxyzw");
+        app.cursor_pos = 0;
+
+        // The zero-width space is stepped over together with 'a'
+        app.move_right();
+        assert_eq!(app.cursor_pos, 2);
+        app.move_right();
+        assert_eq!(app.get_column(), 2);
+        app.move_left();
+        app.move_left();
+        assert_eq!(app.cursor_pos, 0);
+
+        // Column 2 on the first line is column 2 on the second
+        app.cursor_pos = 3;
+        app.move_down();
+        assert_eq!(app.cursor_pos, 7);
+        app.move_up();
+        assert_eq!(app.cursor_pos, 3);
+
+        assert_eq!(app.rendered_dimensions(), (4, 2));
+    }
+
+    #[test]
+    fn test_wide_chars_keep_display_column() {
+        let mut app = App::from_text("日本x\nabcde");
+        app.cursor_pos = 2;
+        app.move_down();
+        assert_eq!(app.cursor_pos, 8);
+    }
+
+    #[test]
+    fn test_set_all_dim() {
+        let mut app = App::from_text("abc");
+        app.text[0].style.dim_level = 1;
+        app.text[1].style.fg = Color::Red;
+        app.text[1].style.bold = true;
+        app.text[2].style.dim_level = 2;
+        let before: Vec<CharStyle> = app.text.iter().map(|c| c.style.clone()).collect();
+
+        assert_eq!(app.set_all_dim(2), 2);
+        assert!(app.text.iter().all(|c| c.style.dim_level == 2));
+        assert_eq!(app.set_all_dim(0), 3);
+        for (c, old) in app.text.iter().zip(&before) {
+            assert_eq!(c.style, CharStyle { dim_level: 0, ..old.clone() });
+        }
+
+        assert_eq!(app.set_all_dim(9), 3);
+        assert_eq!(app.text[0].style.dim_level, 3);
+    }
+
+    #[test]
+    fn test_cropped_region() {
+        let mut app = App::from_text("abcdef\nghijkl\nmn\nopqrst");
+        app.text[10].style.fg = Color::Red; // 'j'
+
+        // From 'c' on the first line to 'k' on the second: two rows by three columns
+        app.selection = Some((2, 11));
+        let region = app.cropped_region();
+        let plain: String = region.iter().map(|c| c.ch).collect();
+        assert_eq!(plain, "cde\nijk");
+        assert_eq!(region[5].style.fg, Color::Red);
+
+        // The short line in the middle stops early
+        app.selection = Some((8, 20));
+        let plain: String = app.cropped_region().iter().map(|c| c.ch).collect();
+        assert_eq!(plain, "hij\nn\npqr");
+
+        // Corners can be given in either order across columns
+        app.selection = Some((4, 19));
+        let plain: String = app.cropped_region().iter().map(|c| c.ch).collect();
+        assert_eq!(plain, "cde\nijk\n\nqrs");
+    }
+
+    #[test]
+    fn test_adjust_temperature() {
+        let mut app = App::from_text("ab");
+        app.text[0].style.fg = Color::Rgb(128, 128, 128);
+        app.text[0].style.bg = Color::Rgb(250, 250, 5);
+
+        assert_eq!(app.adjust_temperature(TEMPERATURE_STEP * 2), 1);
+        let Color::Rgb(r, _, b) = app.text[0].style.fg else {
+            panic!("expected an RGB color");
+        };
+        assert!(r > 128 && b < 128);
+        assert_eq!(app.text[0].style.bg, Color::Rgb(255, 254, 0));
+        // Default colors aren't touched
+        assert_eq!(app.text[1].style, CharStyle::default());
+
+        app.adjust_temperature(-TEMPERATURE_STEP * 2);
+        assert_eq!(app.text[0].style.fg, Color::Rgb(128, 128, 128));
+    }
+
+    #[test]
+    fn test_line_movement_in_empty_buffer() {
+        let mut app = App::from_text("");
+        app.move_up();
+        app.move_down();
+        app.move_to_line_start();
+        app.move_to_line_end();
+        assert_eq!(app.cursor_pos, 0);
+    }
+
+    #[test]
+    fn test_line_movement_with_trailing_newline() {
+        let mut app = App::from_text("abc\n");
+        app.cursor_pos = 2;
+        app.move_down();
+        assert_eq!(app.cursor_pos, 4); // the empty line after the newline
+        app.move_up();
+        assert_eq!(app.cursor_pos, 2);
+        app.move_down();
+        app.move_to_line_end();
+        assert_eq!(app.cursor_pos, 4);
+        app.move_up();
+        assert_eq!(app.cursor_pos, 0); // line end on an empty line is column 0
+        app.move_to_line_end();
+        assert_eq!(app.cursor_pos, 3); // just before the newline
+    }
+
+    #[test]
+    fn test_line_movement_from_column_zero() {
+        let mut app = App::from_text("ab\ncd\nef");
+        app.cursor_pos = 3;
+        app.move_to_line_start();
+        assert_eq!(app.cursor_pos, 3);
+        app.move_up();
+        assert_eq!(app.cursor_pos, 0);
+        app.move_down();
+        app.move_down();
+        assert_eq!(app.cursor_pos, 6);
+    }
+
+    #[test]
+    fn test_line_movement_extends_selection() {
+        let mut app = App::from_text("abc\ndef");
+        app.cursor_pos = 1;
+        app.start_selection();
+        app.move_down();
+        assert_eq!(app.selection, Some((1, 5)));
+        app.move_to_line_end();
+        assert_eq!(app.selection, Some((1, 7)));
+        app.move_to_line_start();
+        assert_eq!(app.selection, Some((1, 4)));
+    }
+
+    #[test]
+    fn test_goal_column_survives_short_line() {
+        let mut app = App::new();
+        for ch in "0123456789\nab\n0123456789".chars() {
+            app.insert_char(ch);
+        }
+        app.goto_line(1);
+        for _ in 0..7 {
+            app.move_right();
+        }
+        assert_eq!(app.cursor_pos, 7);
+
+        app.move_down();
+        assert_eq!(app.cursor_pos, 13); // clamped to the end of "ab"
+        app.move_down();
+        assert_eq!(app.cursor_pos, 21);
+        app.move_up();
+        app.move_up();
+        assert_eq!(app.cursor_pos, 7);
+
+        // An edit forgets the goal column
+        app.move_down();
+        app.insert_char('x');
+        app.move_up();
+        assert_eq!(app.cursor_pos, 3);
+    }
+
+    #[test]
+    fn test_format_painter() {
+        let mut app = App::from_text("Xabcd");
+        app.text[0].style = CharStyle {
+            fg: Color::Red,
+            bold: true,
+            ..CharStyle::default()
+        };
+        assert_eq!(app.paint_style(), None);
+
+        app.cursor_pos = 0;
+        assert!(app.yank_style());
+        app.selection = Some((1, 3));
+        assert_eq!(app.paint_style(), Some(3));
+
+        let painted: Vec<bool> = app.text.iter().map(|c| c.style.bold && c.style.fg == Color::Red).collect();
+        assert_eq!(painted, vec![true, true, true, true, false]);
+        // The current style isn't touched
+        assert_eq!(app.current_fg, Color::Reset);
+        assert!(!app.current_bold);
+    }
+
+    #[test]
+    fn test_save_and_apply_preset() {
+        let mut app = App::from_text("abc");
+        app.current_fg = Color::Red;
+        app.current_bold = true;
+        app.save_preset("error");
+        app.reset_style();
+
+        assert!(!app.apply_preset("missing"));
+        app.selection = Some((0, 1));
+        assert!(app.apply_preset("error"));
+        assert!(app.text[..2].iter().all(|c| c.style.fg == Color::Red && c.style.bold));
+        assert_eq!(app.text[2].style, CharStyle::default());
+        assert!(app.current_bold);
+        assert_eq!(app.preset_names(), vec!["error".to_string()]);
+    }
+
+    #[test]
+    fn test_apply_rainbow_steps_hue_per_visible_char() {
+        let mut app = App::from_text("abc\ndef");
+        app.text[0].style.bold = true;
+        app.selection = Some((0, 6));
+        assert_eq!(app.apply_rainbow(), 6);
+
+        let fgs: Vec<Color> = app.text.iter().filter(|c| c.ch != '\n').map(|c| c.style.fg).collect();
+        // Six chars, 60 degrees apart
+        assert_eq!(
+            fgs,
+            vec![
+                Color::Rgb(255, 0, 0),
+                Color::Rgb(255, 255, 0),
+                Color::Rgb(0, 255, 0),
+                Color::Rgb(0, 255, 255),
+                Color::Rgb(0, 0, 255),
+                Color::Rgb(255, 0, 255),
+            ]
+        );
+        assert_eq!(app.text[3].style.fg, Color::Reset);
+        assert!(app.text[0].style.bold);
+    }
+
+    #[test]
+    fn test_adjust_lightness_darkens_named_color() {
+        let mut app = App::new();
+        app.current_fg = Color::LightRed;
+        let start = app.adjust_lightness(true, 0.0);
+        assert_eq!(app.current_fg, Color::Rgb(255, 0, 0));
+
+        let darker = app.adjust_lightness(true, -LIGHTNESS_STEP * 2.0);
+        assert!(darker < start - 0.09);
+        let Color::Rgb(r, g, b) = app.current_fg else {
+            panic!("expected an RGB color");
+        };
+        assert!(r < 255 && g == 0 && b == 0);
+
+        // Lightness stops at the ends instead of wrapping
+        assert_eq!(app.adjust_lightness(false, -2.0), 0.0);
+        assert_eq!(app.current_bg, Color::Rgb(0, 0, 0));
+        assert_eq!(app.adjust_lightness(false, 2.0), 1.0);
+        assert_eq!(app.current_bg, Color::Rgb(255, 255, 255));
+    }
+
+    #[test]
+    fn test_adjust_lightness_treats_default_background_as_black() {
+        let mut app = App::new();
+        assert_eq!(app.adjust_lightness(false, -LIGHTNESS_STEP), 0.0);
+        assert_eq!(app.current_bg, Color::Rgb(0, 0, 0));
+
+        app.current_bg = Color::Reset;
+        let lighter = app.adjust_lightness(false, LIGHTNESS_STEP);
+        assert!((lighter - LIGHTNESS_STEP).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_auto_contrast_fixes_black_on_black() {
+        let mut app = App::new();
+        app.current_fg = Color::Black;
+        app.current_bg = Color::Black;
+        assert!(contrast_ratio(app.current_fg, app.current_bg) < 1.01);
+
+        let ratio = app.auto_contrast();
+        assert!(ratio >= MIN_CONTRAST);
+        assert_eq!(contrast_ratio(app.current_fg, app.current_bg), ratio);
+        assert_eq!(app.current_bg, Color::Black);
+
+        // Dark text on a light background goes darker
+        app.current_fg = Color::Rgb(200, 200, 255);
+        app.current_bg = Color::White;
+        assert!(app.auto_contrast() >= MIN_CONTRAST);
+        let (_, _, l) = rgb_to_hsl(app.current_fg).unwrap();
+        assert!(l < 0.85);
+    }
+
+    #[test]
+    fn test_swap_fg_bg_per_char() {
+        let mut app = App::from_text("abc");
+        app.text[0].style.fg = Color::Red;
+        app.text[0].style.bg = Color::Blue;
+        app.text[1].style.fg = Color::Rgb(1, 2, 3);
+        app.current_fg = Color::Green;
+        app.selection = Some((0, 1));
+
+        assert_eq!(app.swap_fg_bg(), 2);
+        assert_eq!((app.text[0].style.fg, app.text[0].style.bg), (Color::Blue, Color::Red));
+        assert_eq!((app.text[1].style.fg, app.text[1].style.bg), (Color::Reset, Color::Rgb(1, 2, 3)));
+        assert_eq!(app.text[2].style, CharStyle::default());
+        assert_eq!((app.current_fg, app.current_bg), (Color::Reset, Color::Green));
+        assert_eq!(app.fg_color_index, app.palette.color_index_from_color(Color::Reset));
+        assert_eq!(app.bg_color_index, app.palette.color_index_from_color(Color::Green));
+    }
+
+    #[test]
+    fn test_strip_colors_selection_and_buffer() {
+        let mut app = App::from_text("abc");
+        for c in app.text.iter_mut() {
+            c.style.fg = Color::Red;
+            c.style.bg = Color::Blue;
+            c.style.bold = true;
+        }
+        app.selection = Some((1, 1));
+        assert_eq!(app.strip_colors(), 1);
+        assert_eq!((app.text[1].style.fg, app.text[1].style.bg), (Color::Reset, Color::Reset));
+        assert!(app.text[1].style.bold);
+        assert_eq!(app.text[0].style.fg, Color::Red);
+
+        app.selection = None;
+        assert_eq!(app.strip_colors(), 2);
+        assert!(app.text.iter().all(|c| c.style.fg == Color::Reset && c.style.bg == Color::Reset && c.style.bold));
+    }
+
+    #[test]
+    fn test_clear_formatting_keeps_colors() {
+        let mut app = App::from_text("abc");
+        for c in app.text.iter_mut() {
+            c.style.fg = Color::Green;
+            c.style.italic = true;
+            c.style.underline = UnderlineStyle::Double;
+            c.style.dim_level = 2;
+        }
+        app.selection = Some((0, 1));
+        assert_eq!(app.clear_formatting(), 2);
+        let plain_green = CharStyle {
+            fg: Color::Green,
+            ..CharStyle::default()
+        };
+        assert_eq!(app.text[0].style, plain_green);
+        assert!(app.text[2].style.italic);
+
+        app.selection = None;
+        assert_eq!(app.clear_formatting(), 1);
+        assert!(app.text.iter().all(|c| c.style == plain_green));
+    }
+
+    #[test]
+    fn test_clear_formatting_ignores_plain_links() {
+        let mut app = App::from_text("ab");
+        app.text[0].style.link = Some("https://example.com".to_string());
+        assert_eq!(app.clear_formatting(), 0);
+        assert!(!app.dirty);
+        assert_eq!(app.text[0].style.link.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn test_bold_on_colorful_selection_keeps_colors() {
+        let mut app = App::from_text("abc");
+        app.text[0].style.fg = Color::Red;
+        app.text[1].style.fg = Color::Green;
+        app.text[2].style.bg = Color::Blue;
+        app.current_fg = Color::Yellow;
+        app.cursor_pos = 0;
+        app.start_selection();
+        app.move_to_line_end();
+
+        app.toggle_bold();
+        assert!(app.text.iter().all(|c| c.style.bold));
+        let colors: Vec<_> = app.text.iter().map(|c| (c.style.fg, c.style.bg)).collect();
+        assert_eq!(
+            colors,
+            vec![
+                (Color::Red, Color::Reset),
+                (Color::Green, Color::Reset),
+                (Color::Reset, Color::Blue)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selection_state_three_ways() {
+        let mut app = App::from_text("ab\ncd");
+        assert_eq!(app.selection_state(|s| s.bold), None);
+
+        app.selection = Some((0, 4));
+        assert_eq!(app.selection_state(|s| s.bold), Some(TriState::AllOff));
+
+        // The newline's style doesn't count
+        for i in [0, 1, 3, 4] {
+            app.text[i].style.bold = true;
+        }
+        assert_eq!(app.selection_state(|s| s.bold), Some(TriState::AllOn));
+
+        app.text[4].style.bold = false;
+        assert_eq!(app.selection_state(|s| s.bold), Some(TriState::Mixed));
+        app.selection = Some((0, 1));
+        assert_eq!(app.selection_state(|s| s.bold), Some(TriState::AllOn));
+    }
+
+    #[test]
+    fn test_first_toggle_of_mixed_attribute_turns_it_on() {
+        let mut app = App::from_text("abc");
+        app.text[1].style.italic = true;
+        app.cursor_pos = 0;
+        app.load_style_from_cursor();
+        app.start_selection();
+        app.move_to_line_end();
+        assert!(app.is_mixed(app.touched.italic, |s| s.italic));
+
+        app.toggle_italic();
+        assert!(app.current_italic);
+        assert!(app.text.iter().all(|c| c.style.italic));
+        assert!(!app.is_mixed(app.touched.italic, |s| s.italic));
+
+        app.toggle_italic();
+        assert!(app.text.iter().all(|c| !c.style.italic));
+    }
+
+    #[test]
+    fn test_cancel_selection_reverts_preview() {
+        let mut app = App::from_text("abc");
+        app.text[1].style.fg = Color::Blue;
+        app.cursor_pos = 0;
+        app.start_selection();
+        app.move_right();
+        app.toggle_bold();
+        app.current_fg = Color::Red;
+        app.touched.fg = true;
+        app.apply_style();
+        assert!(app.text[0].style.bold && app.text[1].style.fg == Color::Red);
+
+        assert!(app.cancel_selection());
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.selection, None);
+        assert!(!app.text[0].style.bold);
+        assert_eq!(app.text[1].style.fg, Color::Blue);
+
+        // Nothing to revert once the selection was committed
+        app.reset_style();
+        app.cursor_pos = 0;
+        app.start_selection();
+        app.toggle_bold();
+        app.clear_selection();
+        assert!(!app.cancel_selection());
+        assert!(app.text[0].style.bold);
+    }
+}
@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+
+use crate::app::{Mode, Panel};
+use crate::export::TerminalProfile;
+use crate::palette::parse_palette;
+
+/// Environment variable naming the UI theme
+pub const THEME_ENV: &str = "TERMINAL_STYLER_THEME";
+/// Environment variable pointing at a palette file
+pub const PALETTE_ENV: &str = "TERMINAL_STYLER_PALETTE";
+/// Built-in UI themes
+pub const THEMES: &[&str] = &["default"];
+
+/// Options parsed from the command line
+/// Usage: terminal-styler [--conhost] [--ambient] [--start-panel PANEL] [--start-insert]
+///                        [--theme NAME] [--palette PATH] [--enter-stays] [--apply-on-enter]
+///                        [--osc52] [FILE | -]
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    /// File to load, or "-" for stdin
+    pub file: Option<String>,
+    pub terminal_profile: TerminalProfile,
+    pub ambient_effect: bool,
+    pub start_panel: Option<Panel>,
+    pub start_mode: Option<Mode>,
+    pub theme: Option<String>,
+    pub palette: Option<PathBuf>,
+    /// Enter in a style panel keeps the selection and focus
+    pub enter_stays: bool,
+    /// Color panels apply only on Enter instead of as you pick
+    pub apply_on_enter: bool,
+    /// Copy through the terminal with OSC 52 instead of the system clipboard
+    pub osc52: bool,
+}
+
+/// Theme and palette after applying precedence, plus anything that had to fall back
+#[derive(Debug, PartialEq)]
+pub struct Appearance {
+    pub theme: String,
+    pub palette: Option<PathBuf>,
+    pub warnings: Vec<String>,
+}
+
+/// Parse arguments (without the program name)
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--conhost" => options.terminal_profile = TerminalProfile::Conhost,
+            "--ambient" => options.ambient_effect = true,
+            "--enter-stays" => options.enter_stays = true,
+            "--apply-on-enter" => options.apply_on_enter = true,
+            "--osc52" => options.osc52 = true,
+            "--start-insert" => options.start_mode = Some(Mode::Typing),
+            "--theme" | "--palette" => {
+                let Some(value) = args.next() else {
+                    bail!("{} needs a value", arg);
+                };
+                if arg == "--theme" {
+                    options.theme = Some(value);
+                } else {
+                    options.palette = Some(PathBuf::from(value));
+                }
+            }
+            "--start-panel" => {
+                let Some(name) = args.next() else {
+                    bail!("--start-panel needs a value: editor, fg, bg or formatting");
+                };
+                options.start_panel = Some(parse_panel(&name)?);
+            }
+            _ if arg.starts_with("--start-panel=") => {
+                options.start_panel = Some(parse_panel(&arg["--start-panel=".len()..])?);
+            }
+            _ if arg.starts_with("--") => bail!("Unknown option '{}'", arg),
+            _ => {
+                if options.file.is_none() {
+                    options.file = Some(arg);
+                }
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+/// Pick the first value that is set: CLI flag, then environment, then config file
+pub fn resolve_setting<T>(cli: Option<T>, env: Option<T>, config: Option<T>) -> Option<T> {
+    cli.or(env).or(config)
+}
+
+/// Resolve theme and palette from CLI options and environment (`env` is usually `std::env::var`).
+/// There is no config file layer yet, so anything unset uses the built-in default.
+pub fn resolve_appearance(options: &Options, env: impl Fn(&str) -> Option<String>) -> Appearance {
+    let mut warnings = Vec::new();
+
+    let theme = match resolve_setting(options.theme.clone(), env(THEME_ENV), None) {
+        Some(name) if THEMES.contains(&name.as_str()) => name,
+        Some(name) => {
+            warnings.push(format!("Unknown theme '{}', using default", name));
+            THEMES[0].to_string()
+        }
+        None => THEMES[0].to_string(),
+    };
+
+    let palette = match resolve_setting(options.palette.clone(), env(PALETTE_ENV).map(PathBuf::from), None) {
+        Some(path) => match std::fs::read_to_string(&path) {
+            Ok(content) => match parse_palette(&content) {
+                Ok(_) => Some(path),
+                Err(e) => {
+                    warnings.push(format!("{} in {}, using built-in", e, path.display()));
+                    None
+                }
+            },
+            Err(_) => {
+                warnings.push(format!("Palette not found: {}, using built-in", path.display()));
+                None
+            }
+        },
+        None => None,
+    };
+
+    Appearance {
+        theme,
+        palette,
+        warnings,
+    }
+}
+
+/// Map a panel name to a panel
+fn parse_panel(name: &str) -> Result<Panel> {
+    match name.to_ascii_lowercase().as_str() {
+        "editor" => Ok(Panel::Editor),
+        "fg" | "foreground" => Ok(Panel::FgColor),
+        "bg" | "background" => Ok(Panel::BgColor),
+        "formatting" | "decorations" => Ok(Panel::Formatting),
+        _ => bail!(
+            "Unknown panel '{}' (expected editor, fg, bg or formatting)",
+            name
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_defaults_are_unchanged() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options, Options::default());
+        assert_eq!(options.start_panel, None);
+        assert_eq!(options.start_mode, None);
+    }
+
+    #[test]
+    fn test_start_panel_and_mode_flags() {
+        let options = parse(&["--start-panel", "formatting", "--start-insert", "demo.txt"]).unwrap();
+        assert_eq!(options.start_panel, Some(Panel::Formatting));
+        assert_eq!(options.start_mode, Some(Mode::Typing));
+        assert_eq!(options.file.as_deref(), Some("demo.txt"));
+
+        let options = parse(&["--start-panel=BG"]).unwrap();
+        assert_eq!(options.start_panel, Some(Panel::BgColor));
+    }
+
+    #[test]
+    fn test_invalid_start_panel_errors() {
+        assert!(parse(&["--start-panel", "sidebar"]).is_err());
+        assert!(parse(&["--start-panel"]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_flag_errors() {
+        let err = parse(&["--star-panel", "colors"]).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown option '--star-panel'");
+    }
+
+    /// Environment with only the given variables set
+    fn mock_env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: Vec<(String, String)> =
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |key| vars.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_setting_precedence() {
+        assert_eq!(resolve_setting(Some("cli"), Some("env"), Some("config")), Some("cli"));
+        assert_eq!(resolve_setting(None, Some("env"), Some("config")), Some("env"));
+        assert_eq!(resolve_setting(None, None, Some("config")), Some("config"));
+        assert_eq!(resolve_setting::<&str>(None, None, None), None);
+    }
+
+    #[test]
+    fn test_appearance_from_env_and_flags() {
+        let path = std::env::temp_dir().join(format!("ts-palette-{}.ron", std::process::id()));
+        std::fs::write(&path, r#"[(Red, "Red", '0')]"#).unwrap();
+        let path_str = path.to_str().unwrap();
+        let env = mock_env(&[(THEME_ENV, "default"), (PALETTE_ENV, path_str)]);
+        let appearance = resolve_appearance(&Options::default(), &env);
+        assert_eq!(appearance.theme, "default");
+        assert_eq!(appearance.palette, Some(path.clone()));
+        assert!(appearance.warnings.is_empty());
+
+        // The CLI flag wins over the environment
+        let options = parse(&["--palette", "/no/such/palette.ron"]).unwrap();
+        let appearance = resolve_appearance(&options, &env);
+        assert_eq!(appearance.palette, None);
+        assert_eq!(appearance.warnings.len(), 1);
+
+        // Files that are not palettes fall back too
+        let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let appearance = resolve_appearance(&Options::default(), mock_env(&[(PALETTE_ENV, manifest)]));
+        assert_eq!(appearance.palette, None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_theme_warns_and_falls_back() {
+        let appearance = resolve_appearance(&Options::default(), mock_env(&[(THEME_ENV, "nord")]));
+        assert_eq!(appearance.theme, "default");
+        assert_eq!(appearance.warnings, vec!["Unknown theme 'nord', using default"]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::app::UnderlineStyle;
 use ratatui::style::Color;
 
 /// Available colors for the palette (0-indexed for number key selection)
@@ -22,14 +23,200 @@ pub const COLOR_PALETTE: &[(Color, &str, char)] = &[
     (Color::Gray, "Gray", 'g'),
 ];
 
-/// Get color index from char key
-pub fn color_index_from_key(key: char) -> Option<usize> {
-    COLOR_PALETTE.iter().position(|(_, _, k)| *k == key.to_ascii_lowercase())
+/// Approximate RGB value of a color using the xterm defaults for named and indexed colors;
+/// `Reset` depends on the terminal and has none
+pub fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let rgb = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Black => ANSI[0],
+        Color::Red => ANSI[1],
+        Color::Green => ANSI[2],
+        Color::Yellow => ANSI[3],
+        Color::Blue => ANSI[4],
+        Color::Magenta => ANSI[5],
+        Color::Cyan => ANSI[6],
+        Color::Gray => ANSI[7],
+        Color::DarkGray => ANSI[8],
+        Color::LightRed => ANSI[9],
+        Color::LightGreen => ANSI[10],
+        Color::LightYellow => ANSI[11],
+        Color::LightBlue => ANSI[12],
+        Color::LightMagenta => ANSI[13],
+        Color::LightCyan => ANSI[14],
+        Color::White => ANSI[15],
+        Color::Indexed(i @ 0..=15) => ANSI[i as usize],
+        Color::Indexed(i @ 16..=231) => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = i - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        Color::Indexed(i) => {
+            let v = 8 + (i - 232) * 10;
+            (v, v, v)
+        }
+    };
+    Some(rgb)
 }
 
-/// Get color index from Color value
-pub fn color_index_from_color(color: Color) -> usize {
-    COLOR_PALETTE.iter().position(|(c, _, _)| *c == color).unwrap_or(8) // Default to White
+/// Concrete RGB for any color, named ones via the xterm defaults. `Reset` has no fixed value
+/// and resolves to the xterm default foreground
+pub fn resolve_named(color: Color) -> (u8, u8, u8) {
+    to_rgb(color).unwrap_or((229, 229, 229))
+}
+
+/// Parse `#rgb`, `#rrggbb` or a bare `rrggbb` into `Color::Rgb`
+pub fn parse_hex_color(s: &str) -> Option<Color> {
+    let s = s.trim();
+    let digits: String = match s.strip_prefix('#') {
+        Some(digits) if digits.len() == 3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        Some(digits) => digits.to_string(),
+        None => s.to_string(),
+    };
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Parse a `#rrggbb` (or `#rgb`) hex color, preferring the palette color with that exact value
+pub fn from_hex(hex: &str) -> Option<Color> {
+    if !hex.trim().starts_with('#') {
+        return None;
+    }
+    let rgb = to_rgb(parse_hex_color(hex)?)?;
+    let named = COLOR_PALETTE
+        .iter()
+        .map(|(c, _, _)| *c)
+        .find(|c| to_rgb(*c) == Some(rgb));
+    Some(named.unwrap_or(Color::Rgb(rgb.0, rgb.1, rgb.2)))
+}
+
+/// Name and hex value of a color for display, e.g. "Red (#cd0000)" or "Indexed(196)"
+pub fn color_display_string(color: Color) -> String {
+    let named = COLOR_PALETTE.iter().find(|(c, _, _)| *c == color);
+    match (color, named, to_rgb(color)) {
+        (Color::Reset, _, _) => "Default".to_string(),
+        (Color::Indexed(i), _, _) => format!("Indexed({})", i),
+        (_, Some((_, name, _)), Some((r, g, b))) => format!("{} (#{:02x}{:02x}{:02x})", name, r, g, b),
+        (_, _, Some((r, g, b))) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        _ => format!("{:?}", color),
+    }
+}
+
+/// Linear interpolation between two colors at `t` (0.0 is `from`, 1.0 is `to`)
+/// Returns None when either color has no RGB value
+pub fn lerp_color(from: Color, to: Color, t: f32) -> Option<Color> {
+    let (from, to) = (to_rgb(from)?, to_rgb(to)?);
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Some(Color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2)))
+}
+
+/// Convert HSL (hue in degrees, saturation and lightness 0.0-1.0) to `Color::Rgb`
+pub fn hsl_to_rgb(h: f32, s: f32, l: f32) -> Color {
+    let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let sector = h.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = l - chroma / 2.0;
+    let channel = |v: f32| ((v + m) * 255.0).round() as u8;
+    Color::Rgb(channel(r), channel(g), channel(b))
+}
+
+/// WCAG AA minimum contrast for normal text
+pub const MIN_CONTRAST: f32 = 4.5;
+
+/// WCAG relative luminance of an sRGB color, 0.0 (black) to 1.0 (white)
+fn relative_luminance((r, g, b): (u8, u8, u8)) -> f32 {
+    let linear = |v: u8| {
+        let v = v as f32 / 255.0;
+        if v <= 0.03928 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+/// WCAG contrast ratio between a text and background color, 1.0 to 21.0.
+/// Named colors use their xterm values; `Reset` is taken as light text on a black background
+pub fn contrast_ratio(fg: Color, bg: Color) -> f32 {
+    let fg = relative_luminance(resolve_named(fg));
+    let bg = relative_luminance(to_rgb(bg).unwrap_or((0, 0, 0)));
+    (fg.max(bg) + 0.05) / (fg.min(bg) + 0.05)
+}
+
+/// Convert `Color::Rgb` to HSL (hue in degrees, saturation and lightness 0.0-1.0).
+/// Other colors give None; pass them through `resolve_named` first
+pub fn rgb_to_hsl(color: Color) -> Option<(f32, f32, f32)> {
+    let Color::Rgb(r, g, b) = color else {
+        return None;
+    };
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return Some((0.0, 0.0, l));
+    }
+
+    let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    Some((h, s, l))
+}
+
+/// Shift a color warmer (positive `amount`: more red and a little yellow) or cooler
+/// (negative: more blue). Colors without an RGB value are returned unchanged
+pub fn shift_temperature(color: Color, amount: i32) -> Color {
+    let Some((r, g, b)) = to_rgb(color) else {
+        return color;
+    };
+    let nudge = |v: u8, by: i32| (v as i32 + by).clamp(0, 255) as u8;
+    Color::Rgb(nudge(r, amount), nudge(g, amount / 4), nudge(b, -amount))
+}
+
+/// Blend `fg` toward `bg` by `dim_level` quarters, so each dim level is visibly fainter
+/// Returns None when either color has no RGB value
+pub fn dim_blend(fg: Color, bg: Color, dim_level: u8) -> Option<Color> {
+    let (fg, bg) = (to_rgb(fg)?, to_rgb(bg)?);
+    let weight = dim_level.min(4) as u16;
+    let mix = |f: u8, b: u8| ((f as u16 * (4 - weight) + b as u16 * weight) / 4) as u8;
+    Some(Color::Rgb(mix(fg.0, bg.0), mix(fg.1, bg.1), mix(fg.2, bg.2)))
 }
 
 /// Get ANSI code for foreground color
@@ -101,8 +288,17 @@ pub fn italic_ansi_code(italic: bool) -> Option<&'static str> {
 }
 
 /// Get ANSI code for underline
-pub fn underline_ansi_code(underline: bool) -> Option<&'static str> {
-    if underline { Some("4") } else { None }
+pub fn underline_ansi_code(underline: UnderlineStyle) -> Option<&'static str> {
+    match underline {
+        UnderlineStyle::None => None,
+        UnderlineStyle::Single => Some("4"),
+        UnderlineStyle::Double => Some("21"),
+    }
+}
+
+/// Get ANSI code for overline
+pub fn overline_ansi_code(overline: bool) -> Option<&'static str> {
+    if overline { Some("53") } else { None }
 }
 
 /// Get ANSI code for strikethrough
@@ -110,6 +306,21 @@ pub fn strikethrough_ansi_code(strikethrough: bool) -> Option<&'static str> {
     if strikethrough { Some("9") } else { None }
 }
 
+/// Get ANSI code for blink
+pub fn blink_ansi_code(blink: bool) -> Option<&'static str> {
+    if blink { Some("5") } else { None }
+}
+
+/// Get ANSI code for reverse video
+pub fn reverse_ansi_code(reverse: bool) -> Option<&'static str> {
+    if reverse { Some("7") } else { None }
+}
+
+/// Get ANSI code for conceal
+pub fn hidden_ansi_code(hidden: bool) -> Option<&'static str> {
+    if hidden { Some("8") } else { None }
+}
+
 /// Theme colors for the UI (Anthropic/Claude inspired)
 pub mod theme {
     use ratatui::style::Color;
@@ -134,4 +345,112 @@ pub mod theme {
     // Status colors
     pub const SUCCESS: Color = Color::Rgb(34, 197, 94);          // Green-500
     pub const ERROR: Color = Color::Rgb(239, 68, 68);            // Red-500
+    pub const WARNING_BG: Color = Color::Rgb(120, 53, 15);       // Amber-900
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dim_blend_levels() {
+        let bg = Color::Rgb(0, 0, 0);
+        assert_eq!(dim_blend(Color::Rgb(200, 100, 40), bg, 1), Some(Color::Rgb(150, 75, 30)));
+        assert_eq!(dim_blend(Color::Rgb(200, 100, 40), bg, 3), Some(Color::Rgb(50, 25, 10)));
+        assert_eq!(dim_blend(Color::Red, Color::White, 2), Some(Color::Rgb(230, 127, 127)));
+        assert_eq!(dim_blend(Color::Reset, bg, 1), None);
+        assert_eq!(to_rgb(Color::Indexed(196)), Some((255, 0, 0)));
+        assert_eq!(to_rgb(Color::Indexed(232)), Some((8, 8, 8)));
+    }
+
+    #[test]
+    fn test_color_display_string() {
+        assert_eq!(color_display_string(Color::Red), "Red (#cd0000)");
+        assert_eq!(color_display_string(Color::LightRed), "LightRed (#ff0000)");
+        assert_eq!(color_display_string(Color::Rgb(255, 136, 0)), "#ff8800");
+        assert_eq!(color_display_string(Color::Indexed(196)), "Indexed(196)");
+        assert_eq!(color_display_string(Color::Reset), "Default");
+    }
+
+    #[test]
+    fn test_from_hex() {
+        assert_eq!(from_hex("#cd0000"), Some(Color::Red));
+        assert_eq!(from_hex("#FF8800"), Some(Color::Rgb(255, 136, 0)));
+        assert_eq!(from_hex("#fff"), Some(Color::White));
+        assert_eq!(from_hex("ff8800"), None);
+        assert_eq!(from_hex("#ggg"), None);
+    }
+
+    #[test]
+    fn test_shift_temperature() {
+        assert_eq!(shift_temperature(Color::Rgb(128, 128, 128), 20), Color::Rgb(148, 133, 108));
+        assert_eq!(shift_temperature(Color::Rgb(128, 128, 128), -20), Color::Rgb(108, 123, 148));
+        assert_eq!(shift_temperature(Color::Rgb(250, 0, 5), 20), Color::Rgb(255, 5, 0));
+        assert_eq!(shift_temperature(Color::Reset, 20), Color::Reset);
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#f00"), Some(Color::Rgb(255, 0, 0)));
+        assert_eq!(parse_hex_color("#FF6400"), Some(Color::Rgb(255, 100, 0)));
+        assert_eq!(parse_hex_color("ff6400"), Some(Color::Rgb(255, 100, 0)));
+        assert_eq!(parse_hex_color("#gggggg"), None);
+        assert_eq!(parse_hex_color("#+f+f+f"), None);
+        assert_eq!(parse_hex_color("#ff640"), None);
+        assert_eq!(parse_hex_color("f00"), None);
+    }
+
+    #[test]
+    fn test_hsl_to_rgb() {
+        assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), Color::Rgb(255, 0, 0));
+        assert_eq!(hsl_to_rgb(120.0, 1.0, 0.5), Color::Rgb(0, 255, 0));
+        assert_eq!(hsl_to_rgb(240.0, 1.0, 0.5), Color::Rgb(0, 0, 255));
+        assert_eq!(hsl_to_rgb(-60.0, 1.0, 0.5), Color::Rgb(255, 0, 255));
+        assert_eq!(hsl_to_rgb(200.0, 0.0, 1.0), Color::Rgb(255, 255, 255));
+    }
+
+    #[test]
+    fn test_rgb_to_hsl() {
+        let close = |a: (f32, f32, f32), b: (f32, f32, f32)| {
+            (a.0 - b.0).abs() < 0.5 && (a.1 - b.1).abs() < 0.01 && (a.2 - b.2).abs() < 0.01
+        };
+        assert!(close(rgb_to_hsl(Color::Rgb(255, 0, 0)).unwrap(), (0.0, 1.0, 0.5)));
+        assert!(close(rgb_to_hsl(Color::Rgb(0, 0, 128)).unwrap(), (240.0, 1.0, 0.25)));
+        assert!(close(rgb_to_hsl(Color::Rgb(128, 128, 128)).unwrap(), (0.0, 0.0, 0.5)));
+        assert_eq!(rgb_to_hsl(Color::Red), None);
+        assert_eq!(rgb_to_hsl(Color::Reset), None);
+
+        // Round trips land back on the same color
+        for color in [
+            Color::Rgb(255, 100, 0),
+            Color::Rgb(12, 34, 56),
+            Color::Rgb(200, 200, 40),
+            Color::Rgb(0, 0, 0),
+            Color::Rgb(255, 255, 255),
+        ] {
+            let (h, s, l) = rgb_to_hsl(color).unwrap();
+            assert_eq!(hsl_to_rgb(h, s, l), color);
+        }
+    }
+
+    #[test]
+    fn test_resolve_named() {
+        assert_eq!(resolve_named(Color::Red), (205, 0, 0));
+        assert_eq!(resolve_named(Color::White), (255, 255, 255));
+        assert_eq!(resolve_named(Color::Rgb(1, 2, 3)), (1, 2, 3));
+        let (r, g, b) = resolve_named(Color::LightBlue);
+        assert!(rgb_to_hsl(Color::Rgb(r, g, b)).is_some());
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((contrast_ratio(Color::Black, Color::Black) - 1.0).abs() < 0.01);
+        assert!((contrast_ratio(Color::White, Color::Black) - 21.0).abs() < 0.01);
+        assert_eq!(
+            contrast_ratio(Color::Rgb(0, 0, 0), Color::Rgb(255, 255, 255)),
+            contrast_ratio(Color::Rgb(255, 255, 255), Color::Rgb(0, 0, 0))
+        );
+        // The default pair is readable, so it isn't flagged
+        assert!(contrast_ratio(Color::Reset, Color::Reset) > MIN_CONTRAST);
+    }
 }
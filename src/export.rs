@@ -1,95 +1,1114 @@
-use crate::app::{App, StyledChar};
+use crate::app::{App, CharStyle, StyledChar, UnderlineStyle};
 use crate::colors::{
-    bg_ansi_code, bold_ansi_code, dim_ansi_code, fg_ansi_code,
-    italic_ansi_code, strikethrough_ansi_code, underline_ansi_code,
+    bg_ansi_code, blink_ansi_code, bold_ansi_code, dim_ansi_code, fg_ansi_code, hidden_ansi_code,
+    italic_ansi_code, overline_ansi_code, reverse_ansi_code, strikethrough_ansi_code, theme,
+    to_rgb, underline_ansi_code,
 };
-use anyhow::Result;
+use crate::import::{DocumentSettings, RonEncoder};
+use anyhow::{anyhow, Result};
 use arboard::Clipboard;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ratatui::style::Color;
+use std::io::Write;
+use unicode_width::UnicodeWidthChar;
+
+/// Build the SGR codes for a single character's style.
+///
+/// Codes are always emitted in a fixed order: intensity (bold `1`, dim `2`),
+/// then italic, underline and strikethrough, then foreground and finally
+/// background color. Some terminals only honour dim when `2` precedes the
+/// color code, so attributes come before colors. Default colors are omitted,
+/// so a fully default style has no codes at all.
+fn style_codes(style: &CharStyle) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::new();
+
+    // Bold
+    if let Some(bold) = bold_ansi_code(style.bold) {
+        codes.push(bold.to_string());
+    }
+
+    // Dim
+    if let Some(dim) = dim_ansi_code(style.dim_level) {
+        codes.push(dim.to_string());
+    }
+
+    // Italic
+    if let Some(italic) = italic_ansi_code(style.italic) {
+        codes.push(italic.to_string());
+    }
+
+    // Underline
+    if let Some(underline) = underline_ansi_code(style.underline) {
+        codes.push(underline.to_string());
+    }
+
+    // Overline
+    if let Some(overline) = overline_ansi_code(style.overline) {
+        codes.push(overline.to_string());
+    }
+
+    // Strikethrough
+    if let Some(strike) = strikethrough_ansi_code(style.strikethrough) {
+        codes.push(strike.to_string());
+    }
+
+    // Blink
+    if let Some(blink) = blink_ansi_code(style.blink) {
+        codes.push(blink.to_string());
+    }
+
+    // Reverse video
+    if let Some(reverse) = reverse_ansi_code(style.reverse) {
+        codes.push(reverse.to_string());
+    }
+
+    // Conceal
+    if let Some(hidden) = hidden_ansi_code(style.hidden) {
+        codes.push(hidden.to_string());
+    }
+
+    // Foreground color (only if not reset)
+    let fg_code = fg_ansi_code(style.fg);
+    if fg_code != "39" {
+        codes.push(fg_code);
+    }
+
+    // Background color (only if not reset)
+    let bg_code = bg_ansi_code(style.bg);
+    if bg_code != "49" {
+        codes.push(bg_code);
+    }
+
+    codes
+}
+
+/// SGR codes that turn `from` into `to` without a full reset, in the same order as
+/// `style_codes`. Bold and dim share one off code (`22`), so turning either off clears
+/// both and whichever should stay is re-emitted.
+fn transition_codes(from: &CharStyle, to: &CharStyle) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::new();
+
+    // Intensity
+    let (from_dim, to_dim) = (from.dim_level > 0, to.dim_level > 0);
+    if (from.bold && !to.bold) || (from_dim && !to_dim) {
+        codes.push("22".to_string());
+        codes.extend(bold_ansi_code(to.bold).map(String::from));
+        codes.extend(dim_ansi_code(to.dim_level).map(String::from));
+    } else {
+        codes.extend(bold_ansi_code(to.bold && !from.bold).map(String::from));
+        codes.extend(dim_ansi_code(if from_dim { 0 } else { to.dim_level }).map(String::from));
+    }
+
+    // Italic
+    if from.italic != to.italic {
+        codes.push(if to.italic { "3" } else { "23" }.to_string());
+    }
+
+    // Underline; single and double replace each other without an off code
+    if from.underline != to.underline {
+        codes.push(underline_ansi_code(to.underline).unwrap_or("24").to_string());
+    }
+
+    // Overline, strikethrough, blink, reverse, conceal
+    for (was, is, on, off) in [
+        (from.overline, to.overline, "53", "55"),
+        (from.strikethrough, to.strikethrough, "9", "29"),
+        (from.blink, to.blink, "5", "25"),
+        (from.reverse, to.reverse, "7", "27"),
+        (from.hidden, to.hidden, "8", "28"),
+    ] {
+        if was != is {
+            codes.push(if is { on } else { off }.to_string());
+        }
+    }
+
+    // Colors
+    if from.fg != to.fg {
+        codes.push(fg_ansi_code(to.fg));
+    }
+    if from.bg != to.bg {
+        codes.push(bg_ansi_code(to.bg));
+    }
+
+    codes
+}
+
+/// Target terminal for exported sequences
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TerminalProfile {
+    /// Any terminal with full SGR support
+    #[default]
+    Standard,
+    /// Legacy Windows console host: no dim, no bright colors
+    Conhost,
+}
+
+impl TerminalProfile {
+    /// Downgrade a style to the attributes this terminal can display
+    pub fn downgrade(&self, style: &CharStyle) -> CharStyle {
+        match self {
+            TerminalProfile::Standard => style.clone(),
+            TerminalProfile::Conhost => CharStyle {
+                fg: standard_color(style.fg),
+                bg: standard_color(style.bg),
+                dim_level: 0,
+                ..style.clone()
+            },
+        }
+    }
+}
+
+/// Map a bright color to its standard (30-37) equivalent
+fn standard_color(color: Color) -> Color {
+    match color {
+        Color::DarkGray => Color::Black,
+        Color::LightRed => Color::Red,
+        Color::LightGreen => Color::Green,
+        Color::LightYellow => Color::Yellow,
+        Color::LightBlue => Color::Blue,
+        Color::LightMagenta => Color::Magenta,
+        Color::LightCyan => Color::Cyan,
+        Color::Gray => Color::White,
+        other => other,
+    }
+}
+
+/// How SGR sequences are laid out across the exported text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RunEncoding {
+    /// Full reset and set wherever the style changes
+    ResetOnChange,
+    /// Reset and set before every character, for debugging terminals that lose SGR state
+    PerCharacter,
+    /// Emit only the attributes that change between runs, resetting when that's shorter
+    #[default]
+    Delta,
+}
+
+/// What the `e` export copies
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// `echo -e` command with ANSI escapes
+    #[default]
+    Echo,
+    /// `printf '%b'` command, portable to shells whose echo lacks `-e`
+    Printf,
+    /// C string literal with `\033` escapes
+    CString,
+    /// Python string literal with `\x1b` escapes
+    PythonString,
+    /// `<pre>` block of inline-styled spans
+    Html,
+    /// Standalone SVG image
+    Svg,
+    /// Markdown emphasis for bold, italic and strikethrough; colors are dropped
+    Markdown,
+    /// Just the characters
+    Plain,
+}
+
+impl ExportFormat {
+    /// The next format in the `E` cycle
+    pub fn next(self) -> Self {
+        match self {
+            ExportFormat::Echo => ExportFormat::Printf,
+            ExportFormat::Printf => ExportFormat::CString,
+            ExportFormat::CString => ExportFormat::PythonString,
+            ExportFormat::PythonString => ExportFormat::Html,
+            ExportFormat::Html => ExportFormat::Svg,
+            ExportFormat::Svg => ExportFormat::Markdown,
+            ExportFormat::Markdown => ExportFormat::Plain,
+            ExportFormat::Plain => ExportFormat::Echo,
+        }
+    }
+
+    /// Short name for status messages
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Echo => "echo",
+            ExportFormat::Printf => "printf",
+            ExportFormat::CString => "C string",
+            ExportFormat::PythonString => "Python string",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Svg => "SVG",
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Plain => "plain text",
+        }
+    }
+}
+
+/// How the ESC byte is written in the echo export
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EscapeStyle {
+    /// `\033`, understood by every `echo -e` and `printf`
+    #[default]
+    Octal,
+    /// `\x1b`
+    Hex,
+    /// `\e`, shorter but a bash/zsh extension
+    Bash,
+}
+
+impl EscapeStyle {
+    /// The escape as written inside the echo command's string
+    pub fn prefix(self) -> &'static str {
+        match self {
+            EscapeStyle::Octal => r"\033",
+            EscapeStyle::Hex => r"\x1b",
+            EscapeStyle::Bash => r"\e",
+        }
+    }
+
+    /// The next style in the cycle
+    pub fn next(self) -> Self {
+        match self {
+            EscapeStyle::Octal => EscapeStyle::Hex,
+            EscapeStyle::Hex => EscapeStyle::Bash,
+            EscapeStyle::Bash => EscapeStyle::Octal,
+        }
+    }
+}
+
+/// Options for the echo export
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub encoding: RunEncoding,
+    pub escape: EscapeStyle,
+}
+
+/// Incremental SGR run encoder, so long exports can be produced in chunks.
+/// `esc` introduces each sequence and `push_char` writes a character quoted for the target shell.
+struct SgrEncoder {
+    esc: &'static str,
+    push_char: fn(&mut String, char),
+    options: ExportOptions,
+    current_codes: Vec<String>,
+    /// Style in effect at the end of the output so far, for delta encoding
+    current_style: CharStyle,
+    /// Hyperlink open at the end of the output so far
+    current_link: Option<String>,
+    output: String,
+    /// A style is still in effect at the end of the output so far, so it needs a final reset
+    styled: bool,
+}
+
+impl SgrEncoder {
+    fn new(esc: &'static str, push_char: fn(&mut String, char), options: ExportOptions) -> Self {
+        Self {
+            esc,
+            push_char,
+            options,
+            current_codes: Vec::new(),
+            current_style: CharStyle::default(),
+            current_link: None,
+            output: String::new(),
+            styled: false,
+        }
+    }
+
+    fn push(&mut self, styled_char: &StyledChar) {
+        self.push_link(&styled_char.style.link);
+        if self.options.encoding == RunEncoding::Delta {
+            self.push_delta(styled_char);
+            return;
+        }
+        let new_codes = style_codes(&styled_char.style);
+
+        // Only emit escape sequence if codes changed, unless every character gets one
+        let per_char = self.options.encoding == RunEncoding::PerCharacter;
+        if per_char || new_codes != self.current_codes {
+            if new_codes.is_empty() {
+                // Back to plain text
+                self.output.push_str(&format!("{}[0m", self.esc));
+            } else {
+                // Reset first, then apply new codes
+                let codes = new_codes.join(";");
+                self.output.push_str(&format!("{}[0;{}m", self.esc, codes));
+            }
+            self.styled = !new_codes.is_empty();
+            self.current_codes = new_codes;
+        }
+
+        (self.push_char)(&mut self.output, styled_char.ch);
+    }
+
+    /// Emit only what changed since the previous character, or a reset and the full style
+    /// when that is shorter (always the case for plain text)
+    fn push_delta(&mut self, styled_char: &StyledChar) {
+        // Links are written separately, so they don't count as a style change
+        let style = CharStyle {
+            link: None,
+            ..styled_char.style.clone()
+        };
+        // Compare what gets written, since e.g. every dim level exports as the same code
+        let new_codes = style_codes(&style);
+        if new_codes != style_codes(&self.current_style) {
+            let delta = transition_codes(&self.current_style, &style).join(";");
+            let reset = std::iter::once("0".to_string())
+                .chain(new_codes)
+                .collect::<Vec<_>>()
+                .join(";");
+            if !delta.is_empty() {
+                let codes = if reset.len() <= delta.len() { reset } else { delta };
+                self.output.push_str(&format!("{}[{}m", self.esc, codes));
+            }
+        }
+        self.current_style = style;
+        // Only a style still in effect at the end needs the final reset
+        self.styled = self.current_style != CharStyle::default();
+
+        (self.push_char)(&mut self.output, styled_char.ch);
+    }
+
+    /// Open, switch or close an OSC 8 hyperlink, ending it with ESC and a backslash
+    fn push_link(&mut self, link: &Option<String>) {
+        if *link == self.current_link {
+            return;
+        }
+        self.output.push_str(&format!("{}]8;;", self.esc));
+        for ch in link.iter().flat_map(|url| url.chars()) {
+            (self.push_char)(&mut self.output, ch);
+        }
+        self.output.push_str(self.esc);
+        (self.push_char)(&mut self.output, '\\');
+        self.current_link = link.clone();
+    }
+
+    /// Close any open link and reset at the end if a style is still in effect
+    fn finish(mut self) -> String {
+        self.push_link(&None);
+        if self.styled {
+            self.output.push_str(&format!("{}[0m", self.esc));
+        }
+        self.output
+    }
+}
+
+/// Encode styled text as SGR runs, ending with a reset
+fn encode_sgr_runs(
+    text: &[StyledChar],
+    esc: &'static str,
+    push_char: fn(&mut String, char),
+    options: ExportOptions,
+) -> String {
+    let mut encoder = SgrEncoder::new(esc, push_char, options);
+    for styled_char in text {
+        encoder.push(styled_char);
+    }
+    encoder.finish()
+}
+
+/// Escape a character for a double-quoted `echo -e` argument
+fn push_echo_char(output: &mut String, ch: char) {
+    match ch {
+        '\n' => output.push_str(r#"\n"#),
+        '"' => output.push_str(r#"\""#),
+        '\\' => output.push_str(r#"\\"#),
+        '$' => output.push_str(r#"\$"#),
+        '`' => output.push_str(r#"\`"#),
+        '!' => output.push_str(r#"\!"#),
+        _ => output.push(ch),
+    }
+}
+
+/// ANSI text with literal `\e` escapes and real newlines, for editing by hand
+pub fn generate_ansi_source(text: &[StyledChar]) -> String {
+    encode_sgr_runs(text, r"\e", |output, ch| output.push(ch), ExportOptions::default())
+}
 
 /// Generate an echo command with ANSI escape codes for the styled text
+#[cfg(test)]
 pub fn generate_echo_command(text: &[StyledChar]) -> String {
+    generate_echo_command_with(text, ExportOptions::default())
+}
+
+/// Generate an echo command laid out as `options` says
+pub fn generate_echo_command_with(text: &[StyledChar], options: ExportOptions) -> String {
     if text.is_empty() {
         return r#"echo -e """#.to_string();
     }
 
-    let mut output = String::from(r#"echo -e ""#);
-    let mut current_codes: Vec<String> = Vec::new();
+    let body = encode_sgr_runs(text, options.escape.prefix(), push_echo_char, options);
+    format!(r#"echo -e "{}""#, body)
+}
+
+/// `#rrggbb` for a color, or None for the terminal default
+fn css_color(color: Color) -> Option<String> {
+    to_rgb(color).map(|(r, g, b)| format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+/// Inline CSS for a style; empty for the default style
+fn css_declarations(style: &CharStyle) -> String {
+    let mut css: Vec<String> = Vec::new();
+    if let Some(fg) = css_color(style.fg) {
+        css.push(format!("color:{}", fg));
+    }
+    if let Some(bg) = css_color(style.bg) {
+        css.push(format!("background-color:{}", bg));
+    }
+    if style.bold {
+        css.push("font-weight:bold".to_string());
+    }
+    if style.italic {
+        css.push("font-style:italic".to_string());
+    }
+    let decorations = decoration_names(style);
+    if !decorations.is_empty() {
+        css.push(format!("text-decoration:{}", decorations.join(" ")));
+    }
+    if style.underline == UnderlineStyle::Double {
+        css.push("text-decoration-style:double".to_string());
+    }
+    // Each dim level takes away a quarter of the opacity
+    if style.dim_level > 0 {
+        css.push(format!("opacity:{}", 1.0 - style.dim_level.min(3) as f32 * 0.25));
+    }
+    css.join(";")
+}
 
+/// CSS/SVG `text-decoration` line names for a style
+fn decoration_names(style: &CharStyle) -> Vec<&'static str> {
+    [
+        (style.underline.is_underlined(), "underline"),
+        (style.overline, "overline"),
+        (style.strikethrough, "line-through"),
+    ]
+    .into_iter()
+    .filter_map(|(on, name)| on.then_some(name))
+    .collect()
+}
+
+/// Escape a character for HTML text
+fn push_html_char(output: &mut String, ch: char) {
+    match ch {
+        '<' => output.push_str("&lt;"),
+        '>' => output.push_str("&gt;"),
+        '&' => output.push_str("&amp;"),
+        _ => output.push(ch),
+    }
+}
+
+/// Incremental HTML export, opening a `<span>` wherever the style changes
+struct HtmlEncoder {
+    output: String,
+    /// Style of the run being written, None before the first character
+    run_style: Option<CharStyle>,
+    /// The run has an open `<span>`
+    span_open: bool,
+}
+
+impl HtmlEncoder {
+    fn new() -> Self {
+        Self {
+            output: String::from("<pre>"),
+            run_style: None,
+            span_open: false,
+        }
+    }
+
+    fn push(&mut self, styled_char: &StyledChar) {
+        if self.run_style.as_ref() != Some(&styled_char.style) {
+            if self.span_open {
+                self.output.push_str("</span>");
+            }
+            let css = css_declarations(&styled_char.style);
+            self.span_open = !css.is_empty();
+            if self.span_open {
+                self.output.push_str(&format!("<span style=\"{}\">", css));
+            }
+            self.run_style = Some(styled_char.style.clone());
+        }
+        push_html_char(&mut self.output, styled_char.ch);
+    }
+
+    fn finish(mut self) -> String {
+        if self.span_open {
+            self.output.push_str("</span>");
+        }
+        self.output.push_str("</pre>");
+        self.output
+    }
+}
+
+/// HTML `<pre>` block with one inline-styled `<span>` per run of identically styled characters
+pub fn export_html(text: &[StyledChar]) -> String {
+    let mut encoder = HtmlEncoder::new();
     for styled_char in text {
-        let mut new_codes: Vec<String> = Vec::new();
+        encoder.push(styled_char);
+    }
+    encoder.finish()
+}
+
+/// The characters alone, styles stripped
+pub fn export_plain(text: &[StyledChar]) -> String {
+    text.iter().map(|c| c.ch).collect()
+}
+
+/// Markdown with `**bold**`, `*italic*` and `~~strike~~` runs; colors, underline and dim
+/// have no Markdown form and are dropped. Emphasis never spans a line or starts or ends
+/// with whitespace, so such runs are split and the whitespace kept outside the markers.
+pub fn export_markdown(text: &[StyledChar]) -> String {
+    let emphasis = |style: &CharStyle| (style.bold, style.italic, style.strikethrough);
+    let mut output = String::new();
+
+    for (i, line) in text.split(|c| c.ch == '\n').enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        for run in line.chunk_by(|a, b| emphasis(&a.style) == emphasis(&b.style)) {
+            let mut content = String::new();
+            for styled_char in run {
+                if matches!(styled_char.ch, '\\' | '*' | '_' | '~' | '`') {
+                    content.push('\\');
+                }
+                content.push(styled_char.ch);
+            }
+
+            let (bold, italic, strike) = emphasis(&run[0].style);
+            let marker = match (bold, italic) {
+                (true, true) => "***",
+                (true, false) => "**",
+                (false, true) => "*",
+                (false, false) => "",
+            };
+            let (open, close) = if strike {
+                (format!("~~{}", marker), format!("{}~~", marker))
+            } else {
+                (marker.to_string(), marker.to_string())
+            };
+
+            let trimmed = content.trim();
+            if open.is_empty() || trimmed.is_empty() {
+                output.push_str(&content);
+                continue;
+            }
+            let leading = &content[..content.len() - content.trim_start().len()];
+            let trailing = &content[content.trim_end().len()..];
+            output.push_str(&format!("{}{}{}{}{}", leading, open, trimmed, close, trailing));
+        }
+    }
+    output
+}
+
+/// Width of one terminal column in the SVG export, in pixels
+const SVG_CELL_WIDTH: usize = 10;
+/// Height of one line in the SVG export, in pixels
+const SVG_LINE_HEIGHT: usize = 20;
+/// Font size of the SVG export's text, in pixels
+const SVG_FONT_SIZE: usize = 16;
+
+/// Incremental SVG export: runs are collected until the style or line changes, and the
+/// image size is only known once the last line is in
+struct SvgEncoder {
+    backgrounds: String,
+    texts: String,
+    /// Characters of the run being collected
+    run: Vec<StyledChar>,
+    row: usize,
+    col: usize,
+    max_cols: usize,
+}
+
+impl SvgEncoder {
+    fn new() -> Self {
+        Self {
+            backgrounds: String::new(),
+            texts: String::new(),
+            run: Vec::new(),
+            row: 0,
+            col: 0,
+            max_cols: 0,
+        }
+    }
+
+    fn push(&mut self, styled_char: &StyledChar) {
+        if styled_char.ch == '\n' {
+            self.flush_run();
+            self.max_cols = self.max_cols.max(self.col);
+            self.row += 1;
+            self.col = 0;
+            return;
+        }
+        if self.run.first().is_some_and(|first| first.style != styled_char.style) {
+            self.flush_run();
+        }
+        self.run.push(styled_char.clone());
+    }
 
-        // Foreground color
-        new_codes.push(fg_ansi_code(styled_char.style.fg));
+    /// Write the collected run's background rectangle and text
+    fn flush_run(&mut self) {
+        let Some(first) = self.run.first() else {
+            return;
+        };
+        let style = &first.style;
+        let cols: usize = self.run.iter().map(|c| c.ch.width().unwrap_or(1)).sum();
+        let (x, y) = (self.col * SVG_CELL_WIDTH, self.row * SVG_LINE_HEIGHT);
+        if let Some(bg) = css_color(style.bg) {
+            self.backgrounds.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                x, y, cols * SVG_CELL_WIDTH, SVG_LINE_HEIGHT, bg
+            ));
+        }
 
-        // Background color (only if not reset)
-        let bg_code = bg_ansi_code(styled_char.style.bg);
-        if bg_code != "49" {
-            new_codes.push(bg_code);
+        let decorations = decoration_names(style);
+        let decorated = !decorations.is_empty();
+        if cols > 0 && (decorated || self.run.iter().any(|c| !c.ch.is_whitespace())) {
+            let fill = css_color(style.fg).unwrap_or_else(|| svg_hex(theme::TEXT_PRIMARY));
+            let mut attrs = format!(
+                "x=\"{}\" y=\"{}\" fill=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\"",
+                x,
+                y + SVG_FONT_SIZE,
+                fill,
+                cols * SVG_CELL_WIDTH
+            );
+            if style.bold {
+                attrs.push_str(" font-weight=\"bold\"");
+            }
+            if style.italic {
+                attrs.push_str(" font-style=\"italic\"");
+            }
+            if decorated {
+                attrs.push_str(&format!(" text-decoration=\"{}\"", decorations.join(" ")));
+            }
+            if style.dim_level > 0 {
+                attrs.push_str(&format!(" opacity=\"{}\"", 1.0 - style.dim_level.min(3) as f32 * 0.25));
+            }
+            let mut content = String::new();
+            for styled_char in &self.run {
+                push_html_char(&mut content, styled_char.ch);
+            }
+            self.texts.push_str(&format!("<text {}>{}</text>\n", attrs, content));
         }
+        self.col += cols;
+        self.run.clear();
+    }
+
+    fn finish(mut self) -> String {
+        self.flush_run();
+        let max_cols = self.max_cols.max(self.col);
+        let (width, height) = (max_cols * SVG_CELL_WIDTH, (self.row + 1) * SVG_LINE_HEIGHT);
+        format!(
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+                "<rect width=\"100%\" height=\"100%\" fill=\"{bg}\"/>\n",
+                "{backgrounds}",
+                "<g font-family=\"monospace\" font-size=\"{font}\" xml:space=\"preserve\">\n",
+                "{texts}",
+                "</g>\n</svg>\n"
+            ),
+            w = width,
+            h = height,
+            bg = svg_hex(theme::BG_PRIMARY),
+            backgrounds = self.backgrounds,
+            font = SVG_FONT_SIZE,
+            texts = self.texts,
+        )
+    }
+}
+
+/// `#rrggbb` for one of the theme's colors
+fn svg_hex(color: Color) -> String {
+    css_color(color).unwrap_or_default()
+}
+
+/// Standalone SVG rendering: a background, a rectangle behind each run with a background
+/// color, and a `<text>` element per run stretched to exactly its columns so runs line up
+pub fn export_svg(text: &[StyledChar]) -> String {
+    let mut encoder = SvgEncoder::new();
+    for styled_char in text {
+        encoder.push(styled_char);
+    }
+    encoder.finish()
+}
+
+/// Buffers longer than this export in chunks with a progress readout, in the echo, HTML,
+/// SVG and RON formats; the other formats are quick enough to run in one go
+pub const PROGRESS_THRESHOLD: usize = 50_000;
+/// Characters encoded per step of a chunked export
+const EXPORT_CHUNK: usize = 8_192;
+
+/// Result of advancing a chunked export
+#[derive(Debug, PartialEq)]
+pub enum ExportStep {
+    /// Characters encoded so far out of the total
+    InProgress(usize, usize),
+    Done(String),
+    Failed(String),
+    Cancelled,
+}
 
-        // Bold
-        if let Some(bold) = bold_ansi_code(styled_char.style.bold) {
-            new_codes.push(bold.to_string());
+/// The formats that can be written a chunk at a time
+enum ChunkEncoder {
+    Echo(SgrEncoder),
+    Html(HtmlEncoder),
+    Svg(SvgEncoder),
+    Ron(RonEncoder),
+}
+
+impl ChunkEncoder {
+    fn push(&mut self, styled_char: &StyledChar) -> Result<()> {
+        match self {
+            ChunkEncoder::Echo(encoder) => encoder.push(styled_char),
+            ChunkEncoder::Html(encoder) => encoder.push(styled_char),
+            ChunkEncoder::Svg(encoder) => encoder.push(styled_char),
+            ChunkEncoder::Ron(encoder) => encoder.push(styled_char)?,
         }
+        Ok(())
+    }
 
-        // Italic
-        if let Some(italic) = italic_ansi_code(styled_char.style.italic) {
-            new_codes.push(italic.to_string());
+    fn finish(self) -> String {
+        match self {
+            ChunkEncoder::Echo(encoder) => format!(r#"echo -e "{}""#, encoder.finish()),
+            ChunkEncoder::Html(encoder) => encoder.finish(),
+            ChunkEncoder::Svg(encoder) => encoder.finish(),
+            ChunkEncoder::Ron(encoder) => encoder.finish(),
         }
+    }
+}
+
+/// Export produced a chunk at a time so the UI stays responsive and Esc can cancel it
+pub struct ProgressExport {
+    text: Vec<StyledChar>,
+    pos: usize,
+    encoder: Option<ChunkEncoder>,
+    /// What the status line calls the copied result; empty for the main export
+    what: &'static str,
+    cancelled: bool,
+}
 
-        // Underline
-        if let Some(underline) = underline_ansi_code(styled_char.style.underline) {
-            new_codes.push(underline.to_string());
+impl ProgressExport {
+    fn new(text: Vec<StyledChar>, encoder: ChunkEncoder, what: &'static str) -> Self {
+        Self {
+            text,
+            pos: 0,
+            encoder: Some(encoder),
+            what,
+            cancelled: false,
         }
+    }
+
+    /// `echo -e` command laid out as `options` says
+    pub fn echo(text: Vec<StyledChar>, options: ExportOptions) -> Self {
+        let encoder = SgrEncoder::new(options.escape.prefix(), push_echo_char, options);
+        Self::new(text, ChunkEncoder::Echo(encoder), "")
+    }
 
-        // Strikethrough
-        if let Some(strike) = strikethrough_ansi_code(styled_char.style.strikethrough) {
-            new_codes.push(strike.to_string());
+    /// HTML `<pre>` block, as `export_html`
+    pub fn html(text: Vec<StyledChar>) -> Self {
+        Self::new(text, ChunkEncoder::Html(HtmlEncoder::new()), "")
+    }
+
+    /// SVG image, as `export_svg`
+    pub fn svg(text: Vec<StyledChar>) -> Self {
+        Self::new(text, ChunkEncoder::Svg(SvgEncoder::new()), "")
+    }
+
+    /// RON document, as `export_ron_document`
+    pub fn ron(text: Vec<StyledChar>, settings: Option<DocumentSettings>) -> Result<Self> {
+        Ok(Self::new(text, ChunkEncoder::Ron(RonEncoder::new(settings)?), "RON"))
+    }
+
+    /// Stop at the next step
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    /// Encode the next chunk
+    pub fn step(&mut self) -> ExportStep {
+        if self.cancelled {
+            return ExportStep::Cancelled;
         }
+        let Some(encoder) = self.encoder.as_mut() else {
+            return ExportStep::Cancelled;
+        };
 
-        // Dim
-        if let Some(dim) = dim_ansi_code(styled_char.style.dim_level) {
-            new_codes.push(dim.to_string());
+        let end = (self.pos + EXPORT_CHUNK).min(self.text.len());
+        for styled_char in &self.text[self.pos..end] {
+            if let Err(e) = encoder.push(styled_char) {
+                self.encoder = None;
+                return ExportStep::Failed(e.to_string());
+            }
         }
+        self.pos = end;
 
-        // Only emit escape sequence if codes changed
-        if new_codes != current_codes {
-            // Reset first, then apply new codes
-            let codes = new_codes.join(";");
-            output.push_str(&format!(r#"\033[0;{}m"#, codes));
-            current_codes = new_codes;
+        if self.pos < self.text.len() {
+            return ExportStep::InProgress(self.pos, self.text.len());
+        }
+        match self.encoder.take() {
+            Some(encoder) => ExportStep::Done(encoder.finish()),
+            None => ExportStep::Cancelled,
         }
+    }
+}
+
+/// A chunked export of the selected format, if the buffer is long enough to need one
+pub fn chunked_export(app: &App) -> Option<ProgressExport> {
+    if app.source_edit.is_some() || app.text.len() <= PROGRESS_THRESHOLD {
+        return None;
+    }
+    let text = app.text.clone();
+    match app.export_format {
+        ExportFormat::Echo => Some(ProgressExport::echo(text, app.export_options)),
+        ExportFormat::Html => Some(ProgressExport::html(text)),
+        ExportFormat::Svg => Some(ProgressExport::svg(text)),
+        _ => None,
+    }
+}
 
-        // Escape special characters
-        match styled_char.ch {
-            '\n' => output.push_str(r#"\n"#),
-            '"' => output.push_str(r#"\""#),
-            '\\' => output.push_str(r#"\\"#),
-            '$' => output.push_str(r#"\$"#),
-            '`' => output.push_str(r#"\`"#),
-            '!' => output.push_str(r#"\!"#),
-            _ => output.push(styled_char.ch),
+/// Advance a running export, copying the result when it completes
+pub fn poll_export_job(app: &mut App) {
+    let Some(job) = app.export_job.as_mut() else {
+        return;
+    };
+    let what = job.what;
+    match job.step() {
+        ExportStep::InProgress(done, total) => {
+            let filled = done * 20 / total.max(1);
+            app.set_status(format!(
+                "Exporting [{}{}] {}% (Esc cancels)",
+                "█".repeat(filled),
+                "░".repeat(20 - filled),
+                done * 100 / total.max(1)
+            ));
+        }
+        ExportStep::Done(output) => {
+            app.export_job = None;
+            match set_clipboard_text(app, &output) {
+                Ok(target) => app.set_status(target.status(what)),
+                Err(e) => app.set_status(format!("✗ Copy failed: {}", e)),
+            }
+        }
+        ExportStep::Failed(e) => {
+            app.export_job = None;
+            app.set_status(format!("✗ Export failed: {}", e));
+        }
+        ExportStep::Cancelled => {
+            app.export_job = None;
+            app.set_status("Export cancelled");
         }
     }
+}
 
-    // Reset at the end
-    output.push_str(r#"\033[0m""#);
-    output
+/// Describe the byte length of the echo command, to check it against clipboard and `ARG_MAX` limits
+pub fn export_size_summary(text: &[StyledChar], options: ExportOptions) -> String {
+    let bytes = generate_echo_command_with(text, options).len();
+    let styled = text.iter().filter(|c| c.style != CharStyle::default()).count();
+    format!("Echo command: {} bytes ({} chars styled)", bytes, styled)
+}
+
+/// Generate a PowerShell `Write-Host` command for the styled text, downgraded for `profile`
+pub fn generate_powershell_command(text: &[StyledChar], profile: TerminalProfile) -> String {
+    if text.is_empty() {
+        return r#"Write-Host """#.to_string();
+    }
+
+    let downgraded: Vec<StyledChar> = text
+        .iter()
+        .map(|c| StyledChar::with_style(c.ch, profile.downgrade(&c.style)))
+        .collect();
+
+    // `e needs PowerShell 7; $([char]27) also works in Windows PowerShell 5
+    let body = encode_sgr_runs(&downgraded, "$([char]27)", push_powershell_char, ExportOptions::default());
+    format!(r#"Write-Host "{}""#, body)
+}
+
+/// Escape a character for a double-quoted PowerShell string
+fn push_powershell_char(output: &mut String, ch: char) {
+    // Escape special characters with the PowerShell backtick
+    match ch {
+        '\n' => output.push_str("`n"),
+        '\t' => output.push_str("`t"),
+        '"' => output.push_str("`\""),
+        '$' => output.push_str("`$"),
+        '`' => output.push_str("``"),
+        _ => output.push(ch),
+    }
 }
 
-/// Copy the echo command to clipboard
-pub fn copy_to_clipboard(app: &App) -> Result<()> {
-    let command = generate_echo_command(&app.text);
-    let mut clipboard = Clipboard::new()?;
-    clipboard.set_text(&command)?;
-    Ok(())
+/// Generate a Rust string literal containing the ANSI escape sequences
+pub fn export_rust_literal(text: &[StyledChar]) -> String {
+    if text.is_empty() {
+        return r#""""#.to_string();
+    }
+
+    let body = encode_sgr_runs(text, r#"\x1b"#, push_rust_char, ExportOptions::default());
+    format!(r#""{}""#, body)
+}
+
+/// Escape a character for a Rust string literal
+fn push_rust_char(output: &mut String, ch: char) {
+    // Escape characters that are special inside a Rust string literal
+    match ch {
+        '\n' => output.push_str(r#"\n"#),
+        '\r' => output.push_str(r#"\r"#),
+        '\t' => output.push_str(r#"\t"#),
+        '"' => output.push_str(r#"\""#),
+        '\\' => output.push_str(r#"\\"#),
+        _ => output.push(ch),
+    }
+}
+
+/// Generate a `printf '%b'` command; `%b` expands the escapes in its single-quoted argument
+pub fn export_printf(text: &[StyledChar]) -> String {
+    let body = encode_sgr_runs(text, r"\033", push_printf_char, ExportOptions::default());
+    format!("printf '%b' '{}'", body)
+}
+
+/// Escape a character for a single-quoted `printf '%b'` argument
+fn push_printf_char(output: &mut String, ch: char) {
+    match ch {
+        '\n' => output.push_str(r"\n"),
+        '\\' => output.push_str(r"\\"),
+        // Close the quotes, add an escaped quote, reopen
+        '\'' => output.push_str(r"'\''"),
+        _ => output.push(ch),
+    }
+}
+
+/// Generate a C string literal. Octal escapes stop after three digits, unlike `\x`
+/// which would swallow hex-looking text after it.
+pub fn export_c_string(text: &[StyledChar]) -> String {
+    let body = encode_sgr_runs(text, r"\033", push_c_char, ExportOptions::default());
+    format!(r#""{}""#, body)
+}
+
+/// Escape a character for a C or Python double-quoted string literal
+fn push_c_char(output: &mut String, ch: char) {
+    match ch {
+        '\n' => output.push_str(r"\n"),
+        '\r' => output.push_str(r"\r"),
+        '\t' => output.push_str(r"\t"),
+        '"' => output.push_str(r#"\""#),
+        '\\' => output.push_str(r"\\"),
+        _ => output.push(ch),
+    }
+}
+
+/// Generate a Python string literal
+pub fn export_python_string(text: &[StyledChar]) -> String {
+    let body = encode_sgr_runs(text, r"\x1b", push_c_char, ExportOptions::default());
+    format!(r#""{}""#, body)
+}
+
+/// Raw ANSI text with real ESC bytes, ready to `cat` or embed in a file
+pub fn generate_raw_ansi(text: &[StyledChar]) -> String {
+    encode_sgr_runs(text, "\x1b", push_raw_char, ExportOptions::default())
+}
+
+/// Write a character as is, except control characters that would act on the terminal
+/// (a stray ESC could start a sequence of its own), which become U+FFFD
+fn push_raw_char(output: &mut String, ch: char) {
+    match ch {
+        '\n' | '\t' => output.push(ch),
+        ch if ch.is_control() => output.push('\u{FFFD}'),
+        _ => output.push(ch),
+    }
+}
+
+/// OSC 52 sequence asking the terminal to put `text` on the system clipboard
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+/// Where copied text went
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClipboardTarget {
+    /// The system clipboard took it
+    System,
+    /// Sent to the terminal with OSC 52, which terminals without support drop silently
+    Terminal,
+}
+
+impl ClipboardTarget {
+    /// Status line for copying `what` (empty for the main export), only claiming a copy
+    /// the system clipboard confirmed
+    pub fn status(self, what: &str) -> String {
+        match (self, what.is_empty()) {
+            (ClipboardTarget::System, true) => "✓ Copied to clipboard!".to_string(),
+            (ClipboardTarget::System, false) => format!("✓ Copied {} to clipboard!", what),
+            (ClipboardTarget::Terminal, true) => "Sent to terminal clipboard (OSC 52)".to_string(),
+            (ClipboardTarget::Terminal, false) => format!("Sent {} to terminal clipboard (OSC 52)", what),
+        }
+    }
+}
+
+/// Put `text` on the clipboard, through the terminal with OSC 52 when asked to or when
+/// the system clipboard can't be reached (e.g. over SSH)
+pub fn set_clipboard_text(app: &App, text: &str) -> Result<ClipboardTarget> {
+    if !app.osc52_clipboard {
+        let copied = Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text));
+        if copied.is_ok() {
+            return Ok(ClipboardTarget::System);
+        }
+    }
+    // The sequence isn't drawn, so writing it between frames leaves the alternate screen intact
+    let mut stdout = std::io::stdout();
+    stdout.write_all(osc52_sequence(text).as_bytes())?;
+    stdout.flush()?;
+    Ok(ClipboardTarget::Terminal)
+}
+
+/// The styled buffer to export, refused while it holds RON or ANSI source being edited
+pub fn export_text(app: &App) -> Result<&[StyledChar]> {
+    if app.source_edit.is_some() {
+        return Err(anyhow!("Apply the source with Ctrl+U first"));
+    }
+    Ok(&app.text)
+}
+
+/// Copy the buffer in the selected export format to clipboard
+pub fn copy_to_clipboard(app: &App) -> Result<ClipboardTarget> {
+    let text = export_text(app)?;
+    let output = match app.export_format {
+        ExportFormat::Echo => generate_echo_command_with(text, app.export_options),
+        ExportFormat::Printf => export_printf(text),
+        ExportFormat::CString => export_c_string(text),
+        ExportFormat::PythonString => export_python_string(text),
+        ExportFormat::Html => export_html(text),
+        ExportFormat::Svg => export_svg(text),
+        ExportFormat::Markdown => export_markdown(text),
+        ExportFormat::Plain => export_plain(text),
+    };
+    set_clipboard_text(app, &output)
+}
+
+/// Copy the echo command for the rectangle spanned by the selection
+pub fn copy_region_to_clipboard(app: &App) -> Result<(usize, ClipboardTarget)> {
+    export_text(app)?;
+    let region = app.cropped_region();
+    let command = generate_echo_command_with(&region, app.export_options);
+    let target = set_clipboard_text(app, &command)?;
+    Ok((region.len(), target))
+}
+
+/// Copy the selection's (or whole buffer's) text without styling to clipboard
+pub fn copy_plain_to_clipboard(app: &App) -> Result<ClipboardTarget> {
+    let text = app.selected_plain_text().unwrap_or_else(|| app.plain_text());
+    set_clipboard_text(app, &text)
+}
+
+/// Copy raw ANSI text to clipboard
+pub fn copy_raw_to_clipboard(app: &App) -> Result<ClipboardTarget> {
+    let raw = generate_raw_ansi(export_text(app)?);
+    set_clipboard_text(app, &raw)
+}
+
+/// Copy the Rust string literal to clipboard
+pub fn copy_rust_literal_to_clipboard(app: &App) -> Result<ClipboardTarget> {
+    let literal = export_rust_literal(export_text(app)?);
+    set_clipboard_text(app, &literal)
+}
+
+/// Copy the PowerShell command to clipboard
+pub fn copy_powershell_to_clipboard(app: &App) -> Result<ClipboardTarget> {
+    let command = generate_powershell_command(export_text(app)?, app.terminal_profile);
+    set_clipboard_text(app, &command)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::app::CharStyle;
-    use ratatui::style::Color;
 
     #[test]
     fn test_generate_empty() {
@@ -100,14 +1119,44 @@ mod tests {
 
     #[test]
     fn test_generate_simple() {
-        let text: Vec<StyledChar> = vec![
-            StyledChar::new('H'),
-            StyledChar::new('i'),
-        ];
-        let result = generate_echo_command(&text);
-        assert!(result.starts_with(r#"echo -e ""#));
-        assert!(result.ends_with(r#"\033[0m""#));
-        assert!(result.contains("Hi"));
+        let app = App::from_text("Hi");
+        let result = generate_echo_command(&app.text);
+        assert_eq!(result, r#"echo -e "Hi""#);
+    }
+
+    #[test]
+    fn test_plain_runs_emit_no_codes() {
+        let mut app = App::from_text("ab cd");
+        app.text[1].style.bold = true;
+        let result = generate_echo_command(&app.text);
+        assert_eq!(result, r#"echo -e "a\033[1mb\033[0m cd""#);
+    }
+
+    #[test]
+    fn test_escape_styles() {
+        let mut app = App::from_text("ab\ncd");
+        app.text[1].style.fg = Color::Red;
+        for (escape, prefix) in [
+            (EscapeStyle::Octal, r"\033"),
+            (EscapeStyle::Hex, r"\x1b"),
+            (EscapeStyle::Bash, r"\e"),
+        ] {
+            let options = ExportOptions {
+                escape,
+                ..ExportOptions::default()
+            };
+            let expected = format!(r#"echo -e "a{p}[31mb{p}[0m\ncd""#, p = prefix);
+            assert_eq!(generate_echo_command_with(&app.text, options), expected);
+
+            // Chunked exports use the same escape
+            let mut job = ProgressExport::echo(app.text.clone(), options);
+            assert_eq!(job.step(), ExportStep::Done(expected.clone()));
+
+            // Every representation imports back to the same text
+            let parsed = crate::import::decode_import(&expected).unwrap().0;
+            assert!(parsed.iter().zip(&app.text).all(|(a, b)| a.ch == b.ch && a.style == b.style));
+            assert_eq!(parsed.len(), app.text.len());
+        }
     }
 
     #[test]
@@ -118,9 +1167,14 @@ mod tests {
                 bg: Color::Reset,
                 bold: true,
                 italic: false,
-                underline: false,
+                underline: UnderlineStyle::None,
+                overline: false,
                 strikethrough: false,
+                blink: false,
+                reverse: false,
+                hidden: false,
                 dim_level: 0,
+                link: None,
             }),
         ];
         let result = generate_echo_command(&text);
@@ -136,9 +1190,14 @@ mod tests {
                 bg: Color::Reset,
                 bold: false,
                 italic: true,
-                underline: true,
+                underline: UnderlineStyle::Single,
+                overline: false,
                 strikethrough: true,
+                blink: false,
+                reverse: false,
+                hidden: false,
                 dim_level: 0,
+                link: None,
             }),
         ];
         let result = generate_echo_command(&text);
@@ -149,15 +1208,612 @@ mod tests {
 
     #[test]
     fn test_generate_multiline() {
-        let text: Vec<StyledChar> = vec![
-            StyledChar::new('H'),
-            StyledChar::new('i'),
-            StyledChar::new('\n'),
-            StyledChar::new('!'),
+        let app = App::from_text("Hi\n!");
+        let result = generate_echo_command(&app.text);
+        assert_eq!(result, r#"echo -e "Hi\n\!""#); // Newline and history expansion are escaped
+    }
+
+    #[test]
+    fn test_dim_precedes_color() {
+        let dim_red = CharStyle {
+            fg: Color::Red,
+            dim_level: 2,
+            ..CharStyle::default()
+        };
+        let result = generate_echo_command(&[StyledChar::with_style('D', dim_red)]);
+        assert!(result.contains(r#"\033[2;31m"#));
+
+        let dim_rgb = CharStyle {
+            fg: Color::Rgb(10, 20, 30),
+            bold: true,
+            dim_level: 1,
+            ..CharStyle::default()
+        };
+        let result = generate_echo_command(&[StyledChar::with_style('D', dim_rgb)]);
+        assert!(result.contains(r#"\033[1;2;38;2;10;20;30m"#));
+    }
+
+    #[test]
+    fn test_generate_powershell() {
+        let app = App::from_text("a\"$\n");
+        let result = generate_powershell_command(&app.text, TerminalProfile::Standard);
+        assert_eq!(result, r#"Write-Host "a`"`$`n""#);
+    }
+
+    #[test]
+    fn test_conhost_drops_dim() {
+        let style = CharStyle {
+            fg: Color::Red,
+            dim_level: 2,
+            ..CharStyle::default()
+        };
+        let text = vec![StyledChar::with_style('x', style)];
+        let standard = generate_powershell_command(&text, TerminalProfile::Standard);
+        assert!(standard.contains("[2;31m"));
+        let conhost = generate_powershell_command(&text, TerminalProfile::Conhost);
+        assert!(conhost.contains("[31m") && !conhost.contains("[2;"));
+    }
+
+    #[test]
+    fn test_conhost_maps_bright_colors() {
+        let style = CharStyle {
+            fg: Color::LightGreen,
+            bg: Color::DarkGray,
+            ..CharStyle::default()
+        };
+        let downgraded = TerminalProfile::Conhost.downgrade(&style);
+        assert_eq!(downgraded.fg, Color::Green);
+        assert_eq!(downgraded.bg, Color::Black);
+
+        let text = vec![StyledChar::with_style('x', style)];
+        let conhost = generate_powershell_command(&text, TerminalProfile::Conhost);
+        assert!(conhost.contains("[32;40m"));
+    }
+
+    /// Unescape the body of a Rust string literal (only the escapes we emit)
+    fn unescape_rust_literal(literal: &str) -> String {
+        let inner = &literal[1..literal.len() - 1];
+        let mut out = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                assert_ne!(c, '"', "unescaped quote in {}", literal);
+                out.push(c);
+                continue;
+            }
+            match chars.next().unwrap() {
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                'x' => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    out.push(u8::from_str_radix(&hex, 16).unwrap() as char);
+                }
+                other => panic!("unexpected escape \\{}", other),
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_export_rust_literal() {
+        let red = CharStyle {
+            fg: Color::Red,
+            ..CharStyle::default()
+        };
+        let text: Vec<StyledChar> = "a\"b\\c\n"
+            .chars()
+            .map(|ch| StyledChar::with_style(ch, red.clone()))
+            .collect();
+        let literal = export_rust_literal(&text);
+        assert!(literal.starts_with('"') && literal.ends_with('"'));
+        assert!(literal.contains(r"\x1b[31m"));
+        assert!(literal.contains(r#"a\"b\\c\n"#));
+        assert_eq!(unescape_rust_literal(&literal), "\x1b[31ma\"b\\c\n\x1b[0m");
+    }
+
+    #[test]
+    fn test_export_rust_literal_empty() {
+        assert_eq!(export_rust_literal(&[]), r#""""#);
+    }
+
+    #[test]
+    fn test_export_size_matches_echo_command_length() {
+        let bold = CharStyle {
+            bold: true,
+            ..CharStyle::default()
+        };
+        let text: Vec<StyledChar> = "hi $there\n"
+            .chars()
+            .map(|ch| StyledChar::with_style(ch, bold.clone()))
+            .collect();
+        let expected = generate_echo_command(&text).len();
+        assert_eq!(
+            export_size_summary(&text, ExportOptions::default()),
+            format!("Echo command: {} bytes ({} chars styled)", expected, text.len())
+        );
+
+        // Plain characters don't count as styled
+        let mut mixed = text.clone();
+        mixed.push(StyledChar::new('!'));
+        assert!(export_size_summary(&mixed, ExportOptions::default()).ends_with("(10 chars styled)"));
+    }
+
+    #[test]
+    fn test_progress_export_matches_direct_export() {
+        let text: Vec<StyledChar> = (0..EXPORT_CHUNK * 2 + 10)
+            .map(|i| {
+                let mut c = StyledChar::new(if i % 7 == 0 { '$' } else { 'a' });
+                c.style.bold = i % 3 == 0;
+                c
+            })
+            .collect();
+        let mut job = ProgressExport::echo(text.clone(), ExportOptions::default());
+
+        assert_eq!(job.step(), ExportStep::InProgress(EXPORT_CHUNK, text.len()));
+        assert_eq!(job.step(), ExportStep::InProgress(EXPORT_CHUNK * 2, text.len()));
+        assert_eq!(job.step(), ExportStep::Done(generate_echo_command(&text)));
+    }
+
+    #[test]
+    fn test_progress_export_cancel() {
+        let text = vec![StyledChar::new('a'); EXPORT_CHUNK * 3];
+        let mut job = ProgressExport::echo(text, ExportOptions::default());
+        assert!(matches!(job.step(), ExportStep::InProgress(..)));
+        job.cancel();
+        assert_eq!(job.step(), ExportStep::Cancelled);
+    }
+
+    #[test]
+    fn test_progress_export_empty_buffer() {
+        let mut job = ProgressExport::echo(Vec::new(), ExportOptions::default());
+        assert_eq!(job.step(), ExportStep::Done(generate_echo_command(&[])));
+    }
+
+    /// Lines of bold and plain characters spanning a little over two export chunks
+    fn chunked_text() -> Vec<StyledChar> {
+        (0..EXPORT_CHUNK * 2 + 10)
+            .map(|i| {
+                let mut c = StyledChar::new(if i % 80 == 79 { '\n' } else { '<' });
+                c.style.bold = i % 3 == 0;
+                c
+            })
+            .collect()
+    }
+
+    /// Step `job` to completion, checking its progress, then cancel a fresh one midway
+    fn check_chunked_export(make_job: impl Fn() -> ProgressExport, expected: String) {
+        let total = chunked_text().len();
+        let mut job = make_job();
+        assert_eq!(job.step(), ExportStep::InProgress(EXPORT_CHUNK, total));
+        assert_eq!(job.step(), ExportStep::InProgress(EXPORT_CHUNK * 2, total));
+        assert_eq!(job.step(), ExportStep::Done(expected));
+
+        let mut job = make_job();
+        assert_eq!(job.step(), ExportStep::InProgress(EXPORT_CHUNK, total));
+        job.cancel();
+        assert_eq!(job.step(), ExportStep::Cancelled);
+    }
+
+    #[test]
+    fn test_progress_export_html() {
+        let text = chunked_text();
+        check_chunked_export(|| ProgressExport::html(text.clone()), export_html(&text));
+    }
+
+    #[test]
+    fn test_progress_export_svg() {
+        let text = chunked_text();
+        check_chunked_export(|| ProgressExport::svg(text.clone()), export_svg(&text));
+    }
+
+    #[test]
+    fn test_progress_export_ron() {
+        let text = chunked_text();
+        let settings = Some(DocumentSettings::from_app(&App::new()));
+        let expected = crate::import::export_ron_document(&text, settings.clone()).unwrap();
+        check_chunked_export(|| ProgressExport::ron(text.clone(), settings.clone()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_per_character_encoding() {
+        let options = ExportOptions {
+            encoding: RunEncoding::PerCharacter,
+            ..ExportOptions::default()
+        };
+        let red = CharStyle {
+            fg: Color::Red,
+            ..CharStyle::default()
+        };
+        let text: Vec<StyledChar> = "abc".chars().map(|ch| StyledChar::with_style(ch, red.clone())).collect();
+
+        let command = generate_echo_command_with(&text, options);
+        assert_eq!(command.matches(r#"\033[0;31m"#).count(), 3);
+
+        // Still parses back to the same styled text
+        let raw = encode_sgr_runs(&text, "\x1b", |output, ch| output.push(ch), options);
+        let parsed = crate::import::parse_ansi(&raw).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert!(parsed.iter().zip(&text).all(|(a, b)| a.ch == b.ch && a.style == b.style));
+
+        // Plain text at the end is already reset, so no second reset follows it
+        let mut text = text;
+        text.push(StyledChar::new('p'));
+        assert_eq!(
+            generate_echo_command_with(&text, options),
+            r#"echo -e "\033[0;31ma\033[0;31mb\033[0;31mc\033[0mp""#
+        );
+    }
+
+    #[test]
+    fn test_export_refused_while_editing_source() {
+        let mut app = App::from_text("ab");
+        assert!(export_text(&app).is_ok());
+        crate::import::enter_source_edit(&mut app, crate::app::SourceFormat::Ansi).unwrap();
+        assert!(export_text(&app).is_err());
+    }
+
+    #[test]
+    fn test_clipboard_status_only_confirms_system_copies() {
+        assert_eq!(ClipboardTarget::System.status("RON"), "✓ Copied RON to clipboard!");
+        assert_eq!(ClipboardTarget::Terminal.status("RON"), "Sent RON to terminal clipboard (OSC 52)");
+        assert_eq!(ClipboardTarget::Terminal.status(""), "Sent to terminal clipboard (OSC 52)");
+    }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("hello"), "\x1b]52;c;aGVsbG8=\x07");
+        assert_eq!(osc52_sequence(""), "\x1b]52;c;\x07");
+        // Escape sequences inside the payload are encoded, not passed through
+        assert_eq!(osc52_sequence("\x1b[31mA"), "\x1b]52;c;G1szMW1B\x07");
+    }
+
+    #[test]
+    fn test_delta_bold_dim_to_dim_only() {
+        let options = ExportOptions {
+            encoding: RunEncoding::Delta,
+            ..ExportOptions::default()
+        };
+        let bold_dim = CharStyle {
+            fg: Color::Red,
+            bold: true,
+            dim_level: 1,
+            ..CharStyle::default()
+        };
+        let dim = CharStyle {
+            fg: Color::Red,
+            dim_level: 1,
+            ..CharStyle::default()
+        };
+        let text = vec![
+            StyledChar::with_style('a', bold_dim),
+            StyledChar::with_style('b', dim),
+            StyledChar::new('c'),
         ];
-        let result = generate_echo_command(&text);
-        assert!(result.contains(r#"\n"#)); // Newline is escaped
-        assert!(result.starts_with(r#"echo -e ""#));
-        assert!(result.ends_with(r#"\033[0m""#));
+
+        let raw = encode_sgr_runs(&text, "\x1b", |output, ch| output.push(ch), options);
+        // Dropping bold clears dim too, so dim comes straight back
+        assert_eq!(raw, "\x1b[1;2;31ma\x1b[22;2mb\x1b[0mc");
+
+        let parsed = crate::import::parse_ansi(&raw).unwrap();
+        assert!(parsed.iter().zip(&text).all(|(a, b)| a.ch == b.ch && a.style == b.style));
+    }
+
+    #[test]
+    fn test_delta_dim_level_change_keeps_style() {
+        let options = ExportOptions {
+            encoding: RunEncoding::Delta,
+            ..ExportOptions::default()
+        };
+        let dim = |level| CharStyle {
+            fg: Color::Red,
+            dim_level: level,
+            ..CharStyle::default()
+        };
+        let text = vec![
+            StyledChar::with_style('a', dim(1)),
+            StyledChar::with_style('b', dim(2)),
+        ];
+
+        let raw = encode_sgr_runs(&text, "\x1b", |output, ch| output.push(ch), options);
+        // Both dim levels export as 2, so nothing is written between them
+        assert_eq!(raw, "\x1b[2;31mab\x1b[0m");
+    }
+
+    #[test]
+    fn test_transition_codes_only_changes() {
+        let red = CharStyle {
+            fg: Color::Red,
+            ..CharStyle::default()
+        };
+        let bold_red_italic = CharStyle {
+            bold: true,
+            italic: true,
+            ..red.clone()
+        };
+        assert_eq!(transition_codes(&red, &bold_red_italic), vec!["1", "3"]);
+        assert_eq!(transition_codes(&bold_red_italic, &red), vec!["22", "23"]);
+        assert!(transition_codes(&red, &red).is_empty());
+    }
+
+    #[test]
+    fn test_export_html_coalesces_runs() {
+        let red = CharStyle {
+            fg: Color::Red,
+            bold: true,
+            ..CharStyle::default()
+        };
+        let mut text: Vec<StyledChar> = "ab".chars().map(|ch| StyledChar::with_style(ch, red.clone())).collect();
+        text.extend("c\nd".chars().map(StyledChar::new));
+        text.push(StyledChar::with_style('e', CharStyle { dim_level: 2, underline: UnderlineStyle::Single, strikethrough: true, ..CharStyle::default() }));
+
+        assert_eq!(
+            export_html(&text),
+            concat!(
+                "<pre><span style=\"color:#cd0000;font-weight:bold\">ab</span>c\nd",
+                "<span style=\"text-decoration:underline line-through;opacity:0.5\">e</span></pre>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_export_html_escapes_and_round_trips() {
+        let styled = CharStyle {
+            fg: Color::Rgb(255, 136, 0),
+            bg: Color::Blue,
+            italic: true,
+            dim_level: 1,
+            ..CharStyle::default()
+        };
+        let text: Vec<StyledChar> = "<a & b>".chars().map(|ch| StyledChar::with_style(ch, styled.clone())).collect();
+        let html = export_html(&text);
+        assert!(html.contains("&lt;a &amp; b&gt;"));
+        assert_eq!(export_html(&[]), "<pre></pre>");
+
+        let parsed = crate::import::parse_html(&html).unwrap();
+        assert!(parsed.iter().zip(&text).all(|(a, b)| a.ch == b.ch && a.style == b.style));
+        assert_eq!(parsed.len(), text.len());
+
+        // Indexed colors go through the 256-color table
+        let indexed = [StyledChar::with_style('x', CharStyle { fg: Color::Indexed(208), ..CharStyle::default() })];
+        assert!(export_html(&indexed).contains("color:#ff8700"));
+    }
+
+    #[test]
+    fn test_export_html_round_trips_double_underline() {
+        let double = CharStyle {
+            underline: UnderlineStyle::Double,
+            overline: true,
+            ..CharStyle::default()
+        };
+        let text = vec![StyledChar::with_style('d', double), StyledChar::new('p')];
+        let parsed = crate::import::parse_html(&export_html(&text)).unwrap();
+        assert!(parsed.iter().zip(&text).all(|(a, b)| a.ch == b.ch && a.style == b.style));
+        assert_eq!(parsed.len(), text.len());
+    }
+
+    #[test]
+    fn test_export_svg_elements() {
+        let on_blue = CharStyle {
+            fg: Color::Red,
+            bg: Color::Blue,
+            bold: true,
+            ..CharStyle::default()
+        };
+        let mut text: Vec<StyledChar> = "hi ".chars().map(StyledChar::new).collect();
+        text.extend("<x>".chars().map(|ch| StyledChar::with_style(ch, on_blue.clone())));
+        text.extend("\n\nlast".chars().map(StyledChar::new));
+
+        let svg = export_svg(&text);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("viewBox=\"0 0 60 60\""));
+        // One page background plus the blue run's background
+        assert_eq!(svg.matches("<rect ").count(), 2);
+        assert!(svg.contains("<rect x=\"30\" y=\"0\" width=\"30\" height=\"20\" fill=\"#0000ee\"/>"));
+        // "hi ", "<x>" and "last"; the empty line has none
+        assert_eq!(svg.matches("<text ").count(), 3);
+        assert_eq!(svg.matches("</text>").count(), 3);
+        assert!(svg.contains("fill=\"#cd0000\" textLength=\"30\" lengthAdjust=\"spacingAndGlyphs\" font-weight=\"bold\">&lt;x&gt;</text>"));
+        assert!(svg.contains("<text x=\"0\" y=\"56\""));
+    }
+
+    fn styled_words(words: &[(&str, CharStyle)]) -> Vec<StyledChar> {
+        words
+            .iter()
+            .flat_map(|(word, style)| word.chars().map(|ch| StyledChar::with_style(ch, style.clone())).collect::<Vec<_>>())
+            .collect()
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        let bold = CharStyle { bold: true, fg: Color::Red, ..CharStyle::default() };
+        let italic = CharStyle { italic: true, ..CharStyle::default() };
+        let strike = CharStyle { strikethrough: true, bg: Color::Blue, ..CharStyle::default() };
+        let plain = CharStyle::default();
+        let text = styled_words(&[
+            ("bold ", bold.clone()),
+            ("italic", italic.clone()),
+            (" ", plain.clone()),
+            ("strike", strike),
+            ("\n2*3", plain),
+        ]);
+        // Colors are dropped and the space after "bold" stays outside the markers
+        assert_eq!(export_markdown(&text), "**bold** *italic* ~~strike~~\n2\\*3");
+
+        let both = CharStyle { bold: true, italic: true, ..CharStyle::default() };
+        let all = CharStyle { strikethrough: true, ..both.clone() };
+        let text = styled_words(&[("bolditalic", both), (" ", italic), ("x", all)]);
+        assert_eq!(export_markdown(&text), "***bolditalic*** ~~***x***~~");
+    }
+
+    #[test]
+    fn test_export_markdown_splits_runs_at_newlines() {
+        let bold = CharStyle { bold: true, ..CharStyle::default() };
+        let text = styled_words(&[("a\nb", bold)]);
+        assert_eq!(export_markdown(&text), "**a**\n**b**");
+    }
+
+    #[test]
+    fn test_export_plain() {
+        let bold = CharStyle { bold: true, fg: Color::Red, ..CharStyle::default() };
+        let text = styled_words(&[("ab\n", bold), ("c", CharStyle::default())]);
+        assert_eq!(export_plain(&text), "ab\nc");
+    }
+
+    #[test]
+    fn test_delta_encoding_is_incremental() {
+        let red = CharStyle {
+            fg: Color::Red,
+            ..CharStyle::default()
+        };
+        let bold_red = CharStyle {
+            bold: true,
+            ..red.clone()
+        };
+        let text = vec![
+            StyledChar::with_style('r', red),
+            StyledChar::with_style('b', bold_red),
+            StyledChar::new('p'),
+        ];
+        assert_eq!(generate_echo_command(&text), r#"echo -e "\033[31mr\033[1mb\033[0mp""#);
+
+        let full_resets = ExportOptions {
+            encoding: RunEncoding::ResetOnChange,
+            ..ExportOptions::default()
+        };
+        assert_eq!(
+            generate_echo_command_with(&text, full_resets),
+            r#"echo -e "\033[0;31mr\033[0;1;31mb\033[0mp""#
+        );
+    }
+
+    #[test]
+    fn test_delta_encoding_resets_when_shorter() {
+        let busy = CharStyle {
+            fg: Color::Red,
+            bold: true,
+            italic: true,
+            underline: UnderlineStyle::Single,
+            overline: false,
+            ..CharStyle::default()
+        };
+        let blue = CharStyle {
+            fg: Color::Blue,
+            ..CharStyle::default()
+        };
+        let text = vec![StyledChar::with_style('a', busy), StyledChar::with_style('b', blue)];
+        // "22;23;24;34" would be longer than starting over
+        assert_eq!(generate_echo_command(&text), r#"echo -e "\033[1;3;4;31ma\033[0;34mb\033[0m""#);
+    }
+
+    #[test]
+    fn test_generate_raw_ansi() {
+        let bold = CharStyle {
+            bold: true,
+            ..CharStyle::default()
+        };
+        let mut text: Vec<StyledChar> = "a\"$\\\n".chars().map(StyledChar::new).collect();
+        text.push(StyledChar::new('\x1b'));
+        text.extend("hi".chars().map(|ch| StyledChar::with_style(ch, bold.clone())));
+
+        let raw = generate_raw_ansi(&text);
+        assert!(raw.as_bytes().contains(&0x1b));
+        assert_eq!(raw, "a\"$\\\n\u{FFFD}\x1b[1mhi\x1b[0m");
+        assert!(raw.ends_with("\x1b[0m"));
+        assert_eq!(generate_raw_ansi(&[]), "");
+    }
+
+    fn bold_red_hi() -> Vec<StyledChar> {
+        let style = CharStyle {
+            fg: Color::Red,
+            bold: true,
+            ..CharStyle::default()
+        };
+        "Hi".chars().map(|ch| StyledChar::with_style(ch, style.clone())).collect()
+    }
+
+    #[test]
+    fn test_export_printf() {
+        assert_eq!(export_printf(&bold_red_hi()), r"printf '%b' '\033[1;31mHi\033[0m'");
+
+        let text: Vec<StyledChar> = "it's a\\b\n".chars().map(StyledChar::new).collect();
+        assert_eq!(export_printf(&text), r"printf '%b' 'it'\''s a\\b\n'");
+        assert_eq!(export_printf(&[]), "printf '%b' ''");
+    }
+
+    #[test]
+    fn test_export_c_string() {
+        assert_eq!(export_c_string(&bold_red_hi()), r#""\033[1;31mHi\033[0m""#);
+
+        let text: Vec<StyledChar> = "say \"a\\b\"\n".chars().map(StyledChar::new).collect();
+        assert_eq!(export_c_string(&text), r#""say \"a\\b\"\n""#);
+    }
+
+    #[test]
+    fn test_export_python_string() {
+        assert_eq!(export_python_string(&bold_red_hi()), r#""\x1b[1;31mHi\x1b[0m""#);
+
+        let text: Vec<StyledChar> = "'q'\t\"\n".chars().map(StyledChar::new).collect();
+        assert_eq!(export_python_string(&text), r#""'q'\t\"\n""#);
+    }
+
+    #[test]
+    fn test_export_blink() {
+        let blink = CharStyle {
+            blink: true,
+            ..CharStyle::default()
+        };
+        let text = vec![
+            StyledChar::with_style('a', blink.clone()),
+            StyledChar::with_style('b', CharStyle { bold: true, ..blink }),
+            StyledChar::new('c'),
+        ];
+        assert_eq!(generate_echo_command(&text), r#"echo -e "\033[5ma\033[1mb\033[0mc""#);
+    }
+
+    #[test]
+    fn test_export_reverse_and_hidden() {
+        let reverse = CharStyle {
+            reverse: true,
+            ..CharStyle::default()
+        };
+        let text = vec![
+            StyledChar::with_style('a', reverse.clone()),
+            StyledChar::with_style('b', CharStyle { hidden: true, ..reverse }),
+            StyledChar::new('c'),
+        ];
+        assert_eq!(generate_echo_command(&text), r#"echo -e "\033[7ma\033[8mb\033[0mc""#);
+    }
+
+    #[test]
+    fn test_export_double_underline_and_overline() {
+        let double = CharStyle {
+            underline: UnderlineStyle::Double,
+            ..CharStyle::default()
+        };
+        let text = vec![
+            StyledChar::with_style('a', double.clone()),
+            StyledChar::with_style('b', CharStyle { underline: UnderlineStyle::Single, overline: true, ..double }),
+            StyledChar::new('c'),
+        ];
+        assert_eq!(generate_echo_command(&text), r#"echo -e "\033[21ma\033[4;53mb\033[0mc""#);
+        assert!(export_html(&text[..1]).contains("text-decoration:underline;text-decoration-style:double"));
+    }
+
+    #[test]
+    fn test_export_osc8_link() {
+        let link = CharStyle {
+            link: Some("https://example.com".to_string()),
+            ..CharStyle::default()
+        };
+        let mut text = vec![StyledChar::with_style('a', link.clone())];
+        text.push(StyledChar::with_style('b', CharStyle { bold: true, ..link }));
+        text.push(StyledChar::new('c'));
+        assert_eq!(
+            generate_echo_command(&text),
+            r#"echo -e "\033]8;;https://example.com\033\\a\033[1mb\033]8;;\033\\\033[0mc""#
+        );
     }
 }
@@ -1,24 +1,60 @@
+use std::time::Instant;
+
 use ratatui::{layout::Rect, style::Color, Frame};
-use tachyonfx::{Duration, EffectManager, Interpolation, Motion, fx};
+use tachyonfx::{fx, CellFilter, Duration, EffectManager, Interpolation, Motion};
+
+use crate::colors::theme;
+
+/// How long without input before the ambient effect starts
+pub const AMBIENT_IDLE_AFTER: std::time::Duration = std::time::Duration::from_secs(20);
+
+/// Keys for effects that can be replaced or cancelled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FxKey {
+    #[default]
+    Ambient,
+}
 
 /// A wrapper for the effect manager to handle animations.
 pub struct FxManager {
-    effects: EffectManager<()>,
+    effects: EffectManager<FxKey>,
+    /// Animations hold still while the terminal is unfocused
+    paused: bool,
 }
 
 impl FxManager {
     pub fn new() -> Self {
         FxManager {
             effects: EffectManager::default(),
+            paused: false,
         }
     }
 
     /// Process and render effects on the frame buffer
     pub fn render(&mut self, frame: &mut Frame, area: Rect, elapsed: Duration) {
+        let elapsed = self.effective_elapsed(elapsed);
         self.effects
             .process_effects(elapsed, frame.buffer_mut(), area);
     }
 
+    /// Pause animations when the terminal loses focus and resume when it regains it
+    pub fn set_focused(&mut self, focused: bool) {
+        self.paused = !focused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Time to advance effects by: none while paused, so they resume where they stopped
+    fn effective_elapsed(&self, elapsed: Duration) -> Duration {
+        if self.paused {
+            Duration::from_millis(0)
+        } else {
+            elapsed
+        }
+    }
+
     /// Trigger the startup slide-in animation
     /// Exactly replicates statui's approach
     pub fn trigger_startup(&mut self) {
@@ -29,6 +65,97 @@ impl FxManager {
         let fx = fx::slide_in(Motion::UpToDown, 10, 0, c, timer);
         self.effects.add_effect(fx);
     }
+
+    /// Start a slow, endless hue shimmer on the panel borders
+    pub fn trigger_ambient(&mut self, area: Rect) {
+        let timer = (3000, Interpolation::SineInOut);
+        let shimmer = fx::hsl_shift_fg([40.0, 0.0, 15.0], timer);
+        let fx = fx::repeating(fx::ping_pong(shimmer))
+            .with_filter(CellFilter::FgColor(theme::BORDER_DEFAULT))
+            .with_area(area);
+        self.effects.add_unique_effect(FxKey::Ambient, fx);
+    }
+
+    /// Stop the ambient effect immediately
+    pub fn cancel_ambient(&mut self) {
+        self.effects.cancel_unique_effect(FxKey::Ambient);
+    }
 }
 
+/// Tracks user inactivity to decide when the ambient effect should run
+pub struct IdleTracker {
+    last_input: Instant,
+    ambient_running: bool,
+}
 
+impl IdleTracker {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_input: now,
+            ambient_running: false,
+        }
+    }
+
+    /// Record input at `now`; returns true if a running ambient effect should be cancelled
+    pub fn input(&mut self, now: Instant) -> bool {
+        self.last_input = now;
+        std::mem::take(&mut self.ambient_running)
+    }
+
+    /// Should the ambient effect start now?
+    pub fn should_start_ambient(&self, now: Instant) -> bool {
+        !self.ambient_running && now.duration_since(self.last_input) >= AMBIENT_IDLE_AFTER
+    }
+
+    /// Record that the ambient effect has started
+    pub fn mark_ambient_started(&mut self) {
+        self.ambient_running = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ambient_starts_after_idle_period() {
+        let start = Instant::now();
+        let idle = IdleTracker::new(start);
+        assert!(!idle.should_start_ambient(start + Duration::from_secs(19)));
+        assert!(idle.should_start_ambient(start + AMBIENT_IDLE_AFTER));
+    }
+
+    #[test]
+    fn test_ambient_starts_once_and_input_cancels() {
+        let start = Instant::now();
+        let mut idle = IdleTracker::new(start);
+        let later = start + Duration::from_secs(25);
+        idle.mark_ambient_started();
+        assert!(!idle.should_start_ambient(later));
+
+        // Input cancels the running effect and restarts the idle clock
+        assert!(idle.input(later));
+        assert!(!idle.input(later));
+        assert!(!idle.should_start_ambient(later + Duration::from_secs(10)));
+        assert!(idle.should_start_ambient(later + Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn test_focus_pauses_and_resumes() {
+        let mut fx_manager = FxManager::new();
+        let frame = tachyonfx::Duration::from_millis(16);
+        assert!(!fx_manager.is_paused());
+        assert_eq!(fx_manager.effective_elapsed(frame), frame);
+
+        fx_manager.set_focused(false);
+        assert!(fx_manager.is_paused());
+        assert_eq!(fx_manager.effective_elapsed(frame), tachyonfx::Duration::from_millis(0));
+
+        // Repeated focus-out events don't stack
+        fx_manager.set_focused(false);
+        fx_manager.set_focused(true);
+        assert!(!fx_manager.is_paused());
+        assert_eq!(fx_manager.effective_elapsed(frame), frame);
+    }
+}
@@ -1,12 +1,15 @@
-//! Import functionality for ANSI escape codes and RON format
+//! Import functionality for ANSI escape codes, RON format and styled HTML
 
-use crate::app::{App, CharStyle, StyledChar};
+use crate::app::{App, CharStyle, SourceFormat, StyledChar, UnderlineStyle, MAX_TAB_WIDTH};
+use crate::colors::from_hex;
+use crate::export::{export_text, generate_ansi_source, set_clipboard_text, ClipboardTarget};
 use anyhow::{anyhow, Result};
 use arboard::Clipboard;
 use pest::Parser;
 use pest_derive::Parser;
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
+use std::io::Read;
 
 #[derive(Parser)]
 #[grammar = "ansi.pest"]
@@ -20,8 +23,21 @@ pub struct SerializableStyle {
     pub bold: bool,
     pub italic: bool,
     pub underline: bool,
+    #[serde(default)]
+    pub double_underline: bool,
+    #[serde(default)]
+    pub overline: bool,
     pub strikethrough: bool,
+    /// Missing from files saved before blink was supported
+    #[serde(default)]
+    pub blink: bool,
+    #[serde(default)]
+    pub reverse: bool,
+    #[serde(default)]
+    pub hidden: bool,
     pub dim_level: u8,
+    #[serde(default)]
+    pub link: Option<String>,
 }
 
 /// Serializable color representation
@@ -55,10 +71,44 @@ pub struct SerializableChar {
     pub style: SerializableStyle,
 }
 
+/// Current RON document version; version 2 added the optional settings block
+pub const DOCUMENT_VERSION: u8 = 2;
+
+/// Editing context a document carries so it opens the way its author left it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSettings {
+    pub tab_width: usize,
+    /// Style new text is typed in
+    pub default_style: SerializableStyle,
+    /// Horizontal movement wraps across line ends
+    pub cursor_wrap: bool,
+}
+
+impl DocumentSettings {
+    /// Capture the app's current editing context
+    pub fn from_app(app: &App) -> Self {
+        DocumentSettings {
+            tab_width: app.tab_width,
+            default_style: (&app.current_style()).into(),
+            cursor_wrap: app.cursor_wrap,
+        }
+    }
+
+    /// Restore the editing context on the app
+    pub fn apply(&self, app: &mut App) {
+        app.tab_width = self.tab_width.clamp(1, MAX_TAB_WIDTH);
+        app.set_current_style(&self.default_style.clone().into());
+        app.cursor_wrap = self.cursor_wrap;
+    }
+}
+
 /// Serializable document for RON export
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StyledDocument {
     pub version: u8,
+    /// Missing in version 1 documents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<DocumentSettings>,
     pub chars: Vec<SerializableChar>,
 }
 
@@ -121,9 +171,15 @@ impl From<&CharStyle> for SerializableStyle {
             bg: style.bg.into(),
             bold: style.bold,
             italic: style.italic,
-            underline: style.underline,
+            underline: style.underline.is_underlined(),
+            double_underline: style.underline == UnderlineStyle::Double,
+            overline: style.overline,
             strikethrough: style.strikethrough,
+            blink: style.blink,
+            reverse: style.reverse,
+            hidden: style.hidden,
             dim_level: style.dim_level,
+            link: style.link.clone(),
         }
     }
 }
@@ -135,9 +191,18 @@ impl From<SerializableStyle> for CharStyle {
             bg: style.bg.into(),
             bold: style.bold,
             italic: style.italic,
-            underline: style.underline,
+            underline: match (style.underline, style.double_underline) {
+                (_, true) => UnderlineStyle::Double,
+                (true, false) => UnderlineStyle::Single,
+                (false, false) => UnderlineStyle::None,
+            },
+            overline: style.overline,
             strikethrough: style.strikethrough,
+            blink: style.blink,
+            reverse: style.reverse,
+            hidden: style.hidden,
             dim_level: style.dim_level,
+            link: style.link,
         }
     }
 }
@@ -164,14 +229,22 @@ struct ParseState {
     bg: Color,
     bold: bool,
     italic: bool,
-    underline: bool,
+    underline: UnderlineStyle,
+    overline: bool,
     strikethrough: bool,
+    blink: bool,
+    reverse: bool,
+    hidden: bool,
     dim: bool,
+    link: Option<String>,
 }
 
 impl ParseState {
+    /// SGR reset; an open hyperlink isn't part of SGR and stays open
     fn reset(&mut self) {
+        let link = self.link.take();
         *self = Self::default();
+        self.link = link;
     }
 
     fn to_char_style(&self) -> CharStyle {
@@ -181,9 +254,38 @@ impl ParseState {
             bold: self.bold,
             italic: self.italic,
             underline: self.underline,
+            overline: self.overline,
             strikethrough: self.strikethrough,
+            blink: self.blink,
+            reverse: self.reverse,
+            hidden: self.hidden,
             dim_level: if self.dim { 1 } else { 0 },
+            link: self.link.clone(),
+        }
+    }
+}
+
+/// Apply a parameter with colon-separated sub-parameters (`4:2`, `38:2::r:g:b`), which belong to it alone
+fn apply_sgr_subparams(state: &mut ParseState, group: &[u32]) {
+    match group {
+        // Underline style; curly, dotted and dashed (3-5) show as a single underline
+        [4, 0, ..] => state.underline = UnderlineStyle::None,
+        [4, 2, ..] => state.underline = UnderlineStyle::Double,
+        [4, ..] => state.underline = UnderlineStyle::Single,
+        [38 | 48, rest @ ..] => {
+            let color = match *rest {
+                [5, index] => Color::Indexed(index as u8),
+                // T.416 puts a color space id before the components, usually left empty
+                [2, _, r, g, b, ..] | [2, r, g, b] => Color::Rgb(r as u8, g as u8, b as u8),
+                _ => return,
+            };
+            if group[0] == 38 {
+                state.fg = color;
+            } else {
+                state.bg = color;
+            }
         }
+        _ => {}
     }
 }
 
@@ -198,14 +300,22 @@ fn apply_sgr_param(state: &mut ParseState, params: &[u32], index: &mut usize) {
         1 => state.bold = true,
         2 => state.dim = true,
         3 => state.italic = true,
-        4 => state.underline = true,
+        4 => state.underline = UnderlineStyle::Single,
+        // Slow and rapid blink look the same here
+        5 | 6 => state.blink = true,
+        7 => state.reverse = true,
+        8 => state.hidden = true,
         9 => state.strikethrough = true,
+        21 => state.underline = UnderlineStyle::Double,
         22 => {
             state.bold = false;
             state.dim = false;
         }
         23 => state.italic = false,
-        24 => state.underline = false,
+        24 => state.underline = UnderlineStyle::None,
+        25 => state.blink = false,
+        27 => state.reverse = false,
+        28 => state.hidden = false,
         29 => state.strikethrough = false,
         // Standard foreground colors (30-37)
         30 => state.fg = Color::Black,
@@ -228,15 +338,13 @@ fn apply_sgr_param(state: &mut ParseState, params: &[u32], index: &mut usize) {
                             state.fg = Color::Indexed(params[*index] as u8);
                         }
                     }
-                    2 => {
+                    2 if *index + 3 < params.len() => {
                         // RGB mode
-                        if *index + 3 < params.len() {
-                            let r = params[*index + 1] as u8;
-                            let g = params[*index + 2] as u8;
-                            let b = params[*index + 3] as u8;
-                            state.fg = Color::Rgb(r, g, b);
-                            *index += 3;
-                        }
+                        let r = params[*index + 1] as u8;
+                        let g = params[*index + 2] as u8;
+                        let b = params[*index + 3] as u8;
+                        state.fg = Color::Rgb(r, g, b);
+                        *index += 3;
                     }
                     _ => {}
                 }
@@ -264,21 +372,21 @@ fn apply_sgr_param(state: &mut ParseState, params: &[u32], index: &mut usize) {
                             state.bg = Color::Indexed(params[*index] as u8);
                         }
                     }
-                    2 => {
+                    2 if *index + 3 < params.len() => {
                         // RGB mode
-                        if *index + 3 < params.len() {
-                            let r = params[*index + 1] as u8;
-                            let g = params[*index + 2] as u8;
-                            let b = params[*index + 3] as u8;
-                            state.bg = Color::Rgb(r, g, b);
-                            *index += 3;
-                        }
+                        let r = params[*index + 1] as u8;
+                        let g = params[*index + 2] as u8;
+                        let b = params[*index + 3] as u8;
+                        state.bg = Color::Rgb(r, g, b);
+                        *index += 3;
                     }
                     _ => {}
                 }
             }
         }
         49 => state.bg = Color::Reset,
+        53 => state.overline = true,
+        55 => state.overline = false,
         // Bright foreground colors (90-97)
         90 => state.fg = Color::DarkGray,
         91 => state.fg = Color::LightRed,
@@ -303,11 +411,16 @@ fn apply_sgr_param(state: &mut ParseState, params: &[u32], index: &mut usize) {
 
 /// Parse ANSI-styled text into StyledChars
 pub fn parse_ansi(input: &str) -> Result<Vec<StyledChar>> {
+    parse_ansi_with(input, &mut ParseState::default())
+}
+
+/// Parse ANSI text starting from `state`, leaving in it the style in effect at the end,
+/// so a style can carry over from one chunk of a file to the next
+fn parse_ansi_with(input: &str, state: &mut ParseState) -> Result<Vec<StyledChar>> {
     let pairs = AnsiParser::parse(Rule::ansi_text, input)
         .map_err(|e| anyhow!("Failed to parse ANSI: {}", e))?;
 
     let mut result = Vec::new();
-    let mut state = ParseState::default();
 
     for pair in pairs {
         for inner in pair.into_inner() {
@@ -326,20 +439,35 @@ pub fn parse_ansi(input: &str) -> Result<Vec<StyledChar>> {
                     };
                     result.push(StyledChar::with_style(ch, state.to_char_style()));
                 }
+                Rule::osc8_sequence => {
+                    let uri = inner.into_inner().next().map_or("", |uri| uri.as_str());
+                    state.link = (!uri.is_empty()).then(|| uri.to_string());
+                }
                 Rule::escape_sequence => {
                     // Find the sgr_params inside the escape sequence
                     for seq_inner in inner.into_inner() {
                         if seq_inner.as_rule() == Rule::sgr_params {
-                            let params: Vec<u32> = seq_inner
+                            // Each parameter with its sub-parameters; an empty one counts as 0
+                            let groups: Vec<Vec<u32>> = seq_inner
                                 .into_inner()
                                 .filter(|p| p.as_rule() == Rule::param)
-                                .filter_map(|p| p.as_str().parse().ok())
+                                .filter_map(|p| {
+                                    p.as_str()
+                                        .split(':')
+                                        .map(|n| if n.is_empty() { Some(0) } else { n.parse().ok() })
+                                        .collect()
+                                })
                                 .collect();
+                            let params: Vec<u32> = groups.iter().map(|group| group[0]).collect();
 
                             // Apply all parameters
                             let mut i = 0;
                             while i < params.len() {
-                                apply_sgr_param(&mut state, &params, &mut i);
+                                if groups[i].len() > 1 {
+                                    apply_sgr_subparams(state, &groups[i]);
+                                } else {
+                                    apply_sgr_param(state, &params, &mut i);
+                                }
                                 i += 1;
                             }
                             
@@ -360,8 +488,14 @@ pub fn parse_ansi(input: &str) -> Result<Vec<StyledChar>> {
 
 /// Export styled text to RON format
 pub fn export_ron(text: &[StyledChar]) -> Result<String> {
+    export_ron_document(text, None)
+}
+
+/// Export styled text to RON format with an optional settings block
+pub fn export_ron_document(text: &[StyledChar], settings: Option<DocumentSettings>) -> Result<String> {
     let doc = StyledDocument {
-        version: 1,
+        version: DOCUMENT_VERSION,
+        settings,
         chars: text.iter().map(|c| c.into()).collect(),
     };
 
@@ -369,12 +503,267 @@ pub fn export_ron(text: &[StyledChar]) -> Result<String> {
         .map_err(|e| anyhow!("Failed to serialize to RON: {}", e))
 }
 
+/// Line break and indent of an entry in the document's `chars` list
+const RON_CHAR_INDENT: &str = "\n        ";
+
+/// RON document written a character at a time for chunked exports, matching
+/// `export_ron_document`. Each character is pretty-printed on its own with line breaks
+/// that carry the list's indent, so a newline inside a `ch` literal stays as it is.
+pub struct RonEncoder {
+    output: String,
+    /// Everything after the character list
+    tail: String,
+    empty: bool,
+}
+
+impl RonEncoder {
+    pub fn new(settings: Option<DocumentSettings>) -> Result<Self> {
+        let document = export_ron_document(&[], settings)?;
+        // The list comes last, so a link in the settings can't be mistaken for it
+        let (head, tail) = document
+            .rsplit_once("chars: [")
+            .ok_or_else(|| anyhow!("Failed to serialize to RON: no character list"))?;
+        Ok(Self {
+            output: format!("{}chars: [", head),
+            tail: tail.to_string(),
+            empty: true,
+        })
+    }
+
+    pub fn push(&mut self, styled_char: &StyledChar) -> Result<()> {
+        let config = ron::ser::PrettyConfig::default().new_line(RON_CHAR_INDENT.to_string());
+        let entry = ron::ser::to_string_pretty(&SerializableChar::from(styled_char), config)
+            .map_err(|e| anyhow!("Failed to serialize to RON: {}", e))?;
+        self.output.push_str(RON_CHAR_INDENT);
+        self.output.push_str(&entry);
+        self.output.push(',');
+        self.empty = false;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> String {
+        if !self.empty {
+            self.output.push_str("\n    ");
+        }
+        self.output.push_str(&self.tail);
+        self.output
+    }
+}
+
 /// Import styled text from RON format
 pub fn import_ron(input: &str) -> Result<Vec<StyledChar>> {
+    Ok(import_ron_document(input)?.0)
+}
+
+/// Import styled text and any settings block from RON format
+pub fn import_ron_document(input: &str) -> Result<(Vec<StyledChar>, Option<DocumentSettings>)> {
     let doc: StyledDocument =
         ron::from_str(input).map_err(|e| anyhow!("Failed to parse RON: {}", e))?;
+    if let Some(settings) = &doc.settings {
+        if !(1..=MAX_TAB_WIDTH).contains(&settings.tab_width) {
+            return Err(anyhow!(
+                "Tab width {} is outside 1-{}",
+                settings.tab_width,
+                MAX_TAB_WIDTH
+            ));
+        }
+    }
+
+    Ok((doc.chars.into_iter().map(|c| c.into()).collect(), doc.settings))
+}
+
+/// Content types recognized when importing from the clipboard or a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Ron,
+    Html,
+    Echo,
+    Ansi,
+}
+
+impl ImportFormat {
+    /// Short name for status messages
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportFormat::Ron => "RON",
+            ImportFormat::Html => "HTML",
+            ImportFormat::Echo => "echo cmd",
+            ImportFormat::Ansi => "ANSI",
+        }
+    }
+}
+
+/// Detect the format of imported content; anything unrecognized is treated as ANSI
+pub fn detect_format(input: &str) -> ImportFormat {
+    if is_ron_format(input) {
+        ImportFormat::Ron
+    } else if is_html_format(input) {
+        ImportFormat::Html
+    } else if strip_echo_wrapper(input).len() != input.len() {
+        ImportFormat::Echo
+    } else {
+        ImportFormat::Ansi
+    }
+}
+
+/// Detect styled HTML such as a `<pre>` block or inline-styled spans copied from a web page
+pub fn is_html_format(input: &str) -> bool {
+    let lower = input.trim_start().to_ascii_lowercase();
+    lower.starts_with('<') && (lower.contains("<pre") || lower.contains("<span style"))
+}
+
+/// Apply the declarations of an inline `style` attribute on top of `base`
+fn parse_css_style(css: &str, base: &CharStyle) -> CharStyle {
+    let mut style = base.clone();
+    let mut double_underline = None;
+    for declaration in css.split(';') {
+        let Some((property, value)) = declaration.split_once(':') else {
+            continue;
+        };
+        let property = property.trim().to_ascii_lowercase();
+        let value = value.trim().to_ascii_lowercase();
+        match property.as_str() {
+            "color" => style.fg = from_hex(&value).unwrap_or(style.fg),
+            "background-color" | "background" => style.bg = from_hex(&value).unwrap_or(style.bg),
+            "font-weight" => {
+                style.bold = value == "bold"
+                    || value == "bolder"
+                    || value.parse::<u16>().is_ok_and(|w| w >= 600)
+            }
+            "font-style" => style.italic = value == "italic" || value == "oblique",
+            "text-decoration" | "text-decoration-line" => {
+                style.underline = if !value.contains("underline") {
+                    UnderlineStyle::None
+                } else if value.contains("double") {
+                    UnderlineStyle::Double
+                } else {
+                    UnderlineStyle::Single
+                };
+                style.overline = value.contains("overline");
+                style.strikethrough = value.contains("line-through");
+            }
+            "text-decoration-style" => double_underline = Some(value == "double"),
+            // Dim levels are exported as opacity in quarters
+            "opacity" => {
+                if let Ok(opacity) = value.parse::<f32>() {
+                    style.dim_level = ((1.0 - opacity.clamp(0.0, 1.0)) * 4.0).round().min(3.0) as u8;
+                }
+            }
+            _ => {}
+        }
+    }
+    // The line style may come before or after the lines it applies to
+    if let Some(double) = double_underline.filter(|_| style.underline.is_underlined()) {
+        style.underline = if double { UnderlineStyle::Double } else { UnderlineStyle::Single };
+    }
+    style
+}
+
+/// Decode an HTML character reference without its `&` and `;`
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Value of `attr` in the inside of a start tag, e.g. `span style="color:#cd0000"`
+fn tag_attribute<'a>(tag: &'a str, attr: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let needle = format!("{}=", attr);
+    let mut search = 0;
+    while let Some(found) = lower[search..].find(&needle) {
+        let start = search + found;
+        let preceded_by_space = lower[..start].ends_with(char::is_whitespace);
+        let rest = &tag[start + needle.len()..];
+        if preceded_by_space {
+            return match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => rest[1..].split(quote).next(),
+                _ => rest.split(char::is_whitespace).next(),
+            };
+        }
+        search = start + needle.len();
+    }
+    None
+}
+
+/// Parse styled HTML back into characters, reading each span's inline `style` attribute
+/// Other tags are ignored except `<br>`, and `<style>`/`<script>` contents are skipped
+pub fn parse_html(input: &str) -> Result<Vec<StyledChar>> {
+    let mut result = Vec::new();
+    let mut styles = vec![CharStyle::default()];
+    let mut rest = input;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+        } else if c == '<' {
+            let end = rest
+                .find('>')
+                .ok_or_else(|| anyhow!("Unclosed HTML tag"))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            let name = tag
+                .trim_start_matches('/')
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or("")
+                .to_ascii_lowercase();
+            let closing = tag.starts_with('/');
+            match name.as_str() {
+                "span" if closing && styles.len() > 1 => {
+                    styles.pop();
+                }
+                "span" if closing => {}
+                "span" => {
+                    let base = styles.last().cloned().unwrap_or_default();
+                    let style = match tag_attribute(tag, "style") {
+                        Some(css) => parse_css_style(css, &base),
+                        None => base,
+                    };
+                    styles.push(style);
+                }
+                "br" => result.push(StyledChar::new('\n')),
+                "style" | "script" if !closing => {
+                    let close = format!("</{}", name);
+                    let lower = rest.to_ascii_lowercase();
+                    rest = lower.find(&close).map_or("", |end| &rest[end..]);
+                }
+                _ => {}
+            }
+        } else {
+            let mut ch = c;
+            let mut len = c.len_utf8();
+            if c == '&' {
+                if let Some(end) = rest[1..].find(';').filter(|&end| end <= 10) {
+                    if let Some(decoded) = decode_entity(&rest[1..end + 1]) {
+                        ch = decoded;
+                        len = end + 2;
+                    }
+                }
+            }
+            rest = &rest[len..];
+            if ch == '\r' {
+                continue;
+            }
+            let style = styles.last().cloned().unwrap_or_default();
+            result.push(StyledChar { ch, style });
+        }
+    }
 
-    Ok(doc.chars.into_iter().map(|c| c.into()).collect())
+    Ok(result)
 }
 
 /// Detect if input is RON format (starts with opening paren or struct name)
@@ -404,8 +793,7 @@ pub fn strip_echo_wrapper(input: &str) -> &str {
     ];
     
     for prefix in prefixes {
-        if trimmed.starts_with(prefix) {
-            let after_prefix = &trimmed[prefix.len()..];
+        if let Some(after_prefix) = trimmed.strip_prefix(prefix) {
             // Find the matching closing quote
             let quote_char = prefix.chars().last().unwrap();
             
@@ -432,35 +820,214 @@ pub fn strip_echo_wrapper(input: &str) -> &str {
     input
 }
 
-/// Import from clipboard - auto-detect format (RON vs ANSI)
-pub fn import_from_clipboard(app: &mut App) -> Result<String> {
-    let mut clipboard = Clipboard::new()?;
-    let content = clipboard.get_text()?;
+/// Decode imported content into styled characters - auto-detect format (RON, HTML or ANSI)
+/// Returns the characters, the detected format and a RON document's settings
+pub fn decode_import(content: &str) -> Result<(Vec<StyledChar>, ImportFormat, Option<DocumentSettings>)> {
+    let format = detect_format(content);
+    match format {
+        ImportFormat::Ron => {
+            let (chars, settings) = import_ron_document(content)?;
+            Ok((chars, format, settings))
+        }
+        ImportFormat::Html => Ok((parse_html(content)?, format, None)),
+        ImportFormat::Echo | ImportFormat::Ansi => {
+            Ok((parse_ansi(strip_echo_wrapper(content))?, format, None))
+        }
+    }
+}
+
+/// Inputs larger than this many bytes load in chunks with a progress readout
+pub const IMPORT_PROGRESS_THRESHOLD: usize = 256 * 1024;
+/// Approximate bytes parsed per step of a chunked import
+const IMPORT_CHUNK: usize = 16 * 1024;
+
+/// ANSI import parsed a few lines at a time, so a large file shows up as it loads.
+/// Chunks end on newlines, which never occur inside an escape sequence, and the
+/// parse state carries over so styles can span chunks.
+pub struct ProgressImport {
+    input: String,
+    pos: usize,
+    state: ParseState,
+    chunk_size: usize,
+}
 
-    let (chars, format_name) = if is_ron_format(&content) {
-        (import_ron(&content)?, "RON")
+impl ProgressImport {
+    /// Start importing `content`, which should be ANSI or an echo command
+    pub fn new(content: &str) -> Self {
+        Self {
+            input: strip_echo_wrapper(content).to_string(),
+            pos: 0,
+            state: ParseState::default(),
+            chunk_size: IMPORT_CHUNK,
+        }
+    }
+
+    /// Bytes parsed so far and the total
+    pub fn progress(&self) -> (usize, usize) {
+        (self.pos, self.input.len())
+    }
+
+    /// Parse the next chunk, or return None once everything is parsed
+    pub fn step(&mut self) -> Result<Option<Vec<StyledChar>>> {
+        if self.pos >= self.input.len() {
+            return Ok(None);
+        }
+        let rest = &self.input[self.pos..];
+        // Searching bytes is safe: a newline byte is never part of a multibyte char
+        let from = self.chunk_size.min(rest.len());
+        let end = match rest.as_bytes()[from..].iter().position(|&b| b == b'\n') {
+            Some(newline) => from + newline + 1,
+            None => rest.len(),
+        };
+        let chars = parse_ansi_with(&rest[..end], &mut self.state)?;
+        self.pos += end;
+        Ok(Some(chars))
+    }
+}
+
+/// Append the next chunk of a running import to the buffer
+pub fn poll_import_job(app: &mut App) {
+    let Some(job) = app.import_job.as_mut() else {
+        return;
+    };
+    match job.step() {
+        Ok(Some(chars)) => {
+            let (done, total) = job.progress();
+            app.text.extend(chars);
+            if done < total {
+                let filled = done * 20 / total.max(1);
+                app.set_status(format!(
+                    "Loading [{}{}] {}% (Esc stops)",
+                    "█".repeat(filled),
+                    "░".repeat(20 - filled),
+                    done * 100 / total.max(1)
+                ));
+            }
+        }
+        Ok(None) => {
+            app.import_job = None;
+            app.set_status(format!("✓ Loaded {} chars", app.text.len()));
+        }
+        Err(e) => {
+            app.import_job = None;
+            app.set_status(format!("✗ Load stopped: {}", e));
+        }
+    }
+}
+
+/// Replace the buffer with loaded file content, parsing large ANSI input in chunks.
+/// Returns the characters loaded right away, which is none while a chunked load runs
+pub fn load_content(app: &mut App, content: &str) -> Result<usize> {
+    let large_ansi = content.len() > IMPORT_PROGRESS_THRESHOLD
+        && matches!(detect_format(content), ImportFormat::Ansi | ImportFormat::Echo);
+    if large_ansi {
+        // Show the file as it parses instead of freezing until it's all read
+        app.replace_document(Vec::new());
+        app.import_job = Some(ProgressImport::new(content));
+        return Ok(0);
+    }
+    let (chars, _, settings) = decode_import(content)?;
+    app.replace_document(chars);
+    if let Some(settings) = settings {
+        settings.apply(app);
+    }
+    Ok(app.text.len())
+}
+
+/// Decode raw file or stdin bytes as UTF-8
+/// Invalid sequences become U+FFFD and a leading byte order mark is dropped
+pub fn decode_bytes(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Read a file, or stdin when `path` is `-`, as UTF-8 text
+pub fn read_input(path: &str) -> Result<String> {
+    let bytes = if path == "-" {
+        let mut buf = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut buf)
+            .map_err(|e| anyhow!("Failed to read stdin: {}", e))?;
+        buf
     } else {
-        // Try to strip echo wrapper if present
-        let stripped = strip_echo_wrapper(&content);
-        let was_echo = stripped.len() != content.len();
-        let format = if was_echo { "echo cmd" } else { "ANSI" };
-        (parse_ansi(stripped)?, format)
+        std::fs::read(path).map_err(|e| anyhow!("Failed to read {}: {}", path, e))?
     };
+    Ok(decode_bytes(&bytes))
+}
 
-    let char_count = chars.len();
-    app.text = chars;
-    app.cursor_pos = app.text.len();
+/// Put imported characters into the buffer, either replacing it or inserting at the cursor
+fn apply_import(app: &mut App, chars: Vec<StyledChar>, append: bool) {
+    if append {
+        app.insert_chars(chars);
+    } else {
+        app.text = chars;
+        app.cursor_pos = app.text.len();
+        app.clear_selection();
+        app.dirty = true;
+    }
+}
+
+/// Replace the buffer with its RON or ANSI source as plain text
+pub fn enter_source_edit(app: &mut App, format: SourceFormat) -> Result<()> {
+    let source = match format {
+        SourceFormat::Ron => export_ron(&app.text)?,
+        SourceFormat::Ansi => generate_ansi_source(&app.text),
+    };
+    app.text = source.chars().map(StyledChar::new).collect();
+    app.cursor_pos = 0;
+    app.scroll_offset = 0;
     app.clear_selection();
+    app.search_matches.clear();
+    app.source_edit = Some(format);
+    Ok(())
+}
 
-    Ok(format!("Imported {} chars ({})", char_count, format_name))
+/// Parse the edited source back into the styled buffer
+/// On a parse error the source stays in the buffer so it can be fixed
+pub fn commit_source_edit(app: &mut App) -> Result<usize> {
+    let Some(format) = app.source_edit else {
+        return Err(anyhow!("Not editing source"));
+    };
+    let source = app.plain_text();
+    let chars = match format {
+        SourceFormat::Ron => import_ron(&source)?,
+        SourceFormat::Ansi => parse_ansi(&source)?,
+    };
+    let count = chars.len();
+    app.source_edit = None;
+    apply_import(app, chars, false);
+    Ok(count)
 }
 
-/// Export to RON and copy to clipboard
-pub fn export_ron_to_clipboard(app: &App) -> Result<()> {
-    let ron_str = export_ron(&app.text)?;
+/// Import from clipboard - auto-detect format (RON vs ANSI)
+/// When `append` is set the content is inserted at the cursor instead of replacing the buffer
+pub fn import_from_clipboard(app: &mut App, append: bool) -> Result<String> {
     let mut clipboard = Clipboard::new()?;
-    clipboard.set_text(&ron_str)?;
-    Ok(())
+    let content = clipboard.get_text()?;
+    if append && app.source_edit.is_some() {
+        return Err(anyhow!("Apply the source with Ctrl+U before inserting"));
+    }
+
+    let (chars, format, settings) = decode_import(&content)?;
+    // A replaced buffer is styled text again, not source
+    if !append {
+        app.source_edit = None;
+    }
+    let char_count = chars.len();
+    apply_import(app, chars, append);
+    // A replaced buffer takes on the document's editing context
+    if let Some(settings) = settings.filter(|_| !append) {
+        settings.apply(app);
+    }
+
+    let verb = if append { "Appended" } else { "Imported" };
+    Ok(format!("{} {} chars ({})", verb, char_count, format.label()))
+}
+
+/// Export to RON and copy to clipboard
+pub fn export_ron_to_clipboard(app: &App) -> Result<ClipboardTarget> {
+    let ron_str = export_ron_document(export_text(app)?, Some(DocumentSettings::from_app(app)))?;
+    set_clipboard_text(app, &ron_str)
 }
 
 #[cfg(test)]
@@ -507,9 +1074,14 @@ mod tests {
                     bg: Color::Blue,
                     bold: true,
                     italic: false,
-                    underline: true,
+                    underline: UnderlineStyle::Single,
+                    overline: false,
                     strikethrough: false,
+                    blink: false,
+                    reverse: false,
+                    hidden: false,
                     dim_level: 0,
+                    link: None,
                 },
             ),
             StyledChar::with_style('B', CharStyle::default()),
@@ -609,6 +1181,58 @@ mod tests {
         assert_eq!(result[6].ch, 'L');
     }
 
+    #[test]
+    fn test_decode_import_formats() {
+        let (chars, format, _) = decode_import(r#"echo -e "\033[31mHi\033[0m""#).unwrap();
+        assert_eq!(format, ImportFormat::Echo);
+        assert_eq!(chars.len(), 2);
+        let (_, format, _) = decode_import("\x1b[1mB").unwrap();
+        assert_eq!(format, ImportFormat::Ansi);
+        let ron_str = export_ron(&chars).unwrap();
+        let (chars, format, _) = decode_import(&ron_str).unwrap();
+        assert_eq!(format, ImportFormat::Ron);
+        assert_eq!(chars[0].style.fg, Color::Red);
+    }
+
+    #[test]
+    fn test_apply_import_replace() {
+        let mut app = App::from_text("old");
+        let (chars, _, _) = decode_import("new!").unwrap();
+        apply_import(&mut app, chars, false);
+        let text: String = app.text.iter().map(|c| c.ch).collect();
+        assert_eq!(text, "new!");
+        assert_eq!(app.cursor_pos, 4);
+    }
+
+    #[test]
+    fn test_apply_import_append_at_cursor() {
+        let mut app = App::from_text("ad");
+        app.cursor_pos = 1;
+        let (chars, _, _) = decode_import("\x1b[31mbc").unwrap();
+        apply_import(&mut app, chars, true);
+        let text: String = app.text.iter().map(|c| c.ch).collect();
+        assert_eq!(text, "abcd");
+        assert_eq!(app.cursor_pos, 3);
+        assert_eq!(app.text[1].style.fg, Color::Red);
+        assert_eq!(app.text[3].style.fg, Color::Reset);
+    }
+
+    #[test]
+    fn test_decode_bytes_strips_bom() {
+        let decoded = decode_bytes(b"\xEF\xBB\xBF\x1b[31mHi");
+        let chars = parse_ansi(&decoded).unwrap();
+        assert_eq!(chars.len(), 2);
+        assert_eq!(chars[0].ch, 'H');
+        assert_eq!(chars[0].style.fg, Color::Red);
+    }
+
+    #[test]
+    fn test_decode_bytes_replaces_invalid() {
+        let decoded = decode_bytes(b"a\xFF\xFEb");
+        assert_eq!(decoded, "a\u{FFFD}\u{FFFD}b");
+        assert_eq!(parse_ansi(&decoded).unwrap().len(), 4);
+    }
+
     #[test]
     fn test_parse_multiline_with_style() {
         // Test parsing multiline with ANSI styling
@@ -618,4 +1242,378 @@ mod tests {
         assert_eq!(result[3].ch, '\n');
         assert_eq!(result[4].style.fg, Color::Red); // Style persists after newline
     }
+
+    #[test]
+    fn test_source_edit_commit_round_trip() {
+        let red = CharStyle { fg: Color::Red, ..Default::default() };
+        let mut app = App::from_styled(vec![
+            StyledChar::with_style('a', red),
+            StyledChar::new('b'),
+        ]);
+
+        enter_source_edit(&mut app, SourceFormat::Ansi).unwrap();
+        assert_eq!(app.plain_text(), r"\e[31ma\e[0mb");
+
+        // Recolor the first run by hand
+        app.text[4] = StyledChar::new('2');
+        assert_eq!(commit_source_edit(&mut app).unwrap(), 2);
+        assert_eq!(app.source_edit, None);
+        assert_eq!(app.plain_text(), "ab");
+        assert_eq!(app.text[0].style.fg, Color::Green);
+        assert_eq!(app.text[1].style.fg, Color::Reset);
+    }
+
+    #[test]
+    fn test_source_edit_commit_error_keeps_source() {
+        let mut app = App::from_text("hi");
+        enter_source_edit(&mut app, SourceFormat::Ron).unwrap();
+        let closing = app.text.pop().unwrap();
+        let source = app.plain_text();
+
+        assert!(commit_source_edit(&mut app).is_err());
+        assert_eq!(app.source_edit, Some(SourceFormat::Ron));
+        assert_eq!(app.plain_text(), source);
+
+        // Fixing the source lets the commit through
+        app.text.push(closing);
+        assert_eq!(commit_source_edit(&mut app).unwrap(), 2);
+        assert_eq!(app.plain_text(), "hi");
+    }
+
+    #[test]
+    fn test_version_2_document_applies_settings() {
+        let mut author = App::from_text("a\tb");
+        author.tab_width = 4;
+        author.cursor_wrap = false;
+        author.current_fg = Color::Magenta;
+        author.current_bold = true;
+        let ron_str =
+            export_ron_document(&author.text, Some(DocumentSettings::from_app(&author))).unwrap();
+        assert!(ron_str.contains("version: 2"));
+
+        let (chars, _, settings) = decode_import(&ron_str).unwrap();
+        let mut app = App::from_styled(chars);
+        settings.unwrap().apply(&mut app);
+        assert_eq!(app.tab_width, 4);
+        assert!(!app.cursor_wrap);
+        assert_eq!(app.current_fg, Color::Magenta);
+        assert!(app.current_bold);
+        assert_eq!(app.fg_color_index, 6);
+        assert_eq!(app.rendered_dimensions(), (5, 1));
+    }
+
+    #[test]
+    fn test_out_of_range_tab_width_rejected() {
+        let mut author = App::from_text("a\tb");
+        author.tab_width = 1 << 40;
+        let ron_str =
+            export_ron_document(&author.text, Some(DocumentSettings::from_app(&author))).unwrap();
+        assert!(decode_import(&ron_str).is_err());
+
+        // Settings built by hand are clamped when applied
+        let mut settings = DocumentSettings::from_app(&App::new());
+        settings.tab_width = 0;
+        let mut app = App::new();
+        settings.apply(&mut app);
+        assert_eq!(app.tab_width, 1);
+    }
+
+    #[test]
+    fn test_version_1_document_loads_with_defaults() {
+        let ron_str = "(version: 1, chars: [(ch: 'x', style: (fg: Red, bg: Reset, bold: false, \
+                       italic: false, underline: false, strikethrough: false, dim_level: 0))])";
+        let (chars, format, settings) = decode_import(ron_str).unwrap();
+        assert_eq!(format, ImportFormat::Ron);
+        assert!(settings.is_none());
+        assert_eq!(chars[0].style.fg, Color::Red);
+
+        // Documents without settings don't write a settings block
+        assert!(!export_ron(&chars).unwrap().contains("settings"));
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format("(chars: [])"), ImportFormat::Ron);
+        assert_eq!(detect_format("<pre>hi</pre>"), ImportFormat::Html);
+        assert_eq!(detect_format("  <span style=\"color:#cd0000\">x</span>"), ImportFormat::Html);
+        assert_eq!(detect_format("echo -e \"x\""), ImportFormat::Echo);
+        assert_eq!(detect_format("a < b <pre"), ImportFormat::Ansi);
+        assert_eq!(detect_format("plain"), ImportFormat::Ansi);
+    }
+
+    #[test]
+    fn test_parse_html_snippet() {
+        let html = concat!(
+            "<pre style=\"background-color:#000000\">",
+            "<span style=\"color:#cd0000;font-weight:bold\">a&lt;</span>",
+            "<span style=\"color:#ff8800;background-color:#0000ee;font-style:italic;",
+            "text-decoration:underline line-through;opacity:0.5\">b</span>",
+            "\n&amp;&#33;<br>c",
+            "</pre>"
+        );
+        let (chars, format, _) = decode_import(html).unwrap();
+        assert_eq!(format, ImportFormat::Html);
+        let plain: String = chars.iter().map(|c| c.ch).collect();
+        assert_eq!(plain, "a<b\n&!\nc");
+
+        assert_eq!(chars[0].style.fg, Color::Red);
+        assert!(chars[0].style.bold);
+        assert_eq!(chars[1].style.fg, Color::Red);
+
+        let b = &chars[2].style;
+        assert_eq!(b.fg, Color::Rgb(255, 136, 0));
+        assert_eq!(b.bg, Color::Blue);
+        assert!(b.italic && b.underline.is_underlined() && b.strikethrough && !b.bold);
+        assert_eq!(b.dim_level, 2);
+
+        // Text outside spans keeps the default style
+        assert_eq!(chars[4].style, CharStyle::default());
+    }
+
+    #[test]
+    fn test_ron_encoder_matches_one_shot_export() {
+        let mut app = App::from_text("a\n'b");
+        app.text[0].style.fg = Color::Red;
+        app.text[2].style.link = Some("chars: [".to_string());
+        app.tab_width = 4;
+        for settings in [None, Some(DocumentSettings::from_app(&app))] {
+            for text in [&app.text[..], &[]] {
+                let mut encoder = RonEncoder::new(settings.clone()).unwrap();
+                for styled_char in text {
+                    encoder.push(styled_char).unwrap();
+                }
+                assert_eq!(encoder.finish(), export_ron_document(text, settings.clone()).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_html_rejects_unclosed_tag() {
+        assert!(parse_html("<pre>ok<span").is_err());
+    }
+
+    #[test]
+    fn test_chunked_import_matches_one_shot() {
+        let input = "plain\n\x1b[1;31mbold red\nstill red\x1b[0m\n\x1b[44mblue bg\nand more\n\x1b[0mdone";
+        let one_shot = parse_ansi(input).unwrap();
+
+        let mut job = ProgressImport::new(input);
+        job.chunk_size = 4;
+        let mut chunked = Vec::new();
+        let mut steps = 0;
+        while let Some(chars) = job.step().unwrap() {
+            chunked.extend(chars);
+            steps += 1;
+        }
+
+        assert!(steps > 3);
+        assert_eq!(job.progress(), (input.len(), input.len()));
+        assert_eq!(chunked.len(), one_shot.len());
+        assert!(chunked.iter().zip(&one_shot).all(|(a, b)| a.ch == b.ch && a.style == b.style));
+        // "still red" sits in a later chunk than the code that set it
+        let still = chunked.iter().position(|c| c.ch == 's').unwrap();
+        assert_eq!(chunked[still].style.fg, Color::Red);
+        assert!(chunked[still].style.bold);
+    }
+
+    #[test]
+    fn test_parse_blink() {
+        let result = parse_ansi("\x1b[5ma\x1b[6mb\x1b[25mc").unwrap();
+        assert!(result[0].style.blink);
+        assert!(result[1].style.blink);
+        assert!(!result[2].style.blink);
+    }
+
+    #[test]
+    fn test_ron_roundtrip_blink() {
+        let blink = CharStyle {
+            blink: true,
+            ..CharStyle::default()
+        };
+        let chars = vec![StyledChar::with_style('*', blink)];
+        let imported = import_ron(&export_ron(&chars).unwrap()).unwrap();
+        assert!(imported[0].style.blink);
+    }
+
+    #[test]
+    fn test_parse_reverse_and_hidden() {
+        let result = parse_ansi("\x1b[7;8ma\x1b[27mb\x1b[28mc").unwrap();
+        assert!(result[0].style.reverse && result[0].style.hidden);
+        assert!(!result[1].style.reverse && result[1].style.hidden);
+        assert!(!result[2].style.reverse && !result[2].style.hidden);
+    }
+
+    #[test]
+    fn test_ron_roundtrip_reverse_and_hidden() {
+        let style = CharStyle {
+            reverse: true,
+            hidden: true,
+            ..CharStyle::default()
+        };
+        let chars = vec![StyledChar::with_style('*', style)];
+        let imported = import_ron(&export_ron(&chars).unwrap()).unwrap();
+        assert!(imported[0].style.reverse);
+        assert!(imported[0].style.hidden);
+    }
+
+    #[test]
+    fn test_parse_double_underline_and_overline() {
+        let result = parse_ansi("\x1b[21ma\x1b[4mb\x1b[4:2;53mc\x1b[24;55md").unwrap();
+        assert_eq!(result[0].style.underline, UnderlineStyle::Double);
+        assert_eq!(result[1].style.underline, UnderlineStyle::Single);
+        assert_eq!(result[2].style.underline, UnderlineStyle::Double);
+        assert!(result[2].style.overline);
+        assert_eq!(result[3].style.underline, UnderlineStyle::None);
+        assert!(!result[3].style.overline);
+
+        let chars = import_ron(&export_ron(&result).unwrap()).unwrap();
+        assert_eq!(chars[0].style.underline, UnderlineStyle::Double);
+        assert_eq!(chars[1].style.underline, UnderlineStyle::Single);
+        assert!(chars[2].style.overline);
+    }
+
+    #[test]
+    fn test_parse_colon_truecolor() {
+        let result = parse_ansi("\x1b[38:2::255:100:0ma\x1b[48:2:1:2:3;4:3mb\x1b[38:5:208mc").unwrap();
+        assert_eq!(result[0].style.fg, Color::Rgb(255, 100, 0));
+        assert_eq!(result[1].style.fg, Color::Rgb(255, 100, 0));
+        assert_eq!(result[1].style.bg, Color::Rgb(1, 2, 3));
+        assert_eq!(result[1].style.underline, UnderlineStyle::Single);
+        assert_eq!(result[2].style.fg, Color::Indexed(208));
+    }
+
+    #[test]
+    fn test_parse_skips_non_sgr_csi() {
+        let result = parse_ansi("\x1b[2J\x1b[10;5H\x1b[31mred\x1b[?25l\x1b[K\x1b[mok\x1b[1A").unwrap();
+        let text: String = result.iter().map(|c| c.ch).collect();
+        assert_eq!(text, "redok");
+        assert!(result[..3].iter().all(|c| c.style.fg == Color::Red));
+        // A bare `ESC[m` resets
+        assert!(result[3..].iter().all(|c| c.style == CharStyle::default()));
+    }
+
+    #[test]
+    fn test_parse_osc8_link() {
+        let input = "see \x1b]8;;https://example.com\x1b\\\x1b[1mhere\x1b[0m\x1b]8;;\x1b\\.";
+        let result = parse_ansi(input).unwrap();
+        let text: String = result.iter().map(|c| c.ch).collect();
+        assert_eq!(text, "see here.");
+        assert_eq!(result[3].style.link, None);
+        // The SGR reset inside the link doesn't close it
+        assert!(result[4..8].iter().all(|c| c.style.link.as_deref() == Some("https://example.com")));
+        assert_eq!(result[8].style.link, None);
+
+        // Back out through the raw and echo exports
+        let styles = |chars: &[StyledChar]| chars.iter().map(|c| (c.ch, c.style.clone())).collect::<Vec<_>>();
+        let reparsed = parse_ansi(&crate::export::generate_raw_ansi(&result)).unwrap();
+        assert_eq!(styles(&reparsed), styles(&result));
+        let echo = crate::export::generate_echo_command(&result);
+        let body = echo.strip_prefix("echo -e \"").unwrap().strip_suffix('"').unwrap();
+        assert_eq!(styles(&parse_ansi(body).unwrap()), styles(&result));
+
+        let chars = import_ron(&export_ron(&result).unwrap()).unwrap();
+        assert_eq!(chars[4].style.link.as_deref(), Some("https://example.com"));
+        assert_eq!(chars[0].style.link, None);
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use crate::export::generate_echo_command;
+    use proptest::prelude::*;
+
+    /// Inputs from the unit tests above, used as seeds for the generators
+    const SEED_CORPUS: &[&str] = &[
+        "Hello",
+        "\x1b[1mBold\x1b[0m",
+        "\x1b[31mRed\x1b[0m",
+        "\x1b[1;31;44mText\x1b[0m",
+        "\\033[31mRed\\033[0m",
+        "\\x1b[1;32mBoldGreen\\x1b[0m",
+        "\\e[44mBlue\\e[0m",
+        r#"Line1\nLine2"#,
+        r#"\033[31mRed\nLine\033[0m"#,
+    ];
+
+    /// SGR parameter lists the parser understands
+    fn sgr_params() -> impl Strategy<Value = String> {
+        let simple = prop::sample::select(vec![
+            0u32, 1, 2, 3, 4, 9, 22, 23, 24, 29, 30, 31, 32, 33, 34, 35, 36, 37, 39, 40, 41, 42,
+            43, 44, 45, 46, 47, 49, 90, 91, 92, 93, 94, 95, 96, 97, 100, 101, 102, 103, 104,
+            105, 106, 107,
+        ])
+        .prop_map(|p| p.to_string());
+        let indexed = (prop::sample::select(vec![38u32, 48]), any::<u8>())
+            .prop_map(|(base, i)| format!("{};5;{}", base, i));
+        let rgb = (prop::sample::select(vec![38u32, 48]), any::<(u8, u8, u8)>())
+            .prop_map(|(base, (r, g, b))| format!("{};2;{};{};{}", base, r, g, b));
+        prop::collection::vec(prop_oneof![4 => simple, 1 => indexed, 1 => rgb], 0..4)
+            .prop_map(|params| params.join(";"))
+    }
+
+    /// Well-formed ANSI text: plain runs interleaved with SGR sequences
+    fn well_formed() -> impl Strategy<Value = String> {
+        let intro = prop::sample::select(vec!["\x1b[", "\\033[", "\\x1b[", "\\x1B[", "\\e["]);
+        let sgr = (intro, sgr_params()).prop_map(|(intro, params)| format!("{}{}m", intro, params));
+        let text = "[a-zA-Z0-9 \n]{0,8}";
+        prop::collection::vec(prop_oneof![sgr, text.prop_map(String::from)], 0..12)
+            .prop_map(|parts| parts.concat())
+    }
+
+    /// Arbitrary fragments biased towards escape-sequence syntax
+    fn fragments() -> impl Strategy<Value = String> {
+        let fragment = prop_oneof![
+            prop::sample::select(vec![
+                "\x1b", "\x1b[", "\\033[", "\\x1b[", "\\e[", "\\", "\\n", "[", ";", "m", "38;2;",
+                "48;5;", "99999999999", "\x07",
+            ])
+            .prop_map(String::from),
+            "[0-9]{1,4}",
+            any::<char>().prop_map(String::from),
+        ];
+        prop::collection::vec(fragment, 0..24).prop_map(|parts| parts.concat())
+    }
+
+    fn seeded(strategy: impl Strategy<Value = String>) -> impl Strategy<Value = String> {
+        (prop::sample::select(SEED_CORPUS), strategy)
+            .prop_map(|(seed, tail)| format!("{}{}", seed, tail))
+    }
+
+    /// Compare two buffers by character and style
+    fn same_buffer(a: &[StyledChar], b: &[StyledChar]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.ch == y.ch && x.style == y.style)
+    }
+
+    proptest! {
+        #[test]
+        fn parse_never_panics(input in prop_oneof![fragments(), seeded(fragments())]) {
+            let _ = parse_ansi(&input);
+        }
+
+        #[test]
+        fn parse_arbitrary_strings_never_panics(input in any::<String>()) {
+            let _ = parse_ansi(&input);
+        }
+
+        #[test]
+        fn well_formed_input_parses(input in prop_oneof![well_formed(), seeded(well_formed())]) {
+            prop_assert!(parse_ansi(&input).is_ok());
+        }
+
+        #[test]
+        fn echo_export_roundtrips(input in well_formed()) {
+            let parsed = parse_ansi(&input).unwrap();
+            let command = generate_echo_command(&parsed);
+            let reparsed = parse_ansi(strip_echo_wrapper(&command)).unwrap();
+            prop_assert!(same_buffer(&parsed, &reparsed), "{:?} -> {}", input, command);
+        }
+    }
+
+    #[test]
+    fn seed_corpus_parses() {
+        for seed in SEED_CORPUS {
+            assert!(parse_ansi(seed).is_ok(), "{:?}", seed);
+        }
+    }
 }
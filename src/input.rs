@@ -1,11 +1,113 @@
-use crate::app::{App, Mode, Panel};
-use crate::colors::{color_index_from_key, COLOR_PALETTE};
-use crate::export::copy_to_clipboard;
-use crate::import::{export_ron_to_clipboard, import_from_clipboard};
+use crate::app::{
+    App, ColorApply, DuplicateAudit, Mode, Panel, PromptKind, SourceFormat, TouchedFields,
+    UnderlineStyle, LIGHTNESS_STEP, TEMPERATURE_STEP,
+};
+use crate::colors::{color_display_string, MIN_CONTRAST};
+use crate::export::{
+    chunked_export, copy_plain_to_clipboard, copy_powershell_to_clipboard, copy_raw_to_clipboard,
+    copy_region_to_clipboard, copy_rust_literal_to_clipboard, copy_to_clipboard,
+    export_size_summary, ProgressExport, RunEncoding, PROGRESS_THRESHOLD,
+};
+use crate::import::{
+    commit_source_edit, enter_source_edit, export_ron_to_clipboard, import_from_clipboard,
+    DocumentSettings,
+};
+use crate::palette::{copy_palette_to_clipboard, PALETTE_ROW_LEN};
+use crate::presets::save_presets;
+use crate::recent::open_recent;
+use crate::recovery::discard_recovery;
+use crate::transform::run_transform;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::Alignment;
 
 /// Handle key events and update app state
 pub fn handle_key_event(app: &mut App, key: KeyEvent) {
+    // Esc cancels a running export; exporting again would only restart it
+    if let Some(job) = app.export_job.as_mut() {
+        match key.code {
+            KeyCode::Esc => {
+                job.cancel();
+                return;
+            }
+            KeyCode::Char('e')
+                if app.mode != Mode::Typing || key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    // Esc stops a running import, keeping what has loaded; until then the buffer is
+    // off limits, so edits and the recovery offer can't interleave with the file
+    if app.import_job.is_some() {
+        match key.code {
+            KeyCode::Esc => {
+                app.import_job = None;
+                app.set_status(format!("Load stopped after {} chars", app.text.len()));
+            }
+            KeyCode::Char('c') | KeyCode::Char('q')
+                if key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                app.should_quit = true;
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    // Recovery offer must be answered first
+    if app.pending_recovery.is_some() {
+        match key.code {
+            KeyCode::Char('c') | KeyCode::Char('q')
+                if key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                app.should_quit = true;
+            }
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                app.accept_recovery();
+                app.set_status("✓ Recovered unsaved work");
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                app.pending_recovery = None;
+                discard_recovery(app.document_path.as_deref());
+                app.set_status("Recovery discarded");
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    // The recent files overlay captures all input
+    if let Some(selected) = app.recent_overlay {
+        handle_recent_overlay_input(app, key, selected);
+        return;
+    }
+
+    // As does the presets overlay
+    if let Some(selected) = app.preset_overlay {
+        handle_preset_overlay_input(app, key, selected);
+        return;
+    }
+
+    // An open prompt captures all input
+    if app.prompt.is_some() {
+        handle_prompt_input(app, key);
+        return;
+    }
+
+    // So does truecolor entry, where letters are hex digits
+    if app.rgb_entry.is_some() {
+        handle_rgb_entry_input(app, key);
+        return;
+    }
+
+    // Alt+V imports at the cursor; terminals keep Ctrl+Shift+V for their own paste
+    if key.modifiers.contains(KeyModifiers::ALT) && matches!(key.code, KeyCode::Char('v') | KeyCode::Char('V')) {
+        import_clipboard(app, true);
+        return;
+    }
+
     // Global quit with Ctrl+C or Ctrl+Q
     if key.modifiers.contains(KeyModifiers::CONTROL) {
         match key.code {
@@ -22,18 +124,192 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) {
                 app.set_status(format!("Selection highlight: {}", mode_name));
                 return;
             }
-            KeyCode::Char('i') => {
-                // Import from clipboard (auto-detect ANSI vs RON)
-                match import_from_clipboard(app) {
-                    Ok(msg) => app.set_status(format!("✓ {}", msg)),
-                    Err(e) => app.set_status(format!("✗ Import failed: {}", e)),
+            KeyCode::Char('l') => {
+                app.line_focus = !app.line_focus;
+                app.set_status(if app.line_focus {
+                    "Line focus: ON"
+                } else {
+                    "Line focus: OFF"
+                });
+                return;
+            }
+            KeyCode::Char('w') => {
+                app.cursor_wrap = !app.cursor_wrap;
+                app.set_status(if app.cursor_wrap {
+                    "Cursor wrap: ON"
+                } else {
+                    "Cursor wrap: OFF"
+                });
+                return;
+            }
+            KeyCode::Char('d') => {
+                // Cycle the duplicate line audit: off, plain text, styled
+                app.duplicate_audit = match app.duplicate_audit {
+                    None => Some(DuplicateAudit::PlainText),
+                    Some(DuplicateAudit::PlainText) => Some(DuplicateAudit::Styled),
+                    Some(DuplicateAudit::Styled) => None,
+                };
+                app.set_status(match app.duplicate_audit {
+                    None => "Duplicate lines: OFF",
+                    Some(DuplicateAudit::PlainText) => "Duplicate lines: by text",
+                    Some(DuplicateAudit::Styled) => "Duplicate lines: by text and style",
+                });
+                return;
+            }
+            KeyCode::Char('b') => {
+                app.show_wrap_markers = !app.show_wrap_markers;
+                app.set_status(if app.show_wrap_markers {
+                    "Wrap markers: ON"
+                } else {
+                    "Wrap markers: OFF"
+                });
+                return;
+            }
+            KeyCode::Char('n') => {
+                app.terminal_bg_preview = !app.terminal_bg_preview;
+                app.set_status(if app.terminal_bg_preview {
+                    "Terminal background preview: ON"
+                } else {
+                    "Terminal background preview: OFF"
+                });
+                return;
+            }
+            KeyCode::Char('t') => {
+                app.show_transparency = !app.show_transparency;
+                app.set_status(if app.show_transparency {
+                    "Transparency grid: ON"
+                } else {
+                    "Transparency grid: OFF"
+                });
+                return;
+            }
+            KeyCode::Char('v') => {
+                // Import from clipboard (auto-detect ANSI vs RON), replacing the buffer.
+                // Not Ctrl+I, which most terminals send as Tab
+                import_clipboard(app, false);
+                return;
+            }
+            KeyCode::Char('x') if matches!(app.active_panel, Panel::FgColor | Panel::BgColor) => {
+                app.swap_current_colors();
+                app.set_status("Swapped FG/BG");
+                return;
+            }
+            KeyCode::Char('p') => {
+                // Export as a PowerShell command
+                match copy_powershell_to_clipboard(app) {
+                    Ok(target) => app.set_status(target.status("PowerShell command")),
+                    Err(e) => app.set_status(format!("✗ Copy failed: {}", e)),
+                }
+                return;
+            }
+            KeyCode::Char('r') => {
+                // Export as a Rust string literal
+                match copy_rust_literal_to_clipboard(app) {
+                    Ok(target) => app.set_status(target.status("Rust string literal")),
+                    Err(e) => app.set_status(format!("✗ Copy failed: {}", e)),
+                }
+                return;
+            }
+            KeyCode::Char('o') => {
+                // Cycle full-reset runs, changed attributes only and per-character sequences
+                app.export_options.encoding = match app.export_options.encoding {
+                    RunEncoding::Delta => RunEncoding::ResetOnChange,
+                    RunEncoding::ResetOnChange => RunEncoding::PerCharacter,
+                    RunEncoding::PerCharacter => RunEncoding::Delta,
+                };
+                app.set_status(match app.export_options.encoding {
+                    RunEncoding::Delta => "Export: changed attributes only",
+                    RunEncoding::ResetOnChange => "Export: full reset per run",
+                    RunEncoding::PerCharacter => "Export: per-character sequences",
+                });
+                return;
+            }
+            KeyCode::Char('u') | KeyCode::Char('U') => {
+                // Edit the buffer as RON (Shift: ANSI) source, or apply the edited source
+                if app.source_edit.is_some() {
+                    match commit_source_edit(app) {
+                        Ok(count) => app.set_status(format!("✓ Applied source ({} chars)", count)),
+                        Err(e) => app.set_status(format!("✗ Source not applied: {}", e)),
+                    }
+                } else {
+                    let format = if key.modifiers.contains(KeyModifiers::SHIFT)
+                        || key.code == KeyCode::Char('U')
+                    {
+                        SourceFormat::Ansi
+                    } else {
+                        SourceFormat::Ron
+                    };
+                    match enter_source_edit(app, format) {
+                        Ok(()) => app.set_status(format!(
+                            "Editing {} source (Ctrl+U applies)",
+                            format.label()
+                        )),
+                        Err(e) => app.set_status(format!("✗ {}", e)),
+                    }
+                }
+                return;
+            }
+            KeyCode::Char('a') => {
+                app.toggle_grid_mode();
+                app.set_status(if app.grid_mode {
+                    "Pixel grid: ON (Space fills a cell)"
+                } else {
+                    "Pixel grid: OFF"
+                });
+                return;
+            }
+            KeyCode::Up | KeyCode::Down => {
+                // Move the editor/controls split
+                app.resize_editor(if key.code == KeyCode::Down { 1 } else { -1 });
+                app.set_status(format!("Editor rows: {:+}", app.editor_extra_rows));
+                return;
+            }
+            KeyCode::Char('y') => {
+                // Show recently opened files
+                if app.recent_files.paths.is_empty() {
+                    app.set_status("No recent files");
+                } else {
+                    app.recent_overlay = Some(0);
+                }
+                return;
+            }
+            KeyCode::Char('f') => {
+                // Flip back to the previously focused panel
+                app.toggle_last_panel();
+                app.clear_status();
+                return;
+            }
+            KeyCode::Char('g') => {
+                // Report the rendered size of the buffer
+                let (width, height) = app.rendered_dimensions();
+                app.set_status(format!("Rendered size: {} cols × {} lines", width, height));
+                return;
+            }
+            KeyCode::Char('k') => {
+                // Export the buffer's colors as a palette
+                match copy_palette_to_clipboard(app) {
+                    Ok((count, target)) => app.set_status(target.status(&format!("palette of {} colors", count))),
+                    Err(e) => app.set_status(format!("✗ Palette export failed: {}", e)),
                 }
                 return;
             }
+            KeyCode::Char('s') => {
+                // Show the size of the echo export
+                let summary = export_size_summary(&app.text, app.export_options);
+                app.set_status(summary);
+                return;
+            }
             KeyCode::Char('e') => {
-                // Export to RON format
+                // Export to RON format, in chunks with progress for large buffers
+                if app.source_edit.is_none() && app.text.len() > PROGRESS_THRESHOLD {
+                    match ProgressExport::ron(app.text.clone(), Some(DocumentSettings::from_app(app))) {
+                        Ok(job) => app.export_job = Some(job),
+                        Err(e) => app.set_status(format!("✗ RON export failed: {}", e)),
+                    }
+                    return;
+                }
                 match export_ron_to_clipboard(app) {
-                    Ok(_) => app.set_status("✓ Copied RON to clipboard!"),
+                    Ok(target) => app.set_status(target.status("RON")),
                     Err(e) => app.set_status(format!("✗ RON export failed: {}", e)),
                 }
                 return;
@@ -42,27 +318,34 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) {
         }
     }
 
+    // Toggle the expanded help footer
+    if key.code == KeyCode::F(1) {
+        app.help_expanded = !app.help_expanded;
+        return;
+    }
+
     // Global panel shortcuts (f/b/d/r) when not in typing mode
     if app.mode != Mode::Typing {
         match key.code {
             KeyCode::Char('f') | KeyCode::Char('F') => {
-                app.active_panel = Panel::FgColor;
+                app.focus_panel(Panel::FgColor);
                 app.set_status("Foreground color");
                 return;
             }
             KeyCode::Char('g') | KeyCode::Char('G') => {
-                app.active_panel = Panel::BgColor;
+                app.focus_panel(Panel::BgColor);
                 app.set_status("Background color");
                 return;
             }
             KeyCode::Char('d') | KeyCode::Char('D') => {
-                app.active_panel = Panel::Formatting;
+                app.focus_panel(Panel::Formatting);
                 app.set_status("Decorations");
                 return;
             }
             KeyCode::Char('r') | KeyCode::Char('R') => {
                 app.reset_style();
                 if app.selection.is_some() {
+                    app.touched = TouchedFields::all();
                     app.apply_style();
                     app.set_status("Reset style applied");
                 } else {
@@ -82,6 +365,150 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) {
     }
 }
 
+fn handle_prompt_input(app: &mut App, key: KeyEvent) {
+    let Some(prompt) = app.prompt.as_mut() else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Char(c) => prompt.input.push(c),
+        KeyCode::Backspace => {
+            prompt.input.pop();
+        }
+        KeyCode::Esc => {
+            app.prompt = None;
+            app.clear_status();
+        }
+        KeyCode::Enter => {
+            if let Some(prompt) = app.prompt.take() {
+                submit_prompt(app, prompt.kind, &prompt.input);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn submit_prompt(app: &mut App, kind: PromptKind, input: &str) {
+    match kind {
+        PromptKind::StripePeriod => {
+            let mut parts = input.split(',').map(|p| p.trim().parse::<usize>());
+            match (parts.next(), parts.next().unwrap_or(Ok(0))) {
+                (Some(Ok(period)), Ok(offset)) if period > 0 => {
+                    app.apply_style_striped(period, offset);
+                    app.set_status(format!("Striped every {} chars", period));
+                }
+                _ => app.set_status(format!("✗ Invalid stripe period: {}", input)),
+            }
+        }
+        PromptKind::Align => {
+            let mut parts = input.split(',').map(str::trim);
+            let alignment = match parts.next() {
+                Some("l") => Some(Alignment::Left),
+                Some("c") => Some(Alignment::Center),
+                Some("r") => Some(Alignment::Right),
+                _ => None,
+            };
+            match (alignment, parts.next().map(str::parse::<usize>)) {
+                (Some(alignment), Some(Ok(width))) => {
+                    app.align_lines(width, alignment);
+                    app.set_status(format!("Aligned lines to width {}", width));
+                }
+                _ => app.set_status(format!("✗ Invalid alignment: {}", input)),
+            }
+        }
+        PromptKind::LineOrder => match input.trim() {
+            "s" => {
+                app.sort_lines(true);
+                app.set_status("Sorted lines");
+            }
+            "S" => {
+                app.sort_lines(false);
+                app.set_status("Sorted lines (case-sensitive)");
+            }
+            "r" => {
+                app.reverse_lines();
+                app.set_status("Reversed lines");
+            }
+            _ => app.set_status(format!("✗ Unknown line command: {}", input)),
+        },
+        PromptKind::Command => {
+            let input = input.trim();
+            let mut words = input.split_whitespace();
+            let command = words.next().unwrap_or_default();
+            if command == "transform" {
+                let name = words.next().unwrap_or_default();
+                match run_transform(app, name) {
+                    Ok(description) => app.set_status(format!("✓ {}", description)),
+                    Err(e) if app.transforms.get(name).is_some() => app.set_status(format!("✗ {}", e)),
+                    Err(e) => app.set_status(format!(
+                        "✗ {} (available: {})",
+                        e,
+                        app.transforms.names().join(", ")
+                    )),
+                }
+            } else if let Some(level) = input.strip_prefix("dim") {
+                match level.trim().parse::<u8>() {
+                    Ok(level) if level <= 3 => {
+                        let changed = app.set_all_dim(level);
+                        app.set_status(format!("Dim level {} on all chars ({} changed)", level, changed));
+                    }
+                    _ => app.set_status(format!("✗ Dim level must be 0-3: {}", level.trim())),
+                }
+            } else {
+                match input.parse::<usize>() {
+                    Ok(line) => {
+                        app.goto_line(line);
+                        app.clear_status();
+                    }
+                    Err(_) => app.set_status(format!("✗ Invalid line number: {}", input)),
+                }
+            }
+        }
+        PromptKind::Sequence => match parse_sequence(input) {
+            Some((start, end, template)) => {
+                let count = app.insert_sequence(start, end, &template);
+                app.set_status(format!("Inserted {} chars", count));
+            }
+            None => app.set_status(format!("✗ Invalid sequence: {}", input)),
+        },
+        PromptKind::PresetName => {
+            let name = input.trim();
+            if name.is_empty() {
+                app.set_status("✗ Preset name can't be empty");
+                return;
+            }
+            app.save_preset(name);
+            match save_presets(app) {
+                Ok(()) => app.set_status(format!("✓ Saved preset {}", name)),
+                Err(e) => app.set_status(format!("✗ Saved preset {} for this session only: {}", name, e)),
+            }
+        }
+        PromptKind::Search => match app.search(input) {
+            0 => app.set_status(format!("✗ No matches for: {}", input)),
+            n => app.set_status(format!("{} matches for: {}", n, input)),
+        },
+    }
+}
+
+/// Parse `seq(start..=end, "template")` (or `start..end`) into an inclusive range and template
+fn parse_sequence(input: &str) -> Option<(i64, i64, String)> {
+    let inner = input.trim().strip_prefix("seq(")?.strip_suffix(')')?;
+    let (range, template) = inner.split_once(',')?;
+    let template = template.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let (start, end) = match range.trim().split_once("..=") {
+        Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+        None => {
+            let (start, end) = range.trim().split_once("..")?;
+            (start.trim().parse().ok()?, end.trim().parse::<i64>().ok()?.checked_sub(1)?)
+        }
+    };
+    // Guard against typos like 1..=1000000000 freezing the editor
+    if end.saturating_sub(start) >= 10_000 {
+        return None;
+    }
+    Some((start, end, template.to_string()))
+}
+
 fn handle_editor_input(app: &mut App, key: KeyEvent) {
     match app.mode {
         Mode::Normal | Mode::Typing => handle_normal_typing_input(app, key),
@@ -90,6 +517,42 @@ fn handle_editor_input(app: &mut App, key: KeyEvent) {
 }
 
 fn handle_normal_typing_input(app: &mut App, key: KeyEvent) {
+    // Count prefix: a leading 0 is line start, later zeros extend the count
+    if app.mode == Mode::Normal {
+        if let KeyCode::Char(c @ '0'..='9') = key.code {
+            if c != '0' || app.pending_count.is_some() {
+                let digit = c as usize - '0' as usize;
+                let count = app.pending_count.unwrap_or(0);
+                // No motion can usefully repeat more often than there are characters
+                let limit = app.text.len().max(1);
+                app.pending_count = Some(count.saturating_mul(10).saturating_add(digit).min(limit));
+                return;
+            }
+        }
+    }
+    let count = app.pending_count.take().unwrap_or(1);
+
+    // Second key of an `M` command
+    if app.pending_mark {
+        app.pending_mark = false;
+        match key.code {
+            KeyCode::Char('a') => {
+                app.marks.0 = Some(app.cursor_pos);
+                app.set_status("Mark a set");
+            }
+            KeyCode::Char('b') => {
+                app.marks.1 = Some(app.cursor_pos);
+                app.set_status("Mark b set");
+            }
+            KeyCode::Char('i') => match app.interpolate_styles_between_marks() {
+                Some(n) => app.set_status(format!("Interpolated styles over {} chars", n)),
+                None => app.set_status("✗ Set two different marks with Ma and Mb first"),
+            },
+            _ => app.clear_status(),
+        }
+        return;
+    }
+
     match key.code {
         // Quit
         KeyCode::Char('q') if app.mode == Mode::Normal && app.text.is_empty() => {
@@ -98,43 +561,43 @@ fn handle_normal_typing_input(app: &mut App, key: KeyEvent) {
         
         // Panel navigation
         KeyCode::Tab => {
-            app.active_panel = app.active_panel.next();
+            app.focus_panel(app.active_panel.next());
             app.clear_status();
         }
         KeyCode::BackTab => {
-            app.active_panel = app.active_panel.prev();
+            app.focus_panel(app.active_panel.prev());
             app.clear_status();
         }
 
         // Cursor movement (vim-style and arrows)
         KeyCode::Left | KeyCode::Char('h') if app.mode == Mode::Normal => {
-            app.move_left();
+            repeat_motion(app, count, App::move_left);
         }
         KeyCode::Right | KeyCode::Char('l') if app.mode == Mode::Normal => {
-            app.move_right();
+            repeat_motion(app, count, App::move_right);
         }
         KeyCode::Up | KeyCode::Char('k') if app.mode == Mode::Normal => {
-            app.move_up();
+            repeat_motion(app, count, App::move_up);
         }
         KeyCode::Down | KeyCode::Char('j') if app.mode == Mode::Normal => {
-            app.move_down();
-        }
-        KeyCode::Home | KeyCode::Char('0') if app.mode == Mode::Normal => {
-            app.move_to_line_start();
+            repeat_motion(app, count, App::move_down);
         }
+        KeyCode::Home if app.mode == Mode::Normal => app.move_to_line_start(),
+        KeyCode::Char('0') if app.mode == Mode::Normal => app.move_to_column_zero(),
         KeyCode::End | KeyCode::Char('$') if app.mode == Mode::Normal => {
             app.move_to_line_end();
         }
 
         // Arrow keys always work for movement
-        KeyCode::Left => app.move_left(),
-        KeyCode::Right => app.move_right(),
-        KeyCode::Up => app.move_up(),
-        KeyCode::Down => app.move_down(),
+        KeyCode::Left => repeat_motion(app, count, App::move_left),
+        KeyCode::Right => repeat_motion(app, count, App::move_right),
+        KeyCode::Up => repeat_motion(app, count, App::move_up),
+        KeyCode::Down => repeat_motion(app, count, App::move_down),
         KeyCode::Home => app.move_to_line_start(),
         KeyCode::End => app.move_to_line_end(),
 
         // Enter typing mode
+        KeyCode::Char(' ') if app.mode == Mode::Normal && app.grid_mode => app.fill_grid_cell(),
         KeyCode::Char('i') if app.mode == Mode::Normal => {
             app.mode = Mode::Typing;
             app.set_status("-- INSERT --");
@@ -144,6 +607,91 @@ fn handle_normal_typing_input(app: &mut App, key: KeyEvent) {
             app.move_right();
             app.set_status("-- INSERT --");
         }
+        KeyCode::Char('o') if app.mode == Mode::Normal => {
+            app.open_line_below();
+            app.set_status("-- INSERT --");
+        }
+        KeyCode::Char('O') if app.mode == Mode::Normal => {
+            app.open_line_above();
+            app.set_status("-- INSERT --");
+        }
+
+        // Toggle brush - paint current style while moving
+        KeyCode::Char('b') if app.mode == Mode::Normal => {
+            app.toggle_brush();
+            app.set_status(if app.brush_active { "Brush: ON" } else { "Brush: OFF" });
+        }
+
+        // Style the character at the cursor and advance, for coloring runs one tap at a time
+        KeyCode::Char('.') if app.mode == Mode::Normal => {
+            let mut styled = false;
+            for _ in 0..count {
+                let before = app.cursor_pos;
+                styled = app.apply_and_advance();
+                if !styled || app.cursor_pos == before {
+                    break;
+                }
+            }
+            if styled {
+                app.clear_status();
+            } else {
+                app.set_status("✗ No character at cursor");
+            }
+        }
+
+        // Warm or cool every color in the buffer
+        KeyCode::Char('>') | KeyCode::Char('<') if app.mode == Mode::Normal => {
+            let direction = if key.code == KeyCode::Char('>') { 1 } else { -1 };
+            // Past a full channel's worth of steps every color is already saturated
+            let steps = count.min(255 / TEMPERATURE_STEP as usize + 1) as i32;
+            let changed = app.adjust_temperature(direction * TEMPERATURE_STEP.saturating_mul(steps));
+            app.set_status(match (changed, direction) {
+                (0, _) => "✗ No colors to shift".to_string(),
+                (n, 1) => format!("Warmer ({} chars)", n),
+                (n, _) => format!("Cooler ({} chars)", n),
+            });
+        }
+
+        // Stripe current style over the buffer
+        KeyCode::Char('s') if app.mode == Mode::Normal => {
+            app.open_prompt(PromptKind::StripePeriod);
+        }
+
+        // Go to line
+        KeyCode::Char(':') if app.mode == Mode::Normal => {
+            app.open_prompt(PromptKind::Command);
+        }
+
+        // Sort or reverse lines
+        KeyCode::Char('L') if app.mode == Mode::Normal => {
+            app.open_prompt(PromptKind::LineOrder);
+        }
+
+        // Align lines within a width
+        KeyCode::Char('=') if app.mode == Mode::Normal => {
+            app.open_prompt(PromptKind::Align);
+        }
+
+        // Insert a generated sequence
+        KeyCode::Char('#') if app.mode == Mode::Normal => {
+            app.open_prompt(PromptKind::Sequence);
+        }
+
+        // Search, jump between matches and style every match
+        KeyCode::Char('/') if app.mode == Mode::Normal => {
+            app.open_prompt(PromptKind::Search);
+        }
+        KeyCode::Char('n') if app.mode == Mode::Normal => app.next_match(),
+        KeyCode::Char('m') if app.mode == Mode::Normal => match app.style_all_matches() {
+            0 => app.set_status("✗ No search matches to style"),
+            n => app.set_status(format!("Styled {} matches", n)),
+        },
+
+        // Marks: Ma/Mb set them, Mi interpolates between them
+        KeyCode::Char('M') if app.mode == Mode::Normal => {
+            app.pending_mark = true;
+            app.set_status("M: a/b set mark, i interpolate");
+        }
 
         // Start selection - load character style into panels
         KeyCode::Char('v') if app.mode == Mode::Normal => {
@@ -152,18 +700,67 @@ fn handle_normal_typing_input(app: &mut App, key: KeyEvent) {
             app.set_status("-- VISUAL --");
         }
 
-        // Export
-        KeyCode::Char('e') if app.mode == Mode::Normal => {
-            match copy_to_clipboard(app) {
-                Ok(_) => app.set_status("✓ Copied to clipboard!"),
+        // Start selection keeping the configured style, ready to apply all of it
+        KeyCode::Char('V') if app.mode == Mode::Normal => {
+            app.start_selection();
+            app.touched = TouchedFields::all();
+            app.set_status("-- VISUAL (keep style) --");
+        }
+
+        // Export, in chunks with progress for large buffers
+        KeyCode::Char('e') if app.mode == Mode::Normal => match chunked_export(app) {
+            Some(job) => app.export_job = Some(job),
+            None => match copy_to_clipboard(app) {
+                Ok(target) => app.set_status(target.status("")),
+                Err(e) => app.set_status(format!("✗ Copy failed: {}", e)),
+            },
+        },
+
+        // Copy raw ANSI with real escape bytes
+        KeyCode::Char('x') if app.mode == Mode::Normal => {
+            match copy_raw_to_clipboard(app) {
+                Ok(target) => app.set_status(target.status("raw ANSI")),
                 Err(e) => app.set_status(format!("✗ Copy failed: {}", e)),
             }
         }
 
+        // Choose what `e` copies
+        KeyCode::Char('E') if app.mode == Mode::Normal => {
+            app.export_format = app.export_format.next();
+            app.set_status(format!("Export format: {}", app.export_format.label()));
+        }
+
+        // Copy plain text
+        KeyCode::Char('c') if app.mode == Mode::Normal => copy_plain(app),
+
+        // Format painter: copy the style at the cursor, paint it on the cursor char
+        KeyCode::Char('Y') if app.mode == Mode::Normal => {
+            if app.yank_style() {
+                app.set_status("Style copied, P to paint it");
+            } else {
+                app.set_status("✗ No character at cursor");
+            }
+        }
+        KeyCode::Char('P') if app.mode == Mode::Normal => paint_style(app),
+
+        // Pick a named style preset
+        KeyCode::Char('"') if app.mode == Mode::Normal => app.preset_overlay = Some(0),
+
+        // Drop colors, or every other attribute, from the whole buffer
+        KeyCode::Char('C') if app.mode == Mode::Normal => strip_colors(app),
+        KeyCode::Char('U') if app.mode == Mode::Normal => clear_formatting(app),
+
+        // Paste the yank buffer
+        KeyCode::Char('p') if app.mode == Mode::Normal => {
+            app.paste();
+            app.clear_status();
+        }
+
         // Exit insert mode
         KeyCode::Esc => {
             app.mode = Mode::Normal;
             app.clear_selection();
+            app.search_matches.clear();
             app.clear_status();
         }
 
@@ -191,6 +788,32 @@ fn handle_normal_typing_input(app: &mut App, key: KeyEvent) {
     }
 }
 
+/// Run a cursor motion up to `count` times, stopping once the cursor no longer moves
+fn repeat_motion(app: &mut App, count: usize, motion: fn(&mut App)) {
+    for _ in 0..count {
+        let before = app.cursor_pos;
+        motion(app);
+        if app.cursor_pos == before {
+            break;
+        }
+    }
+}
+
+/// Import the clipboard, inserting at the cursor when `append` is set
+fn import_clipboard(app: &mut App, append: bool) {
+    match import_from_clipboard(app, append) {
+        Ok(msg) => app.set_status(format!("✓ {}", msg)),
+        Err(e) => app.set_status(format!("✗ Import failed: {}", e)),
+    }
+}
+
+fn copy_plain(app: &mut App) {
+    match copy_plain_to_clipboard(app) {
+        Ok(target) => app.set_status(target.status("plain text")),
+        Err(e) => app.set_status(format!("✗ Copy failed: {}", e)),
+    }
+}
+
 fn handle_selecting_input(app: &mut App, key: KeyEvent) {
     match key.code {
         // Movement extends selection
@@ -198,27 +821,85 @@ fn handle_selecting_input(app: &mut App, key: KeyEvent) {
         KeyCode::Right | KeyCode::Char('l') => app.move_right(),
         KeyCode::Up | KeyCode::Char('k') => app.move_up(),
         KeyCode::Down | KeyCode::Char('j') => app.move_down(),
-        KeyCode::Home | KeyCode::Char('0') => app.move_to_line_start(),
+        KeyCode::Home => app.move_to_line_start(),
+        KeyCode::Char('0') => app.move_to_column_zero(),
         KeyCode::End | KeyCode::Char('$') => app.move_to_line_end(),
 
+        // Stripe current style over the selection
+        KeyCode::Char('s') => app.open_prompt(PromptKind::StripePeriod),
+
+        // Extend the selection to a line
+        KeyCode::Char(':') => app.open_prompt(PromptKind::Command),
+
+        // Copy selected plain text
+        KeyCode::Char('c') => copy_plain(app),
+
+        // Export just the rectangle between the selection's corners
+        KeyCode::Char('e') => match copy_region_to_clipboard(app) {
+            Ok((count, target)) => app.set_status(target.status(&format!("{} chars of the region", count))),
+            Err(e) => app.set_status(format!("✗ Copy failed: {}", e)),
+        },
+
+        // Yank, or append to the yank buffer
+        KeyCode::Char('y') => {
+            let count = app.yank();
+            app.set_status(format!("Yanked {} chars", count));
+        }
+        KeyCode::Char('Y') => {
+            let count = app.yank_append();
+            app.set_status(format!("Appended to yank buffer ({} chars)", count));
+        }
+
         // Apply style to selection
         KeyCode::Enter => {
             app.apply_style();
             app.set_status("Style applied");
         }
 
+        // Paint the style copied with Y over the selection
+        KeyCode::Char('P') => paint_style(app),
+
+        // Pick a named style preset
+        KeyCode::Char('"') => app.preset_overlay = Some(0),
+
+        // Drop colors, or every other attribute, from the selection
+        KeyCode::Char('C') => strip_colors(app),
+        KeyCode::Char('U') => clear_formatting(app),
+
+        // Color the selection around the hue wheel
+        KeyCode::Char('w') => {
+            let count = app.apply_rainbow();
+            app.set_status(format!("Rainbow across {} chars", count));
+        }
+
+        // Delete selected range
+        KeyCode::Delete | KeyCode::Backspace => {
+            app.delete_selection();
+            app.clear_status();
+        }
+
         // Cancel selection
-        KeyCode::Esc | KeyCode::Char('v') => {
+        // Esc cancels, reverting the styles previewed on the selection; v keeps them
+        KeyCode::Esc => {
+            if app.cancel_selection() {
+                app.set_status("Selection cancelled, styles reverted");
+            } else {
+                app.clear_status();
+            }
+        }
+        KeyCode::Char('v') => {
             app.clear_selection();
             app.clear_status();
         }
 
         // Panel switch - apply style first
         KeyCode::Tab => {
-            app.active_panel = app.active_panel.next();
+            app.apply_style();
+            app.focus_panel(app.active_panel.next());
         }
         KeyCode::BackTab => {
-            app.active_panel = app.active_panel.prev();
+            app.apply_style();
+            app.focus_panel(app.active_panel.prev());
         }
 
         _ => {}
@@ -226,83 +907,280 @@ fn handle_selecting_input(app: &mut App, key: KeyEvent) {
 }
 
 fn handle_color_picker_input(app: &mut App, key: KeyEvent, is_foreground: bool) {
-    let color_index = if is_foreground {
-        &mut app.fg_color_index
-    } else {
-        &mut app.bg_color_index
-    };
+    let index = if is_foreground { app.fg_color_index } else { app.bg_color_index };
+    let last = app.palette.entries.len() - 1;
+    let row = PALETTE_ROW_LEN;
 
-    match key.code {
-        // Number/letter key selection (0-9, a-g)
-        KeyCode::Char(c) if color_index_from_key(c).is_some() => {
-            if let Some(idx) = color_index_from_key(c) {
-                *color_index = idx;
-                let (color, name, _) = COLOR_PALETTE[idx];
-                if is_foreground {
-                    app.current_fg = color;
-                    app.set_status(format!("FG: {}", name));
-                } else {
-                    app.current_bg = color;
-                    app.set_status(format!("BG: {}", name));
-                }
+    // Moving to another swatch, by its key (0-9, a-g unless the palette says otherwise)
+    // or by navigation
+    let target = match key.code {
+        KeyCode::Char(c) if app.palette.color_index_from_key(c).is_some() => app.palette.color_index_from_key(c),
+        KeyCode::Left | KeyCode::Char('h') if index > 0 => Some(index - 1),
+        KeyCode::Right | KeyCode::Char('l') if index < last => Some(index + 1),
+        KeyCode::Up | KeyCode::Char('k') if index >= row => Some(index - row),
+        KeyCode::Down | KeyCode::Char('j') if index + row <= last => Some(index + row),
+        _ => None,
+    };
+    if let Some(idx) = target {
+        if is_foreground {
+            app.fg_color_index = idx;
+        } else {
+            app.bg_color_index = idx;
+        }
+        // Live mode recolors the selection as you go; otherwise only Enter applies.
+        // Without a selection browsing only picks, so it never edits the text.
+        if app.color_apply == ColorApply::Live {
+            pick_color(app, idx, is_foreground);
+            if app.selection.is_some() {
                 app.apply_style();
             }
         }
+        return;
+    }
 
-        // Navigate colors
-        KeyCode::Left | KeyCode::Char('h') => {
-            if *color_index > 0 {
-                *color_index -= 1;
-            }
+    match key.code {
+        // Type or dial in any RGB color
+        KeyCode::Char('#') => {
+            app.open_rgb_entry(is_foreground);
+            app.set_status("RGB: type rrggbb or use ←→ and ↑↓, Enter to apply");
         }
-        KeyCode::Right | KeyCode::Char('l') => {
-            if *color_index < COLOR_PALETTE.len() - 1 {
-                *color_index += 1;
+
+        // Brighten or darken the current color, recoloring the selection as it changes
+        KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Char('-') => {
+            let delta = if key.code == KeyCode::Char('-') { -LIGHTNESS_STEP } else { LIGHTNESS_STEP };
+            let lightness = app.adjust_lightness(is_foreground, delta);
+            if app.selection.is_some() {
+                app.apply_style();
             }
+            app.set_status(format!(
+                "{} lightness {:.0}%",
+                if is_foreground { "FG" } else { "BG" },
+                lightness * 100.0
+            ));
         }
-        KeyCode::Up | KeyCode::Char('k') => {
-            if *color_index >= 9 {
-                *color_index -= 9;
-            }
+
+        // Swap foreground and background, on the selection too
+        KeyCode::Char('x') => {
+            let count = app.swap_fg_bg();
+            app.set_status(format!("Swapped FG/BG on {} chars", count));
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if *color_index + 9 < COLOR_PALETTE.len() {
-                *color_index += 9;
-            }
+
+        // Quick theme derived from the foreground color
+        KeyCode::Char('t') if is_foreground => match app.theme_from_current_fg() {
+            Some(_) => app.set_status("✓ Applied theme from FG"),
+            None => app.set_status("✗ Pick a foreground color first"),
+        },
+
+        // Switch between live preview and apply on Enter
+        KeyCode::Char('p') => {
+            app.color_apply = match app.color_apply {
+                ColorApply::Live => ColorApply::OnCommit,
+                ColorApply::OnCommit => ColorApply::Live,
+            };
+            app.set_status(match app.color_apply {
+                ColorApply::Live => "Colors: apply as you pick",
+                ColorApply::OnCommit => "Colors: apply on Enter",
+            });
         }
 
         // Select color and apply
         KeyCode::Enter => {
-            let (color, name, _) = COLOR_PALETTE[*color_index];
-            if is_foreground {
-                app.current_fg = color;
-                app.set_status(format!("FG: {}", name));
-            } else {
-                app.current_bg = color;
-                app.set_status(format!("BG: {}", name));
-            }
+            pick_color(app, index, is_foreground);
             app.apply_style();
+            finish_panel_selection(app);
         }
 
         // Panel navigation
         KeyCode::Tab => {
-            app.active_panel = app.active_panel.next();
+            app.focus_panel(app.active_panel.next());
             app.clear_status();
         }
         KeyCode::BackTab => {
-            app.active_panel = app.active_panel.prev();
+            app.focus_panel(app.active_panel.prev());
+            app.clear_status();
+        }
+
+        KeyCode::Esc => {
+            app.focus_panel(Panel::Editor);
             app.clear_status();
         }
 
+        _ => {}
+    }
+}
+
+fn handle_rgb_entry_input(app: &mut App, key: KeyEvent) {
+    let Some(entry) = app.rgb_entry.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Char(_) if key.modifiers.contains(KeyModifiers::CONTROL) => {}
+        KeyCode::Char(ch) => {
+            let accepted = entry.type_digit(ch);
+            if !accepted && ch.is_ascii_hexdigit() {
+                app.set_status("✗ Already six digits, Backspace to change them");
+            } else if !accepted {
+                app.set_status(format!("✗ '{}' isn't a hex digit", ch));
+            }
+        }
+        KeyCode::Backspace => {
+            entry.typed.pop();
+        }
+        KeyCode::Left => entry.channel = entry.channel.saturating_sub(1),
+        KeyCode::Right => entry.channel = (entry.channel + 1).min(2),
+        KeyCode::Up => entry.adjust(1),
+        KeyCode::Down => entry.adjust(-1),
+        KeyCode::PageUp => entry.adjust(16),
+        KeyCode::PageDown => entry.adjust(-16),
+        KeyCode::Enter => {
+            if !entry.resolve_typed() {
+                app.set_status("✗ Type 3 or 6 hex digits");
+                return;
+            }
+            if let Some(color) = app.commit_rgb_entry() {
+                app.set_status(format!("Color: {}", color_display_string(color)));
+            }
+            app.apply_style();
+            finish_panel_selection(app);
+        }
         KeyCode::Esc => {
-            app.active_panel = Panel::Editor;
+            app.rgb_entry = None;
+            app.clear_status();
+        }
+        _ => {}
+    }
+}
+
+fn handle_recent_overlay_input(app: &mut App, key: KeyEvent, selected: usize) {
+    let last = app.recent_files.paths.len().saturating_sub(1);
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.recent_overlay = Some(selected.saturating_sub(1));
+            app.confirm_open = None;
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.recent_overlay = Some((selected + 1).min(last));
+            app.confirm_open = None;
+        }
+        // Unsaved changes need a second Enter before they're replaced
+        KeyCode::Enter if app.dirty && app.confirm_open != Some(selected) => {
+            app.confirm_open = Some(selected);
+            app.set_status("Unsaved changes will be lost, Enter again to open anyway");
+        }
+        KeyCode::Enter => {
+            app.confirm_open = None;
+            match open_recent(app, selected) {
+                Ok(_) if app.import_job.is_some() => app.recent_overlay = None,
+                Ok(count) => {
+                    app.recent_overlay = None;
+                    app.set_status(format!("✓ Opened {} chars", count));
+                }
+                Err(e) => {
+                    app.set_status(format!("✗ {}", e));
+                    // Stay open on the remaining entries after pruning a missing file
+                    let remaining = app.recent_files.paths.len();
+                    app.recent_overlay = (remaining > 0).then(|| selected.min(remaining - 1));
+                }
+            }
+            // The list is only a convenience, so failing to persist it isn't reported
+            let _ = app.recent_files.save();
+        }
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.recent_overlay = None;
+            app.confirm_open = None;
             app.clear_status();
         }
+        _ => {}
+    }
+}
 
+fn handle_preset_overlay_input(app: &mut App, key: KeyEvent, selected: usize) {
+    let names = app.preset_names();
+    let last = names.len().saturating_sub(1);
+    let apply = |app: &mut App, name: &str| {
+        app.preset_overlay = None;
+        app.apply_preset(name);
+        app.set_status(format!("✓ Applied preset {}", name));
+    };
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => app.preset_overlay = Some(selected.saturating_sub(1)),
+        KeyCode::Down | KeyCode::Char('j') => app.preset_overlay = Some((selected + 1).min(last)),
+        KeyCode::Char(c @ '1'..='9') => {
+            if let Some(name) = names.get(c as usize - '1' as usize) {
+                apply(app, name);
+            }
+        }
+        KeyCode::Enter => {
+            if let Some(name) = names.get(selected) {
+                apply(app, name);
+            }
+        }
+        KeyCode::Char('s') => {
+            app.preset_overlay = None;
+            app.open_prompt(PromptKind::PresetName);
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            if let Some(name) = names.get(selected) {
+                app.presets.remove(name);
+                app.preset_overlay = Some(selected.min(last.saturating_sub(1)));
+                match save_presets(app) {
+                    Ok(()) => app.set_status(format!("Deleted preset {}", name)),
+                    Err(e) => app.set_status(format!("✗ Failed to save presets: {}", e)),
+                }
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => app.preset_overlay = None,
         _ => {}
     }
 }
 
+fn strip_colors(app: &mut App) {
+    let count = app.strip_colors();
+    app.set_status(format!("✓ Stripped colors from {} chars", count));
+}
+
+fn clear_formatting(app: &mut App) {
+    let count = app.clear_formatting();
+    app.set_status(format!("✓ Cleared formatting on {} chars", count));
+}
+
+fn paint_style(app: &mut App) {
+    match app.paint_style() {
+        Some(count) => app.set_status(format!("Painted style on {} chars", count)),
+        None => app.set_status("✗ No style copied, Y copies the one at the cursor"),
+    }
+}
+
+/// Make palette entry `idx` the current foreground or background color
+fn pick_color(app: &mut App, idx: usize, is_foreground: bool) {
+    let color = app.palette.entries[idx].0;
+    let name = app.palette.color_name(idx, is_foreground).to_string();
+    if is_foreground {
+        app.current_fg = color;
+        app.touched.fg = true;
+        app.set_status(format!("FG: {}", name));
+    } else {
+        app.current_bg = color;
+        app.touched.bg = true;
+        app.set_status(format!("BG: {}", name));
+    }
+}
+
+/// After Enter in a style panel during a selection (the style is already applied): either
+/// finish the selection and return to the editor, or keep both for further edits
+fn finish_panel_selection(app: &mut App) {
+    if app.selection.is_none() {
+        return;
+    }
+    if app.enter_returns_to_editor {
+        app.clear_selection();
+        app.focus_panel(Panel::Editor);
+        app.set_status("✓ Style applied to selection");
+    } else {
+        app.set_status("Style applied (selection kept)");
+    }
+}
+
 fn handle_formatting_input(app: &mut App, key: KeyEvent) {
     match key.code {
         // Toggle bold
@@ -320,7 +1198,23 @@ fn handle_formatting_input(app: &mut App, key: KeyEvent) {
         // Toggle underline
         KeyCode::Char('u') | KeyCode::Char('U') | KeyCode::Char('3') => {
             app.toggle_underline();
-            app.set_status(if app.current_underline { "Underline: ON" } else { "Underline: OFF" });
+            app.set_status(if app.current_underline.is_underlined() { "Underline: ON" } else { "Underline: OFF" });
+        }
+
+        // Toggle double underline
+        KeyCode::Char('w') | KeyCode::Char('W') | KeyCode::Char('9') => {
+            app.toggle_double_underline();
+            app.set_status(if app.current_underline == UnderlineStyle::Double {
+                "Double underline: ON"
+            } else {
+                "Double underline: OFF"
+            });
+        }
+
+        // Toggle overline
+        KeyCode::Char('o') | KeyCode::Char('O') | KeyCode::Char('0') => {
+            app.toggle_overline();
+            app.set_status(if app.current_overline { "Overline: ON" } else { "Overline: OFF" });
         }
 
         // Toggle strikethrough
@@ -329,35 +1223,488 @@ fn handle_formatting_input(app: &mut App, key: KeyEvent) {
             app.set_status(if app.current_strikethrough { "Strikethrough: ON" } else { "Strikethrough: OFF" });
         }
 
+        // Toggle blink
+        KeyCode::Char('k') | KeyCode::Char('K') | KeyCode::Char('6') => {
+            app.toggle_blink();
+            app.set_status(if app.current_blink { "Blink: ON" } else { "Blink: OFF" });
+        }
+
+        // Toggle reverse video
+        KeyCode::Char('v') | KeyCode::Char('V') | KeyCode::Char('7') => {
+            app.toggle_reverse();
+            app.set_status(if app.current_reverse { "Reverse: ON" } else { "Reverse: OFF" });
+        }
+
+        // Toggle conceal
+        KeyCode::Char('h') | KeyCode::Char('H') | KeyCode::Char('8') => {
+            app.toggle_hidden();
+            app.set_status(if app.current_hidden { "Hidden: ON" } else { "Hidden: OFF" });
+        }
+
         // Cycle dim
         KeyCode::Char('m') | KeyCode::Char('M') | KeyCode::Char('5') => {
             app.cycle_dim();
             app.set_status(format!("Dim level: {}", app.current_dim));
         }
 
+        // Fix the foreground's lightness until it reads well on the background
+        KeyCode::Char('c') | KeyCode::Char('C') => {
+            let ratio = app.auto_contrast();
+            if app.selection.is_some() {
+                app.apply_style();
+            }
+            if ratio >= MIN_CONTRAST {
+                app.set_status(format!("✓ Contrast {:.1}:1", ratio));
+            } else {
+                app.set_status(format!("✗ Best contrast is {:.1}:1", ratio));
+            }
+        }
+
+        // Cycle how the echo export writes ESC
+        KeyCode::Char('x') | KeyCode::Char('X') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.export_options.escape = app.export_options.escape.next();
+            app.set_status(format!("Escape style: {}", app.export_options.escape.prefix()));
+        }
+
         // Export shortcut
         KeyCode::Char('e') | KeyCode::Char('E') => {
             match copy_to_clipboard(app) {
-                Ok(_) => app.set_status("✓ Copied to clipboard!"),
+                Ok(target) => app.set_status(target.status("")),
                 Err(e) => app.set_status(format!("✗ Copy failed: {}", e)),
             }
         }
 
+        // Commit the selection
+        KeyCode::Enter => {
+            app.apply_style();
+            finish_panel_selection(app);
+        }
+
         // Panel navigation
         KeyCode::Tab => {
-            app.active_panel = app.active_panel.next();
+            app.focus_panel(app.active_panel.next());
             app.clear_status();
         }
         KeyCode::BackTab => {
-            app.active_panel = app.active_panel.prev();
+            app.focus_panel(app.active_panel.prev());
             app.clear_status();
         }
 
         KeyCode::Esc => {
-            app.active_panel = Panel::Editor;
+            app.focus_panel(Panel::Editor);
             app.clear_status();
         }
 
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn press(app: &mut App, code: KeyCode) {
+        handle_key_event(app, KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn test_tab_in_selection_applies_pending_style() {
+        let mut app = App::from_text("abcd");
+        app.cursor_pos = 1;
+        press(&mut app, KeyCode::Char('v'));
+        press(&mut app, KeyCode::Char('l'));
+        app.current_fg = Color::Red;
+        app.touched.fg = true;
+
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.active_panel, Panel::FgColor);
+        assert_eq!(app.text[1].style.fg, Color::Red);
+        assert_eq!(app.text[2].style.fg, Color::Red);
+        assert_eq!(app.text[3].style.fg, Color::Reset);
+        // Selection survives the switch so further panel edits still target it
+        assert_eq!(app.selection, Some((1, 2)));
+    }
+
+    #[test]
+    fn test_selection_survives_panel_round_trips() {
+        let mut app = App::from_text("abcd");
+        app.cursor_pos = 1;
+        press(&mut app, KeyCode::Char('v'));
+        press(&mut app, KeyCode::Char('l'));
+
+        // Tab out and straight back
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::BackTab);
+        assert_eq!(app.active_panel, Panel::Editor);
+        assert_eq!(app.mode, Mode::Selecting);
+        assert_eq!(app.selection, Some((1, 2)));
+
+        // All the way around, picking a color on the way
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.active_panel, Panel::Editor);
+        assert_eq!(app.selection, Some((1, 2)));
+
+        // Jumping to a panel and leaving it with Esc
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.active_panel, Panel::Editor);
+        assert_eq!(app.selection, Some((1, 2)));
+
+        // Movement still extends it, and only Esc in the editor cancels it
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.selection, Some((1, 3)));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.selection, None);
+    }
+
+    #[test]
+    fn test_backtab_in_selection_applies_pending_style() {
+        let mut app = App::from_text("ab");
+        app.cursor_pos = 0;
+        press(&mut app, KeyCode::Char('v'));
+        app.current_bold = true;
+        app.touched.bold = true;
+
+        press(&mut app, KeyCode::BackTab);
+        assert_eq!(app.active_panel, Panel::Formatting);
+        assert!(app.text[0].style.bold);
+        assert!(!app.text[1].style.bold);
+    }
+
+    #[test]
+    fn test_tab_without_selection_leaves_text_alone() {
+        let mut app = App::from_text("ab");
+        app.cursor_pos = 0;
+        app.current_fg = Color::Red;
+
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.active_panel, Panel::FgColor);
+        assert_eq!(app.text[0].style.fg, Color::Reset);
+    }
+
+    #[test]
+    fn test_v_loads_cursor_style_into_panels() {
+        let mut app = App::from_text("ab");
+        app.text[0].style.fg = Color::Blue;
+        app.current_fg = Color::Red;
+        app.cursor_pos = 0;
+
+        press(&mut app, KeyCode::Char('v'));
+        assert_eq!(app.mode, Mode::Selecting);
+        assert_eq!(app.current_fg, Color::Blue);
+    }
+
+    #[test]
+    fn test_shift_v_keeps_configured_style() {
+        let mut app = App::from_text("ab");
+        app.text[0].style.fg = Color::Blue;
+        app.current_fg = Color::Red;
+        app.cursor_pos = 0;
+
+        press(&mut app, KeyCode::Char('V'));
+        assert_eq!(app.mode, Mode::Selecting);
+        assert_eq!(app.current_fg, Color::Red);
+
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.text[0].style.fg, Color::Red);
+        assert_eq!(app.text[1].style.fg, Color::Red);
+    }
+
+    #[test]
+    fn test_count_prefix_repeats_movement() {
+        let mut app = App::from_text("abcdefghijklmn");
+        app.cursor_pos = 0;
+        press(&mut app, KeyCode::Char('3'));
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.cursor_pos, 3);
+        assert_eq!(app.pending_count, None);
+
+        press(&mut app, KeyCode::Char('1'));
+        press(&mut app, KeyCode::Char('0'));
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.cursor_pos, 13);
+    }
+
+    #[test]
+    fn test_open_recent_asks_before_dropping_unsaved_changes() {
+        let mut app = App::from_text("draft");
+        app.dirty = true;
+        app.recent_files.push("/nonexistent/terminal-styler/gone.ron");
+        app.recent_overlay = Some(0);
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.confirm_open, Some(0));
+        assert_eq!(app.recent_files.paths.len(), 1);
+
+        assert_eq!(app.plain_text(), "draft");
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.confirm_open, None);
+    }
+
+    #[test]
+    fn test_large_html_and_ron_exports_run_in_chunks() {
+        let mut app = App::from_text(&"a".repeat(PROGRESS_THRESHOLD + 1));
+        app.export_format = crate::export::ExportFormat::Html;
+        press(&mut app, KeyCode::Char('e'));
+        assert!(app.export_job.is_some());
+        press(&mut app, KeyCode::Esc);
+        crate::export::poll_export_job(&mut app);
+        assert!(app.export_job.is_none());
+        assert_eq!(app.status_message.as_deref(), Some("Export cancelled"));
+
+        handle_key_event(&mut app, KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL));
+        assert!(app.export_job.is_some());
+    }
+
+    #[test]
+    fn test_keys_ignored_while_loading() {
+        let mut app = App::new();
+        app.import_job = Some(crate::import::ProgressImport::new("abc"));
+        press(&mut app, KeyCode::Char('i'));
+        press(&mut app, KeyCode::Char('x'));
+        assert!(app.text.is_empty());
+        assert_eq!(app.mode, Mode::Normal);
+
+        press(&mut app, KeyCode::Esc);
+        assert!(app.import_job.is_none());
+    }
+
+    #[test]
+    fn test_huge_count_is_clamped_to_buffer_length() {
+        let mut app = App::from_text("abc");
+        app.cursor_pos = 0;
+        for _ in 0..11 {
+            press(&mut app, KeyCode::Char('9'));
+        }
+        assert_eq!(app.pending_count, Some(3));
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.cursor_pos, 3);
+    }
+
+    #[test]
+    fn test_bare_zero_moves_to_line_start() {
+        let mut app = App::from_text("abc");
+        press(&mut app, KeyCode::Char('0'));
+        assert_eq!(app.cursor_pos, 0);
+        assert_eq!(app.pending_count, None);
+    }
+
+    #[test]
+    fn test_esc_clears_pending_count() {
+        let mut app = App::from_text("abcdef");
+        app.cursor_pos = 0;
+        press(&mut app, KeyCode::Char('4'));
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.cursor_pos, 1);
+    }
+
+    #[test]
+    fn test_enter_in_color_panel_commits_selection() {
+        let mut app = App::from_text("abc");
+        app.cursor_pos = 0;
+        press(&mut app, KeyCode::Char('V'));
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Char('f'));
+        press(&mut app, KeyCode::Char('l')); // Black
+        press(&mut app, KeyCode::Char('l')); // Red
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.text[0].style.fg, Color::Red);
+        assert_eq!(app.text[1].style.fg, Color::Red);
+        assert_eq!(app.text[2].style.fg, Color::Reset);
+        assert_eq!(app.selection, None);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.active_panel, Panel::Editor);
+    }
+
+    #[test]
+    fn test_enter_in_formatting_panel_can_keep_selection() {
+        let mut app = App::from_text("abc");
+        app.enter_returns_to_editor = false;
+        app.cursor_pos = 0;
+        press(&mut app, KeyCode::Char('V'));
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Char('d'));
+        app.current_italic = true;
+        press(&mut app, KeyCode::Enter);
+
+        assert!(app.text[0].style.italic && app.text[1].style.italic);
+        assert!(!app.text[2].style.italic);
+        assert_eq!(app.selection, Some((0, 1)));
+        assert_eq!(app.active_panel, Panel::Formatting);
+    }
+
+    #[test]
+    fn test_parse_sequence() {
+        assert_eq!(parse_sequence(r#"seq(1..=5, "Item {} ")"#), Some((1, 5, "Item {} ".to_string())));
+        assert_eq!(parse_sequence(r#"seq(0..3,"{},")"#), Some((0, 2, "{},".to_string())));
+        assert_eq!(parse_sequence(r#"seq(-2..=2, "{}")"#), Some((-2, 2, "{}".to_string())));
+        assert_eq!(parse_sequence("seq(1..=5)"), None);
+        assert_eq!(parse_sequence(r#"seq(1..=1000000, "{}")"#), None);
+        assert_eq!(parse_sequence(r#"seq(a..=5, "{}")"#), None);
+        assert_eq!(parse_sequence(r#"seq(0..-9223372036854775808, "{}")"#), None);
+    }
+
+    #[test]
+    fn test_color_navigation_follows_apply_setting() {
+        let select_and_pick = |color_apply: ColorApply| {
+            let mut app = App::from_text("abc");
+            app.color_apply = color_apply;
+            app.cursor_pos = 0;
+            press(&mut app, KeyCode::Char('v'));
+            press(&mut app, KeyCode::Char('l'));
+            press(&mut app, KeyCode::Char('f'));
+            press(&mut app, KeyCode::Char('l'));
+            press(&mut app, KeyCode::Char('l'));
+            app
+        };
+
+        // Live: navigating recolors the selection right away
+        let app = select_and_pick(ColorApply::Live);
+        assert_eq!(app.fg_color_index, 2);
+        assert_eq!(app.text[0].style.fg, Color::Red);
+        assert_eq!(app.text[1].style.fg, Color::Red);
+
+        // On commit: neither navigation nor color keys touch it until Enter
+        let mut app = select_and_pick(ColorApply::OnCommit);
+        press(&mut app, KeyCode::Char('3'));
+        assert_eq!(app.fg_color_index, 3);
+        assert_eq!(app.text[0].style.fg, Color::Reset);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.text[0].style.fg, Color::Green);
+        assert_eq!(app.text[1].style.fg, Color::Green);
+    }
+
+    #[test]
+    fn test_live_browsing_without_selection_leaves_text() {
+        let mut app = App::from_text("abc");
+        app.text[0].style.bold = true;
+        app.cursor_pos = 0;
+        press(&mut app, KeyCode::Char('f'));
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.current_fg, Color::Red);
+        assert_eq!(app.text[0].style.fg, Color::Reset);
+        assert!(app.text[0].style.bold);
+    }
+
+    #[test]
+    fn test_rgb_entry_hex() {
+        let type_hex = |digits: &str| {
+            let mut app = App::from_text("abc");
+            press(&mut app, KeyCode::Char('f'));
+            press(&mut app, KeyCode::Char('#'));
+            for ch in digits.chars() {
+                press(&mut app, KeyCode::Char(ch));
+            }
+            press(&mut app, KeyCode::Enter);
+            app
+        };
+
+        let app = type_hex("FF6400");
+        assert!(app.rgb_entry.is_none());
+        assert_eq!(app.current_fg, Color::Rgb(255, 100, 0));
+
+        // Too short: the entry stays open and nothing changes
+        let app = type_hex("ff64");
+        assert_eq!(app.rgb_entry.as_ref().unwrap().typed, "ff64");
+        assert_eq!(app.current_fg, Color::Reset);
+
+        // Three digits are shorthand
+        let app = type_hex("f00");
+        assert_eq!(app.current_fg, Color::Rgb(255, 0, 0));
+
+        // Other characters are refused, not counted as digits
+        let app = type_hex("zz00ff00");
+        assert_eq!(app.current_fg, Color::Rgb(0, 255, 0));
+    }
+
+    #[test]
+    fn test_rgb_entry_arrows() {
+        let mut app = App::from_text("abc");
+        app.current_bg = Color::Rgb(10, 20, 30);
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('#'));
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::PageUp);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Right);
+        for _ in 0..40 {
+            press(&mut app, KeyCode::Down);
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.current_bg, Color::Rgb(10, 37, 0));
+    }
+
+    #[test]
+    fn test_custom_palette_keys() {
+        let mut app = App::from_text("abc");
+        app.palette.entries = vec![
+            (Color::Rgb(255, 100, 0), "Brand".to_string(), 'x'),
+            (Color::Blue, "Sky".to_string(), 'y'),
+        ];
+        press(&mut app, KeyCode::Char('f'));
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.fg_color_index, 1);
+        assert_eq!(app.current_fg, Color::Blue);
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.current_fg, Color::Rgb(255, 100, 0));
+        // Built-in keys mean nothing now
+        press(&mut app, KeyCode::Char('2'));
+        assert_eq!(app.fg_color_index, 0);
+    }
+
+    #[test]
+    fn test_darken_recolors_selection_live() {
+        let mut app = App::from_text("abc");
+        app.cursor_pos = 0;
+        press(&mut app, KeyCode::Char('v'));
+        press(&mut app, KeyCode::Char('l'));
+        app.current_fg = Color::Rgb(255, 0, 0);
+        press(&mut app, KeyCode::Char('f'));
+        press(&mut app, KeyCode::Char('-'));
+
+        let darker = app.current_fg;
+        assert_ne!(darker, Color::Rgb(255, 0, 0));
+        assert!(app.text[..2].iter().all(|c| c.style.fg == darker));
+        assert_eq!(app.text[2].style.fg, Color::Reset);
+    }
+
+    #[test]
+    fn test_esc_reverts_and_enter_keeps_selection_styles() {
+        let run = |commit: KeyCode| {
+            let mut app = App::from_text("abc");
+            app.cursor_pos = 0;
+            press(&mut app, KeyCode::Char('v'));
+            press(&mut app, KeyCode::Char('l'));
+            press(&mut app, KeyCode::Char('d'));
+            press(&mut app, KeyCode::Char('b'));
+            press(&mut app, KeyCode::Char('f'));
+            press(&mut app, KeyCode::Char('1'));
+            assert!(app.text[0].style.bold && app.text[1].style.bold);
+            if commit == KeyCode::Esc {
+                // Back to the editor first, then cancel there
+                press(&mut app, KeyCode::Esc);
+            }
+            press(&mut app, commit);
+            assert_eq!(app.selection, None);
+            app
+        };
+
+        let app = run(KeyCode::Esc);
+        assert!(app.text.iter().all(|c| !c.style.bold && c.style.fg == Color::Reset));
+
+        let app = run(KeyCode::Enter);
+        assert!(app.text[0].style.bold && app.text[1].style.bold);
+        assert!(!app.text[2].style.bold);
+    }
+}
@@ -1,9 +1,15 @@
 mod app;
+mod cli;
 mod colors;
 mod export;
 mod fx;
 mod import;
 mod input;
+mod palette;
+mod presets;
+mod recent;
+mod recovery;
+mod transform;
 mod ui;
 
 use std::io;
@@ -12,20 +18,28 @@ use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyEventKind},
+    event::{self, DisableFocusChange, EnableFocusChange, Event, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
-use app::App;
-use fx::FxManager;
+use app::{App, ColorApply, Mode};
+use fx::{FxManager, IdleTracker};
+use import::{load_content, read_input};
 use input::handle_key_event;
+use recent::{recent_entry, RecentFiles};
+use recovery::AutosaveTimer;
 
 const FPS: usize = 60;
+/// Event poll interval while the terminal is unfocused and animations are paused
+const PAUSED_POLL: Duration = Duration::from_millis(250);
 
 fn main() -> Result<()> {
+    // Load any file before touching the terminal so errors print normally
+    let app = build_app()?;
+
     // Set up panic hook to restore terminal on crash
     let original_hook = panic::take_hook();
     panic::set_hook(Box::new(move |panic_info| {
@@ -36,13 +50,13 @@ fn main() -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableFocusChange)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
     // Run the app
-    let result = run_app(&mut terminal);
+    let result = run_app(&mut terminal, app);
 
     // Restore terminal
     restore_terminal()?;
@@ -52,37 +66,134 @@ fn main() -> Result<()> {
 
 fn restore_terminal() -> Result<()> {
     disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen)?;
+    execute!(io::stdout(), DisableFocusChange, LeaveAlternateScreen)?;
     Ok(())
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
-    let mut app = App::new();
+/// Build the initial app state from command line arguments
+fn build_app() -> Result<App> {
+    let options = cli::parse_args(std::env::args().skip(1))?;
+    let mut recent_files = RecentFiles::load();
+    let mut app = match options.file {
+        Some(ref path) => {
+            let content = read_input(path)?;
+            let mut app = App::new();
+            load_content(&mut app, &content)?;
+            if let Some(entry) = recent_entry(path) {
+                recent_files.push(&entry);
+                // Not being able to remember the file shouldn't stop it from opening
+                let _ = recent_files.save();
+                app.document_path = Some(entry);
+            }
+            app
+        }
+        None => App::new(),
+    };
+    app.recent_files = recent_files;
+    app.terminal_profile = options.terminal_profile;
+    app.ambient_effect = options.ambient_effect;
+    app.enter_returns_to_editor = !options.enter_stays;
+    app.osc52_clipboard = options.osc52;
+    if options.apply_on_enter {
+        app.color_apply = ColorApply::OnCommit;
+    }
+    if let Some(panel) = options.start_panel {
+        app.active_panel = panel;
+    }
+    if options.start_mode == Some(Mode::Typing) {
+        app.mode = Mode::Typing;
+        app.set_status("-- INSERT --");
+    }
+    let mut appearance = cli::resolve_appearance(&options, |key| std::env::var(key).ok());
+    if let Some(path) = &appearance.palette {
+        match palette::load_palette(path) {
+            Ok(entries) => app.palette = palette::Palette { entries },
+            Err(e) => appearance.warnings.push(format!("{}, using built-in", e)),
+        }
+    }
+    match presets::load_presets() {
+        Ok(loaded) => app.presets = loaded,
+        Err(e) => {
+            appearance.warnings.push(format!("{}, not saving presets this session", e));
+            app.presets_error = Some(e.to_string());
+        }
+    }
+    if !appearance.warnings.is_empty() {
+        app.set_status(format!("✗ {}", appearance.warnings.join("; ")));
+    }
+    app.pending_recovery = recovery::load_recovery(app.document_path.as_deref());
+    Ok(app)
+}
+
+fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, mut app: App) -> Result<()> {
     let mut fx_manager = FxManager::new();
     
     // Trigger startup animation
     fx_manager.trigger_startup();
     
     let mut last_frame = Instant::now();
+    let mut autosave = AutosaveTimer::new(last_frame);
+    let mut idle = IdleTracker::new(last_frame);
 
     loop {
         let elapsed = last_frame.elapsed();
         last_frame = Instant::now();
 
+        // Keep the cursor's line on screen, and the split within what this size can show
+        let size = terminal.size()?;
+        let area = ratatui::layout::Rect::new(0, 0, size.width, size.height);
+        app.ensure_cursor_visible(ui::editor_viewport_height(area, &app));
+        app.editor_rows_bounds = ui::editor_rows_bounds(area, &app);
+
         // Draw UI with effects
         terminal.draw(|frame| {
             ui::render(frame, &app);
             fx_manager.render(frame, frame.area(), elapsed.into());
         })?;
 
-        // Handle events (60 FPS timing)
-        if event::poll(Duration::from_millis(1000 / FPS as u64))? {
-            if let Event::Key(key) = event::read()? {
+        // Handle events (60 FPS timing, slower while unfocused)
+        let poll_interval = if fx_manager.is_paused() {
+            PAUSED_POLL
+        } else {
+            Duration::from_millis(1000 / FPS as u64)
+        };
+        if event::poll(poll_interval)? {
+            match event::read()? {
                 // Only handle key press events (not release or repeat)
-                if key.kind == KeyEventKind::Press {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if idle.input(Instant::now()) {
+                        fx_manager.cancel_ambient();
+                    }
                     handle_key_event(&mut app, key);
                 }
+                Event::FocusLost => fx_manager.set_focused(false),
+                Event::FocusGained => fx_manager.set_focused(true),
+                _ => {}
+            }
+        }
+
+        // Advance any chunked export or import by one step per frame
+        export::poll_export_job(&mut app);
+        import::poll_import_job(&mut app);
+
+        let now = Instant::now();
+        // Start the ambient effect after a stretch of inactivity
+        if app.ambient_effect && !fx_manager.is_paused() && idle.should_start_ambient(now) {
+            let size = terminal.size()?;
+            fx_manager.trigger_ambient(ratatui::layout::Rect::new(0, 0, size.width, size.height));
+            idle.mark_ambient_started();
+        }
+
+        // Periodically write unsaved work to the recovery file; source being edited
+        // isn't the document and a half-loaded file isn't either
+        let saveable = app.source_edit.is_none() && app.import_job.is_none();
+        if saveable && autosave.due(now, app.dirty) {
+            // A failed write stays dirty so the next interval retries it
+            match recovery::write_recovery(app.document_path.as_deref(), &app.text) {
+                Ok(()) => app.dirty = false,
+                Err(e) => app.set_status(format!("✗ Autosave failed: {}", e)),
             }
+            autosave.mark_saved(now);
         }
 
         // Check if we should quit
@@ -91,6 +202,10 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
         }
     }
 
+    // Keep an unanswered recovery file around for next time
+    if app.pending_recovery.is_none() {
+        recovery::mark_clean_exit(app.document_path.as_deref())?;
+    }
     Ok(())
 }
 
@@ -0,0 +1,214 @@
+//! Color palettes stored as RON lists of `(color, name, key)` tuples
+
+use crate::app::{App, StyledChar};
+use crate::colors::COLOR_PALETTE;
+use crate::export::{export_text, set_clipboard_text, ClipboardTarget};
+use crate::import::SerializableColor;
+use anyhow::{anyhow, bail, Result};
+use ratatui::style::Color;
+use std::path::Path;
+
+/// Keys assigned to palette entries, in order
+pub const PALETTE_KEYS: &str = "0123456789abcdefg";
+/// Swatches per row in the color pickers
+pub const PALETTE_ROW_LEN: usize = 9;
+/// Most entries a palette can have, two rows in the pickers
+pub const MAX_PALETTE_LEN: usize = 2 * PALETTE_ROW_LEN;
+/// Picker commands (navigation, swap, theme, live toggle, RGB entry, lightness) that
+/// palette keys would otherwise shadow
+pub const RESERVED_PICKER_KEYS: &str = "hjklxtp#+=-";
+
+/// The colors offered by the color pickers, each with a name and a key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub entries: Vec<(Color, String, char)>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            entries: COLOR_PALETTE
+                .iter()
+                .map(|(color, name, key)| (*color, name.to_string(), *key))
+                .collect(),
+        }
+    }
+}
+
+impl Palette {
+    /// Get color index from char key
+    pub fn color_index_from_key(&self, key: char) -> Option<usize> {
+        self.entries.iter().position(|(_, _, k)| *k == key.to_ascii_lowercase())
+    }
+
+    /// Get color index from Color value, defaulting to White (or the first entry)
+    pub fn color_index_from_color(&self, color: Color) -> usize {
+        let position = |color: Color| self.entries.iter().position(|(c, _, _)| *c == color);
+        position(color).or_else(|| position(Color::White)).unwrap_or(0)
+    }
+
+    /// Name of an entry as it applies to the foreground or background;
+    /// "None" means the terminal's default text color for FG but transparency for BG
+    pub fn color_name(&self, index: usize, is_foreground: bool) -> &str {
+        match self.entries.get(index) {
+            Some((Color::Reset, _, _)) if is_foreground => "Default FG",
+            Some((Color::Reset, _, _)) => "Transparent BG",
+            Some((_, name, _)) => name,
+            None => "Unknown",
+        }
+    }
+}
+
+/// Parse a palette from RON, checking it fits the pickers and has one free key per entry
+pub fn parse_palette(input: &str) -> Result<Vec<(Color, String, char)>> {
+    let entries: Vec<(SerializableColor, String, char)> =
+        ron::from_str(input).map_err(|e| anyhow!("Failed to parse palette: {}", e))?;
+    if entries.is_empty() || entries.len() > MAX_PALETTE_LEN {
+        bail!("Palette needs 1 to {} colors, found {}", MAX_PALETTE_LEN, entries.len());
+    }
+    let mut palette: Vec<(Color, String, char)> = Vec::new();
+    for (color, name, key) in entries {
+        let key = key.to_ascii_lowercase();
+        if palette.iter().any(|(_, _, k)| *k == key) {
+            bail!("Palette key '{}' is used twice", key);
+        }
+        if RESERVED_PICKER_KEYS.contains(key) {
+            bail!("Palette key '{}' is a color picker command", key);
+        }
+        palette.push((color.into(), name, key));
+    }
+    Ok(palette)
+}
+
+/// Read a palette file
+pub fn load_palette(path: impl AsRef<Path>) -> Result<Vec<(Color, String, char)>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Can't read palette {}: {}", path.display(), e))?;
+    parse_palette(&content)
+}
+
+/// Distinct foreground and background colors in order of first use, ignoring Reset
+pub fn used_colors(text: &[StyledChar]) -> Vec<Color> {
+    let mut colors = Vec::new();
+    for color in text.iter().flat_map(|c| [c.style.fg, c.style.bg]) {
+        if color != Color::Reset && !colors.contains(&color) {
+            colors.push(color);
+        }
+    }
+    colors
+}
+
+/// Display name for a color: the built-in name if it has one, otherwise hex or index
+fn swatch_name(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Indexed(i) => format!("Indexed {}", i),
+        _ => COLOR_PALETTE
+            .iter()
+            .find(|(c, _, _)| *c == color)
+            .map_or_else(|| format!("{:?}", color), |(_, name, _)| name.to_string()),
+    }
+}
+
+/// Build a palette RON from the colors the buffer uses, one key each (extra colors are dropped)
+pub fn export_palette_from_buffer(text: &[StyledChar]) -> Result<String> {
+    let entries: Vec<(SerializableColor, String, char)> = used_colors(text)
+        .into_iter()
+        .zip(PALETTE_KEYS.chars())
+        .map(|(color, key)| (color.into(), swatch_name(color), key))
+        .collect();
+
+    ron::ser::to_string_pretty(&entries, ron::ser::PrettyConfig::default())
+        .map_err(|e| anyhow!("Failed to serialize palette: {}", e))
+}
+
+/// Copy the buffer's colors as a palette RON to the clipboard, returning how many were exported
+pub fn copy_palette_to_clipboard(app: &App) -> Result<(usize, ClipboardTarget)> {
+    let text = export_text(app)?;
+    let palette = export_palette_from_buffer(text)?;
+    let target = set_clipboard_text(app, &palette)?;
+    Ok((used_colors(text).len().min(PALETTE_KEYS.len()), target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_from_buffer_round_trips() {
+        let mut text: Vec<StyledChar> = "abcd".chars().map(StyledChar::new).collect();
+        text[0].style.fg = Color::Red;
+        text[1].style.fg = Color::Rgb(255, 128, 0);
+        text[1].style.bg = Color::Red;
+        text[2].style.bg = Color::Indexed(42);
+
+        let palette = parse_palette(&export_palette_from_buffer(&text).unwrap()).unwrap();
+        assert_eq!(
+            palette,
+            vec![
+                (Color::Red, "Red".to_string(), '0'),
+                (Color::Rgb(255, 128, 0), "#ff8000".to_string(), '1'),
+                (Color::Indexed(42), "Indexed 42".to_string(), '2'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_palette_keys_run_out_after_seventeen_colors() {
+        let text: Vec<StyledChar> = (0..20u8)
+            .map(|i| {
+                let mut c = StyledChar::new('x');
+                c.style.fg = Color::Indexed(i);
+                c
+            })
+            .collect();
+        let palette = parse_palette(&export_palette_from_buffer(&text).unwrap()).unwrap();
+        assert_eq!(palette.len(), PALETTE_KEYS.len());
+        assert_eq!(palette.last().unwrap().2, 'g');
+    }
+
+    #[test]
+    fn test_none_is_labelled_per_panel() {
+        let palette = Palette::default();
+        assert_eq!(palette.color_name(0, true), "Default FG");
+        assert_eq!(palette.color_name(0, false), "Transparent BG");
+        assert_eq!(palette.color_name(2, true), "Red");
+        assert_eq!(palette.color_name(2, false), "Red");
+    }
+
+    #[test]
+    fn test_load_custom_palette() {
+        let path = std::env::temp_dir().join(format!("ts-custom-palette-{}.ron", std::process::id()));
+        std::fs::write(&path, r#"[(Rgb(255, 100, 0), "Brand", 'Z'), (Blue, "Sky", 'y')]"#).unwrap();
+        let palette = Palette {
+            entries: load_palette(&path).unwrap(),
+        };
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(palette.color_index_from_key('z'), Some(0));
+        assert_eq!(palette.color_index_from_key('Y'), Some(1));
+        assert_eq!(palette.color_index_from_key('0'), None);
+        assert_eq!(palette.color_name(0, true), "Brand");
+        assert_eq!(palette.color_index_from_color(Color::Blue), 1);
+        // No White to fall back to
+        assert_eq!(palette.color_index_from_color(Color::Red), 0);
+    }
+
+    #[test]
+    fn test_palette_rejects_duplicate_keys_and_empty() {
+        assert!(parse_palette(r#"[(Red, "Red", 'a'), (Blue, "Blue", 'A')]"#).is_err());
+        assert!(parse_palette("[]").is_err());
+        assert!(load_palette("/nonexistent/terminal-styler/palette.ron").is_err());
+    }
+
+    #[test]
+    fn test_palette_rejects_picker_command_keys() {
+        for key in RESERVED_PICKER_KEYS.chars() {
+            let input = format!(r#"[(Red, "Red", '{}')]"#, key);
+            assert!(parse_palette(&input).is_err(), "key {:?} accepted", key);
+        }
+        assert!(parse_palette(r#"[(Red, "Red", 'L')]"#).is_err());
+        assert!(parse_palette(r#"[(Red, "Red", 'z')]"#).is_ok());
+    }
+}
@@ -0,0 +1,113 @@
+//! Named styles saved for reuse, kept as RON in the config directory
+
+use crate::app::{App, CharStyle};
+use crate::import::SerializableStyle;
+use anyhow::{anyhow, bail, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Where user settings live: `$XDG_CONFIG_HOME/terminal-styler`, else `~/.config/terminal-styler`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("terminal-styler"))
+}
+
+fn presets_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("presets.ron"))
+}
+
+/// Serialize presets as a RON map, sorted by name so the file diffs cleanly
+pub fn presets_to_ron(presets: &HashMap<String, CharStyle>) -> Result<String> {
+    let sorted: BTreeMap<&String, SerializableStyle> =
+        presets.iter().map(|(name, style)| (name, style.into())).collect();
+    ron::ser::to_string_pretty(&sorted, ron::ser::PrettyConfig::default())
+        .map_err(|e| anyhow!("Failed to serialize presets: {}", e))
+}
+
+/// Parse presets written by `presets_to_ron`
+pub fn presets_from_ron(input: &str) -> Result<HashMap<String, CharStyle>> {
+    let presets: HashMap<String, SerializableStyle> =
+        ron::from_str(input).map_err(|e| anyhow!("Failed to parse presets: {}", e))?;
+    Ok(presets.into_iter().map(|(name, style)| (name, style.into())).collect())
+}
+
+/// Read the saved presets; a missing file means none, but one that can't be read or parsed is an error
+pub fn load_presets() -> Result<HashMap<String, CharStyle>> {
+    match presets_path() {
+        Some(path) => read_presets(&path),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn read_presets(path: &Path) -> Result<HashMap<String, CharStyle>> {
+    match fs::read_to_string(path) {
+        Ok(content) => presets_from_ron(&content),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(anyhow!("Failed to read presets: {}", e)),
+    }
+}
+
+/// Write the app's presets, unless the presets file failed to load and would be overwritten
+pub fn save_presets(app: &App) -> Result<()> {
+    if let Some(ref error) = app.presets_error {
+        bail!("presets.ron wasn't loaded ({}), so it is left alone", error);
+    }
+    let Some(path) = presets_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, presets_to_ron(&app.presets)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    #[test]
+    fn test_presets_ron_roundtrip() {
+        let mut presets = HashMap::new();
+        presets.insert(
+            "error".to_string(),
+            CharStyle {
+                fg: Color::Red,
+                bold: true,
+                ..CharStyle::default()
+            },
+        );
+        presets.insert(
+            "muted".to_string(),
+            CharStyle {
+                fg: Color::Gray,
+                dim_level: 1,
+                ..CharStyle::default()
+            },
+        );
+
+        let ron = presets_to_ron(&presets).unwrap();
+        assert!(ron.find("error").unwrap() < ron.find("muted").unwrap());
+        assert_eq!(presets_from_ron(&ron).unwrap(), presets);
+        assert!(presets_from_ron("not ron").is_err());
+    }
+
+    #[test]
+    fn test_unreadable_presets_are_not_overwritten() {
+        let path = std::env::temp_dir().join(format!("ts-presets-{}.ron", std::process::id()));
+        assert!(read_presets(&path).unwrap().is_empty());
+        fs::write(&path, "not ron").unwrap();
+        let error = read_presets(&path).unwrap_err();
+        fs::remove_file(path).unwrap();
+
+        let mut app = App::new();
+        app.presets_error = Some(error.to_string());
+        app.save_preset("error");
+        assert!(save_presets(&app).is_err());
+    }
+}
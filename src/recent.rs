@@ -0,0 +1,116 @@
+//! Recently opened files, most recent first, kept next to the recovery file
+
+use crate::app::App;
+use crate::import::{load_content, read_input};
+use crate::recovery::{discard_recovery, state_dir};
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Most files remembered
+pub const RECENT_LIMIT: usize = 10;
+
+/// Recently opened files, most recent first and without duplicates
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecentFiles {
+    pub paths: Vec<String>,
+}
+
+impl RecentFiles {
+    /// Move `path` to the front of the list, dropping the oldest entry past the limit
+    pub fn push(&mut self, path: &str) {
+        self.paths.retain(|p| p != path);
+        self.paths.insert(0, path.to_string());
+        self.paths.truncate(RECENT_LIMIT);
+    }
+
+    /// Read the list saved by a previous session; a missing or unreadable file is an empty list
+    pub fn load() -> Self {
+        let paths = recent_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| content.lines().filter(|l| !l.is_empty()).map(String::from).collect())
+            .unwrap_or_default();
+        let mut recent = Self { paths };
+        recent.paths.truncate(RECENT_LIMIT);
+        recent
+    }
+
+    /// Write the list, one path per line
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = recent_path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.paths.join("\n"))?;
+        Ok(())
+    }
+}
+
+fn recent_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("recent-files"))
+}
+
+/// Absolute form of a loaded path, so the list works from any directory; stdin isn't a file
+pub fn recent_entry(path: &str) -> Option<String> {
+    if path == "-" {
+        return None;
+    }
+    let absolute = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    Some(absolute.to_string_lossy().into_owned())
+}
+
+/// Load recent file `index` into the buffer and move it to the front of the list,
+/// returning the characters loaded so far (a large file keeps loading in chunks).
+/// A file that no longer exists is pruned from the list instead.
+pub fn open_recent(app: &mut App, index: usize) -> Result<usize> {
+    let path = app
+        .recent_files
+        .paths
+        .get(index)
+        .cloned()
+        .ok_or_else(|| anyhow!("No recent file {}", index + 1))?;
+    if !Path::new(&path).exists() {
+        app.recent_files.paths.remove(index);
+        return Err(anyhow!("Removed missing file {}", path));
+    }
+
+    let count = load_content(app, &read_input(&path)?)?;
+    // The previous document's changes were given up, so its recovery file goes too
+    discard_recovery(app.document_path.as_deref());
+    app.document_path = Some(path.clone());
+    app.recent_files.push(&path);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_dedups_and_caps() {
+        let mut recent = RecentFiles::default();
+        for i in 0..RECENT_LIMIT + 3 {
+            recent.push(&format!("/tmp/{}.ron", i));
+        }
+        assert_eq!(recent.paths.len(), RECENT_LIMIT);
+        assert_eq!(recent.paths[0], format!("/tmp/{}.ron", RECENT_LIMIT + 2));
+
+        recent.push("/tmp/5.ron");
+        assert_eq!(recent.paths.len(), RECENT_LIMIT);
+        assert_eq!(recent.paths[0], "/tmp/5.ron");
+        assert_eq!(recent.paths.iter().filter(|p| *p == "/tmp/5.ron").count(), 1);
+    }
+
+    #[test]
+    fn test_open_recent_prunes_missing_file() {
+        let mut app = App::from_text("keep");
+        app.recent_files.push("/nonexistent/terminal-styler/old.ron");
+        app.recent_files.push("/nonexistent/terminal-styler/new.ron");
+
+        assert!(open_recent(&mut app, 1).is_err());
+        assert_eq!(app.recent_files.paths, vec!["/nonexistent/terminal-styler/new.ron"]);
+        assert_eq!(app.plain_text(), "keep");
+    }
+}
@@ -0,0 +1,164 @@
+//! Crash-recovery autosave of the buffer to a RON file
+
+use crate::app::StyledChar;
+use crate::import::{export_ron, import_ron};
+use anyhow::Result;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often a dirty buffer is written to the recovery file
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Tracks when the buffer was last autosaved
+pub struct AutosaveTimer {
+    last_save: Instant,
+    interval: Duration,
+}
+
+impl AutosaveTimer {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_save: now,
+            interval: AUTOSAVE_INTERVAL,
+        }
+    }
+
+    /// Should the buffer be saved now?
+    pub fn due(&self, now: Instant, dirty: bool) -> bool {
+        dirty && now.duration_since(self.last_save) >= self.interval
+    }
+
+    /// Record a save at `now`
+    pub fn mark_saved(&mut self, now: Instant) {
+        self.last_save = now;
+    }
+}
+
+/// Directory holding the recovery and clean-exit files
+pub fn state_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("terminal-styler"))
+}
+
+/// File name stem for a document's recovery files: the opened file's absolute path with
+/// anything outside `[A-Za-z0-9._-]` percent-encoded, or "untitled" for a new or piped buffer
+fn recovery_name(document: Option<&str>) -> String {
+    let Some(path) = document else {
+        return "untitled".to_string();
+    };
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'_' | b'-' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Each document gets its own recovery file so editors running side by side don't overwrite each other
+fn recovery_path(document: Option<&str>) -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("recovery").join(format!("{}.ron", recovery_name(document))))
+}
+
+fn clean_exit_path(document: Option<&str>) -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("recovery").join(format!("{}.clean-exit", recovery_name(document))))
+}
+
+fn modified(path: Option<PathBuf>) -> Option<SystemTime> {
+    fs::metadata(path?).and_then(|m| m.modified()).ok()
+}
+
+/// A recovery file is worth offering if it was written after the last clean exit
+pub fn should_offer_recovery(recovery: Option<SystemTime>, clean_exit: Option<SystemTime>) -> bool {
+    match (recovery, clean_exit) {
+        (Some(recovery), Some(clean_exit)) => recovery > clean_exit,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Write the buffer to the document's recovery file
+pub fn write_recovery(document: Option<&str>, text: &[StyledChar]) -> Result<()> {
+    let Some(path) = recovery_path(document) else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, export_ron(text)?)?;
+    Ok(())
+}
+
+/// Load the document's recovery file if it is newer than its last clean exit
+pub fn load_recovery(document: Option<&str>) -> Option<Vec<StyledChar>> {
+    if !should_offer_recovery(modified(recovery_path(document)), modified(clean_exit_path(document))) {
+        return None;
+    }
+    let content = fs::read_to_string(recovery_path(document)?).ok()?;
+    import_ron(&content).ok()
+}
+
+/// Delete the document's recovery file
+pub fn discard_recovery(document: Option<&str>) {
+    if let Some(path) = recovery_path(document) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Record a clean exit so the document's recovery file is no longer offered
+pub fn mark_clean_exit(document: Option<&str>) -> Result<()> {
+    discard_recovery(document);
+    let Some(path) = clean_exit_path(document) else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, "")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_autosave_due_only_when_dirty_and_elapsed() {
+        let start = Instant::now();
+        let timer = AutosaveTimer::new(start);
+        assert!(!timer.due(start + Duration::from_secs(10), true));
+        assert!(!timer.due(start + Duration::from_secs(31), false));
+        assert!(timer.due(start + Duration::from_secs(30), true));
+    }
+
+    #[test]
+    fn test_autosave_resets_after_save() {
+        let start = Instant::now();
+        let mut timer = AutosaveTimer::new(start);
+        timer.mark_saved(start + Duration::from_secs(30));
+        assert!(!timer.due(start + Duration::from_secs(45), true));
+        assert!(timer.due(start + Duration::from_secs(60), true));
+    }
+
+    #[test]
+    fn test_should_offer_recovery() {
+        let earlier = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let later = SystemTime::UNIX_EPOCH + Duration::from_secs(200);
+        assert!(should_offer_recovery(Some(later), Some(earlier)));
+        assert!(!should_offer_recovery(Some(earlier), Some(later)));
+        assert!(should_offer_recovery(Some(earlier), None));
+        assert!(!should_offer_recovery(None, Some(earlier)));
+        assert!(!should_offer_recovery(None, None));
+    }
+
+    #[test]
+    fn test_recovery_name_is_per_document() {
+        assert_eq!(recovery_name(None), "untitled");
+        assert_eq!(recovery_name(Some("/home/me/logo.ron")), "%2Fhome%2Fme%2Flogo.ron");
+        // Escaping keeps distinct paths apart
+        assert_ne!(recovery_name(Some("/a/b%2Fc")), recovery_name(Some("/a/b/c")));
+        assert_ne!(recovery_name(Some("/tmp/untitled")), recovery_name(None));
+    }
+}
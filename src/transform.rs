@@ -0,0 +1,155 @@
+//! Named whole-buffer transforms, run from the command prompt with `:transform NAME`
+
+use crate::app::{App, TEMPERATURE_STEP};
+use anyhow::{anyhow, Result};
+
+/// A transform rewrites the buffer (or selection) in place, or explains why it couldn't
+pub type TransformFn = fn(&mut App) -> Result<()>;
+
+/// A registered transform
+#[derive(Clone, Copy)]
+pub struct Transform {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub apply: TransformFn,
+}
+
+/// Transforms available by name; new ones only need a `register` call
+#[derive(Clone)]
+pub struct TransformRegistry {
+    transforms: Vec<Transform>,
+}
+
+impl Default for TransformRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("reverse-lines", "Reverse the order of lines", |app| {
+            app.reverse_lines();
+            Ok(())
+        });
+        registry.register("sort-lines", "Sort lines, ignoring case", |app| {
+            app.sort_lines(true);
+            Ok(())
+        });
+        registry.register("sort-lines-cs", "Sort lines, case-sensitive", |app| {
+            app.sort_lines(false);
+            Ok(())
+        });
+        registry.register("theme-from-fg", "Dark background and light accent from the FG color", |app| {
+            app.theme_from_current_fg()
+                .map(|_| ())
+                .ok_or_else(|| anyhow!("Pick a foreground color first"))
+        });
+        registry.register("interpolate", "Blend styles between marks a and b", |app| {
+            app.interpolate_styles_between_marks()
+                .map(|_| ())
+                .ok_or_else(|| anyhow!("Set two different marks with Ma and Mb first"))
+        });
+        registry.register("rainbow", "Color the selection around the hue wheel", |app| {
+            match app.apply_rainbow() {
+                0 => Err(anyhow!("Select some text first")),
+                _ => Ok(()),
+            }
+        });
+        registry.register("warmer", "Shift every color toward red", |app| {
+            match app.adjust_temperature(TEMPERATURE_STEP) {
+                0 => Err(anyhow!("No colors to shift")),
+                _ => Ok(()),
+            }
+        });
+        registry.register("cooler", "Shift every color toward blue", |app| {
+            match app.adjust_temperature(-TEMPERATURE_STEP) {
+                0 => Err(anyhow!("No colors to shift")),
+                _ => Ok(()),
+            }
+        });
+        registry.register("strip-colors", "Drop colors, keeping other attributes", |app| {
+            match app.strip_colors() {
+                0 => Err(anyhow!("No colors to strip")),
+                _ => Ok(()),
+            }
+        });
+        registry.register("clear-formatting", "Drop attributes, keeping colors", |app| {
+            match app.clear_formatting() {
+                0 => Err(anyhow!("No formatting to clear")),
+                _ => Ok(()),
+            }
+        });
+        registry
+    }
+}
+
+impl TransformRegistry {
+    /// A registry with no transforms
+    pub fn empty() -> Self {
+        Self { transforms: Vec::new() }
+    }
+
+    /// Add a transform, replacing any with the same name
+    pub fn register(&mut self, name: &'static str, description: &'static str, apply: TransformFn) {
+        self.transforms.retain(|t| t.name != name);
+        self.transforms.push(Transform { name, description, apply });
+    }
+
+    /// Look up a transform by name
+    pub fn get(&self, name: &str) -> Option<Transform> {
+        self.transforms.iter().find(|t| t.name == name).copied()
+    }
+
+    /// Registered transform names, in registration order
+    pub fn names(&self) -> Vec<&'static str> {
+        self.transforms.iter().map(|t| t.name).collect()
+    }
+}
+
+/// Run the transform called `name` on the app, returning its description
+pub fn run_transform(app: &mut App, name: &str) -> Result<&'static str> {
+    let transform = app
+        .transforms
+        .get(name)
+        .ok_or_else(|| anyhow!("Transform not found: {}", name))?;
+    (transform.apply)(app)?;
+    app.dirty = true;
+    Ok(transform.description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_run_by_name() {
+        let mut app = App::from_text("abc");
+        app.transforms = TransformRegistry::empty();
+        app.transforms.register("shout", "Uppercase everything", |app| {
+            app.text.iter_mut().for_each(|c| c.ch = c.ch.to_ascii_uppercase());
+            Ok(())
+        });
+
+        assert_eq!(run_transform(&mut app, "shout").unwrap(), "Uppercase everything");
+        assert_eq!(app.plain_text(), "ABC");
+        assert_eq!(app.transforms.names(), vec!["shout"]);
+
+        let err = run_transform(&mut app, "whisper").unwrap_err();
+        assert_eq!(err.to_string(), "Transform not found: whisper");
+    }
+
+    #[test]
+    fn test_builtin_transforms_are_registered() {
+        let mut app = App::from_text("b\na");
+        run_transform(&mut app, "sort-lines").unwrap();
+        assert_eq!(app.plain_text(), "a\nb");
+        assert!(app.transforms.get("reverse-lines").is_some());
+        for name in ["rainbow", "warmer", "cooler", "strip-colors", "clear-formatting"] {
+            assert!(app.transforms.get(name).is_some(), "{} not registered", name);
+        }
+    }
+
+    #[test]
+    fn test_failed_transform_reports_why() {
+        let mut app = App::from_text("abc");
+        let err = run_transform(&mut app, "interpolate").unwrap_err();
+        assert_eq!(err.to_string(), "Set two different marks with Ma and Mb first");
+        assert!(!app.dirty);
+    }
+}
@@ -1,29 +1,67 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::app::{App, Mode, Panel, SelectionHighlightMode};
-use crate::colors::{theme, COLOR_PALETTE};
+use crate::app::{App, CharStyle, Mode, Panel, SelectionHighlightMode, UnderlineStyle, MAX_EDITOR_EXTRA_ROWS};
+use crate::colors::{color_display_string, contrast_ratio, dim_blend, rgb_to_hsl, theme, MIN_CONTRAST};
+use crate::palette::PALETTE_ROW_LEN;
 
-/// Render the entire UI
-pub fn render(frame: &mut Frame, app: &App) {
-    let size = frame.area();
+/// Rows the controls keep however far the split is moved
+const MIN_CONTROLS_HEIGHT: i16 = 3;
 
-    // Main background
-    let bg_block = Block::default().style(Style::default().bg(theme::BG_PRIMARY));
-    frame.render_widget(bg_block, size);
+/// Screen areas of the main UI
+struct MainLayout {
+    header: Option<Rect>,
+    editor: Rect,
+    controls: Rect,
+    status: Rect,
+}
 
+/// Controls height with the split unmoved, and the least it can shrink to
+fn controls_heights(size: Rect) -> (i16, i16) {
     // Calculate controls height based on width (stacked vs horizontal)
     let min_horizontal_width = 80;
-    let controls_height = if size.width >= min_horizontal_width + 2 {
-        4  // Horizontal: single row of panels
+    if size.width >= min_horizontal_width + 2 {
+        (4, MIN_CONTROLS_HEIGHT) // Horizontal: single row of panels
     } else {
-        12 // Vertical: stacked panels (4 + 4 + 4)
-    };
+        (12, 3 * MIN_CONTROLS_HEIGHT) // Vertical: stacked panels (4 + 4 + 4), each kept at its minimum
+    }
+}
+
+fn status_height(size: Rect, app: &App) -> u16 {
+    // Expanded help footer takes rows from the editor
+    if app.help_expanded {
+        expanded_help_height(app, size.width)
+    } else {
+        1
+    }
+}
+
+/// Fewest and most extra editor rows that still change the layout for a terminal of `size`
+pub fn editor_rows_bounds(size: Rect, app: &App) -> (i16, i16) {
+    let (base_controls_height, min_controls_height) = controls_heights(size);
+    let (header_height, min_editor_height) = if size.height >= 16 { (3, 4) } else { (0, 3) };
+    // Margins and the spacing around the controls
+    let fixed = 4 + header_height + status_height(size, app) as i16;
+    let spare_editor_rows = size.height as i16 - fixed - base_controls_height - min_editor_height;
+    (
+        -spare_editor_rows.clamp(0, MAX_EDITOR_EXTRA_ROWS),
+        base_controls_height - min_controls_height,
+    )
+}
+
+/// Split the terminal into header, editor, controls and status bar
+fn main_layout(size: Rect, app: &App) -> MainLayout {
+    let (base_controls_height, _) = controls_heights(size);
+    let (min, max) = editor_rows_bounds(size, app);
+    // Moving the split never hides the controls; the editor's minimum height still wins
+    let controls_height = (base_controls_height - app.editor_extra_rows.clamp(min, max)) as u16;
+    let status_height = status_height(size, app);
 
     // Hide header when terminal height is cramped (< 16 lines)
     let show_header = size.height >= 16;
@@ -39,7 +77,7 @@ pub fn render(frame: &mut Frame, app: &App) {
                 Constraint::Length(1),                    // Spacing above controls
                 Constraint::Length(controls_height),     // Controls
                 Constraint::Length(1),                    // Spacing below controls
-                Constraint::Length(1),                    // Status bar
+                Constraint::Length(status_height),        // Status bar
             ])
             .split(size)
     } else {
@@ -51,21 +89,19 @@ pub fn render(frame: &mut Frame, app: &App) {
                 Constraint::Length(1),                    // Spacing above controls
                 Constraint::Length(controls_height),     // Controls
                 Constraint::Length(1),                    // Spacing below controls
-                Constraint::Length(1),                    // Status bar
+                Constraint::Length(status_height),        // Status bar
             ])
             .split(size)
     };
 
-    // Render based on whether header is shown
-    let (editor_chunk, _spacing1_chunk, controls_chunk, _spacing2_chunk, status_chunk) = if show_header {
-        render_header(frame, chunks[0]);
-        (chunks[1], chunks[2], chunks[3], chunks[4], chunks[5])
+    let (header, editor_chunk, controls, status) = if show_header {
+        (Some(chunks[0]), chunks[1], chunks[3], chunks[5])
     } else {
-        (chunks[0], chunks[1], chunks[2], chunks[3], chunks[4])
+        (None, chunks[0], chunks[2], chunks[4])
     };
 
     // Add horizontal and vertical margin around editor
-    let editor_area = Layout::default()
+    let editor = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1),  // Top margin
@@ -81,13 +117,242 @@ pub fn render(frame: &mut Frame, app: &App) {
                 ])
                 .split(editor_chunk)[1]
         )[1];
-    
-    render_editor(frame, app, editor_area);
+
+    MainLayout {
+        header,
+        editor,
+        controls,
+        status,
+    }
+}
+
+/// Number of text lines visible in the editor for a terminal of `size`
+pub fn editor_viewport_height(size: Rect, app: &App) -> usize {
+    // Borders plus the blank padding line above the text
+    main_layout(size, app).editor.height.saturating_sub(3) as usize
+}
+
+/// Render the entire UI
+pub fn render(frame: &mut Frame, app: &App) {
+    let size = frame.area();
+
+    // Main background
+    let bg_block = Block::default().style(Style::default().bg(theme::BG_PRIMARY));
+    frame.render_widget(bg_block, size);
+
+    let layout = main_layout(size, app);
+    if let Some(header) = layout.header {
+        render_header(frame, header);
+    }
+
+    render_editor(frame, app, layout.editor);
     
     // Spacings use BG_PRIMARY already, no render needed
     
-    render_controls(frame, app, controls_chunk);
-    render_status_bar(frame, app, status_chunk);
+    render_controls(frame, app, layout.controls);
+    render_status_bar(frame, app, layout.status);
+
+    if let Some(selected) = app.recent_overlay {
+        render_recent_files(frame, app, selected, size);
+    }
+
+    if let Some(selected) = app.preset_overlay {
+        render_presets(frame, app, selected, size);
+    }
+
+    if let Some(ref recovered) = app.pending_recovery {
+        render_recovery_prompt(frame, recovered.len(), size);
+    }
+}
+
+/// Centered overlay listing recently opened files
+fn render_recent_files(frame: &mut Frame, app: &App, selected: usize, area: Rect) {
+    let paths = &app.recent_files.paths;
+    let width = 64.min(area.width);
+    let height = (paths.len() as u16 + 4).min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let max = (width as usize).saturating_sub(6);
+    let mut lines: Vec<Line> = paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            // Keep the end of long paths, where the file name is
+            let shown: String = if path.chars().count() > max {
+                let tail: String = path.chars().rev().take(max.saturating_sub(1)).collect();
+                format!("…{}", tail.chars().rev().collect::<String>())
+            } else {
+                path.clone()
+            };
+            let style = if i == selected {
+                Style::default()
+                    .fg(theme::ACCENT_PRIMARY)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme::TEXT_PRIMARY)
+            };
+            let marker = if i == selected { "▸ " } else { "  " };
+            Line::from(Span::styled(format!("{}{}", marker, shown), style))
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "↑↓:select │ Enter:open │ Esc:close",
+        Style::default().fg(theme::TEXT_MUTED),
+    )));
+
+    let list = Paragraph::new(lines).block(
+        Block::default()
+            .title(Span::styled(
+                " Recent Files ",
+                Style::default()
+                    .fg(theme::ACCENT_PRIMARY)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(theme::BORDER_FOCUSED))
+            .style(Style::default().bg(theme::BG_PRIMARY)),
+    );
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(list, popup);
+}
+
+/// Centered overlay listing saved style presets, each shown in its own style
+fn render_presets(frame: &mut Frame, app: &App, selected: usize, area: Rect) {
+    let names = app.preset_names();
+    let width = 40.min(area.width);
+    let height = (names.len().max(1) as u16 + 4).min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let mut lines: Vec<Line> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let marker = if i == selected { "▸ " } else { "  " };
+            let number = if i < 9 { format!("{} ", i + 1) } else { "  ".to_string() };
+            let label_style = if i == selected {
+                Style::default()
+                    .fg(theme::ACCENT_PRIMARY)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme::TEXT_MUTED)
+            };
+            Line::from(vec![
+                Span::styled(format!("{}{}", marker, number), label_style),
+                Span::styled(name.clone(), preset_sample_style(&app.presets[name])),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No presets yet",
+            Style::default().fg(theme::TEXT_MUTED),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "1-9/Enter:apply │ s:save │ d:delete │ Esc",
+        Style::default().fg(theme::TEXT_MUTED),
+    )));
+
+    let list = Paragraph::new(lines).block(
+        Block::default()
+            .title(Span::styled(
+                " Style Presets ",
+                Style::default()
+                    .fg(theme::ACCENT_PRIMARY)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(theme::BORDER_FOCUSED))
+            .style(Style::default().bg(theme::BG_PRIMARY)),
+    );
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(list, popup);
+}
+
+/// Approximate look of a preset for its overlay entry
+fn preset_sample_style(preset: &CharStyle) -> Style {
+    let mut style = Style::default().fg(preset.fg).bg(preset.bg);
+    if preset.fg == Color::Reset {
+        style = style.fg(theme::TEXT_PRIMARY);
+    }
+    if preset.bold {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if preset.italic {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if preset.underline.is_underlined() {
+        style = style.add_modifier(Modifier::UNDERLINED);
+    }
+    if preset.strikethrough {
+        style = style.add_modifier(Modifier::CROSSED_OUT);
+    }
+    if preset.reverse {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+    if preset.dim_level > 0 {
+        style = style.add_modifier(Modifier::DIM);
+    }
+    style
+}
+
+/// Centered overlay asking whether to restore an autosaved buffer
+fn render_recovery_prompt(frame: &mut Frame, char_count: usize, area: Rect) {
+    let width = 48.min(area.width);
+    let height = 5.min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let lines = vec![
+        Line::from(Span::styled(
+            format!("Recover {} unsaved chars from last session?", char_count),
+            Style::default().fg(theme::TEXT_PRIMARY),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "y:recover │ n:discard",
+            Style::default().fg(theme::TEXT_MUTED),
+        )),
+    ];
+
+    let prompt = Paragraph::new(lines)
+        .alignment(ratatui::layout::Alignment::Center)
+        .block(
+            Block::default()
+                .title(Span::styled(
+                    " Recovery ",
+                    Style::default()
+                        .fg(theme::ACCENT_PRIMARY)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme::BORDER_FOCUSED))
+                .style(Style::default().bg(theme::BG_PRIMARY)),
+        );
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(prompt, popup);
 }
 
 fn render_header(frame: &mut Frame, area: Rect) {
@@ -126,6 +391,7 @@ fn render_editor(frame: &mut Frame, app: &App, area: Rect) {
     let mut lines: Vec<Line> = vec![Line::from("")]; // Start with empty line for top padding
     let mut current_line_spans: Vec<Span> = vec![Span::raw(" ")]; // Leading space padding
     let mut selection_line_spans: Vec<Span> = vec![Span::raw(" ")]; // For underline mode
+    let mut line_starts: Vec<usize> = Vec::new();
     
     if app.text.is_empty() {
         // Show placeholder text with cursor
@@ -142,6 +408,18 @@ fn render_editor(frame: &mut Frame, app: &App, area: Rect) {
         ));
         lines.push(Line::from(current_line_spans));
     } else {
+        // Position within the text, used for the transparency checkerboard
+        let (mut row, mut col) = (0usize, 0usize);
+        // Rendered column on the current line, for placing tab stops
+        let mut display_col = 0usize;
+        let duplicates = app
+            .duplicate_audit
+            .map(|audit| app.duplicate_lines(audit))
+            .unwrap_or_default();
+        // Rendered line index where each text line begins, used for scrolling
+        line_starts.push(lines.len());
+        let cursor_line = app.cursor_line();
+
         for (i, styled_char) in app.text.iter().enumerate() {
             let is_newline = styled_char.ch == '\n';
             
@@ -150,6 +428,19 @@ fn render_editor(frame: &mut Frame, app: &App, area: Rect) {
             let mut style = Style::default().fg(styled_char.style.fg);
             if styled_char.style.bg != ratatui::style::Color::Reset {
                 style = style.bg(styled_char.style.bg);
+            } else if app.is_search_match(i) && !app.is_selected(i) {
+                // Search matches get a subtle highlight behind the text
+                style = style.bg(theme::BORDER_DEFAULT);
+            } else if duplicates.contains(&row) && !app.is_selected(i) {
+                // Duplicate line audit
+                style = style.bg(theme::WARNING_BG);
+            } else if app.show_transparency && !app.is_selected(i) {
+                // Checkerboard marks cells without an explicit background
+                style = style.bg(if (row + col) % 2 == 0 {
+                    theme::BG_SECONDARY
+                } else {
+                    theme::BG_PRIMARY
+                });
             }
 
             // Apply modifiers
@@ -159,13 +450,46 @@ fn render_editor(frame: &mut Frame, app: &App, area: Rect) {
             if styled_char.style.italic {
                 style = style.add_modifier(Modifier::ITALIC);
             }
-            if styled_char.style.underline {
-                style = style.add_modifier(Modifier::UNDERLINED);
+            match styled_char.style.underline {
+                UnderlineStyle::None => {}
+                UnderlineStyle::Single => style = style.add_modifier(Modifier::UNDERLINED),
+                // No terminal-independent double underline, so tint the line to tell them apart
+                UnderlineStyle::Double => {
+                    style = style.add_modifier(Modifier::UNDERLINED).underline_color(theme::ACCENT_SECONDARY)
+                }
             }
             if styled_char.style.strikethrough {
                 style = style.add_modifier(Modifier::CROSSED_OUT);
             }
+            if styled_char.style.blink {
+                style = style.add_modifier(Modifier::SLOW_BLINK);
+            }
+            if styled_char.style.link.is_some() {
+                // Links get an accent underline, and the accent color unless they have their own
+                style = style.add_modifier(Modifier::UNDERLINED).underline_color(theme::ACCENT_PRIMARY);
+                if styled_char.style.fg == Color::Reset {
+                    style = style.fg(theme::ACCENT_PRIMARY);
+                }
+            }
+            if styled_char.style.reverse {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
             if styled_char.style.dim_level > 0 {
+                // Blend toward the background so each level looks distinct;
+                // the terminal's default fg has no RGB value, so fall back to DIM
+                let bg = match style.bg {
+                    Some(bg) => bg,
+                    None if app.terminal_bg_preview => Color::Reset,
+                    None => theme::BG_PRIMARY,
+                };
+                match dim_blend(styled_char.style.fg, bg, styled_char.style.dim_level) {
+                    Some(fg) => style = style.fg(fg),
+                    None => style = style.add_modifier(Modifier::DIM),
+                }
+            }
+
+            // Line focus dims everything off the cursor's line (display only)
+            if app.line_focus && row != cursor_line {
                 style = style.add_modifier(Modifier::DIM);
             }
 
@@ -193,8 +517,10 @@ fn render_editor(frame: &mut Frame, app: &App, area: Rect) {
                     style = style.add_modifier(Modifier::BOLD);
                 }
             } else {
-                // Reversed mode
-                if is_selected {
+                // Reversed mode; selecting reverse-video text turns it back around
+                if is_selected && styled_char.style.reverse {
+                    style = style.remove_modifier(Modifier::REVERSED);
+                } else if is_selected {
                     style = style.add_modifier(Modifier::REVERSED);
                 }
                 if is_cursor {
@@ -222,8 +548,24 @@ fn render_editor(frame: &mut Frame, app: &App, area: Rect) {
                 // Start new line with padding
                 current_line_spans = vec![Span::raw(" ")];
                 selection_line_spans = vec![Span::raw(" ")];
+                line_starts.push(lines.len());
+                row += 1;
+                col = 0;
+                display_col = 0;
             } else {
-                current_line_spans.push(Span::styled(styled_char.ch.to_string(), style));
+                let glyph = if styled_char.ch == '\t' {
+                    // Tabs advance to the next stop of the document's tab width
+                    let tab_width = app.tab_width.max(1);
+                    " ".repeat(tab_width - display_col % tab_width)
+                } else if styled_char.style.hidden {
+                    // Concealed text keeps its width but shows nothing
+                    " ".repeat(styled_char.ch.width().unwrap_or(1))
+                } else {
+                    styled_char.ch.to_string()
+                };
+                display_col += glyph.width();
+                current_line_spans.push(Span::styled(glyph, style));
+                col += 1;
             }
         }
 
@@ -251,9 +593,13 @@ fn render_editor(frame: &mut Frame, app: &App, area: Rect) {
     }
 
     let mode_indicator = match app.mode {
-        Mode::Normal => "NORMAL",
-        Mode::Typing => "INSERT",
-        Mode::Selecting => "VISUAL",
+        Mode::Normal => "NORMAL".to_string(),
+        Mode::Typing => "INSERT".to_string(),
+        Mode::Selecting => match app.selection_stats() {
+            Some((count, lines)) if lines > 1 => format!("VISUAL {} · {} lines", count, lines),
+            Some((count, _)) => format!("VISUAL {}", count),
+            None => "VISUAL".to_string(),
+        },
     };
 
     let highlight_indicator = if app.mode == Mode::Selecting {
@@ -265,26 +611,89 @@ fn render_editor(frame: &mut Frame, app: &App, area: Rect) {
         ""
     };
 
-    let title = format!(" Editor [{}]{} ", mode_indicator, highlight_indicator);
+    let brush_indicator = if app.brush_active { " │ BRUSH" } else { "" };
+    let grid_indicator = if app.grid_mode { " │ GRID" } else { "" };
+    let source_indicator = app
+        .source_edit
+        .map(|format| format!(" │ {} SOURCE", format.label()))
+        .unwrap_or_default();
 
-    let editor = Paragraph::new(lines)
-        .style(Style::default().bg(theme::BG_PRIMARY))
-        .block(
-            Block::default()
-                .title(Span::styled(
-                    title,
-                    Style::default()
-                        .fg(if is_focused { theme::ACCENT_PRIMARY } else { theme::TEXT_SECONDARY })
-                        .add_modifier(Modifier::BOLD),
-                ))
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(border_color))
-                .style(Style::default().bg(theme::BG_PRIMARY)),
-        )
-        .wrap(Wrap { trim: false });
+    let title = format!(
+        " Editor [{}]{}{}{}{} ",
+        mode_indicator, source_indicator, brush_indicator, grid_indicator, highlight_indicator
+    );
+
+    // Wrap by hand so soft-wrapped rows can be marked
+    let content_width = area.width.saturating_sub(2) as usize;
+    let (lines, row_starts) = wrap_lines(lines, content_width, app.show_wrap_markers);
+    let line_starts: Vec<usize> = line_starts.iter().map(|&line| row_starts[line]).collect();
+
+    let block = Block::default()
+        .title(Span::styled(
+            title,
+            Style::default()
+                .fg(if is_focused { theme::ACCENT_PRIMARY } else { theme::TEXT_SECONDARY })
+                .add_modifier(Modifier::BOLD),
+        ))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(border_color))
+        .style(Style::default().bg(theme::BG_PRIMARY));
+    let content_area = block.inner(area);
+    frame.render_widget(block, area);
+
+    // The terminal preview leaves the content on the terminal's own background
+    let content_bg = if app.terminal_bg_preview { Color::Reset } else { theme::BG_PRIMARY };
+    let editor = Paragraph::new(lines).style(Style::default().bg(content_bg));
+
+    // Scroll so the first visible text line sits at the top (padding line hides once scrolled)
+    let scroll = match app.scroll_offset {
+        0 => 0,
+        offset => line_starts.get(offset).copied().unwrap_or(0),
+    };
+    let editor = editor.scroll((scroll as u16, 0));
 
-    frame.render_widget(editor, area);
+    frame.render_widget(editor, content_area);
+}
+
+/// Soft-wrap lines to `width` columns, breaking between characters. With `markers`, each
+/// soft-wrapped row ends in a faint `\` at the right edge. Returns the rows and the row
+/// each input line starts on.
+fn wrap_lines(lines: Vec<Line<'_>>, width: usize, markers: bool) -> (Vec<Line<'_>>, Vec<usize>) {
+    let width = width.max(2);
+    let limit = if markers { width - 1 } else { width };
+    let mut rows = Vec::with_capacity(lines.len());
+    let mut starts = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        starts.push(rows.len());
+        if line.width() <= width {
+            rows.push(line);
+            continue;
+        }
+
+        let mut row: Vec<Span> = Vec::new();
+        let mut row_width = 0;
+        for span in line.spans {
+            for ch in span.content.chars() {
+                let cell = Span::styled(ch.to_string(), span.style);
+                let ch_width = cell.width();
+                if row_width + ch_width > limit && row_width > 0 {
+                    if markers {
+                        row.push(Span::raw(" ".repeat(limit - row_width)));
+                        row.push(Span::styled("\\", Style::default().fg(theme::TEXT_MUTED)));
+                    }
+                    rows.push(Line::from(std::mem::take(&mut row)));
+                    row_width = 0;
+                }
+                row.push(cell);
+                row_width += ch_width;
+            }
+        }
+        rows.push(Line::from(row));
+    }
+
+    (rows, starts)
 }
 
 fn render_controls(frame: &mut Frame, app: &App, area: Rect) {
@@ -362,11 +771,11 @@ fn render_color_picker(frame: &mut Frame, app: &App, area: Rect, title: &str, is
         app.current_bg
     };
 
-    // Create color palette display (2 rows: first row 0-8, second row 9-16)
+    // Create color palette display (2 rows of up to 9 swatches)
     let mut line1_spans: Vec<Span> = vec![Span::raw(" ")];
     let mut line2_spans: Vec<Span> = vec![Span::raw(" ")];
 
-    for (i, (color, _name, key)) in COLOR_PALETTE.iter().enumerate() {
+    for (i, (color, _name, key)) in app.palette.entries.iter().enumerate() {
         let is_selected = i == selected_index;
         let is_current = *color == current_color;
 
@@ -385,10 +794,10 @@ fn render_color_picker(frame: &mut Frame, app: &App, area: Rect, title: &str, is
         
         let combined = format!("{}{} ", key_char, block_display);
         
-        // For Reset/None color, show a special indicator
+        // For Reset/None color, show a special indicator: inherit for FG, see-through for BG
         let span = if *color == ratatui::style::Color::Reset {
             Span::styled(
-                format!("{}◌ ", key_char),
+                format!("{}{} ", key_char, if is_foreground { "◌" } else { "▒" }),
                 if is_selected && is_focused {
                     Style::default().fg(theme::ACCENT_PRIMARY)
                 } else {
@@ -399,21 +808,63 @@ fn render_color_picker(frame: &mut Frame, app: &App, area: Rect, title: &str, is
             Span::styled(combined, color_style)
         };
 
-        if i < 9 {
+        if i < PALETTE_ROW_LEN {
             line1_spans.push(span);
         } else {
             line2_spans.push(span);
         }
     }
 
-    let text = vec![Line::from(line1_spans), Line::from(line2_spans)];
+    let mut text = vec![Line::from(line1_spans), Line::from(line2_spans)];
+
+    // Truecolor entry replaces the palette with a swatch and the channels
+    if let Some(entry) = app.rgb_entry.as_ref().filter(|entry| entry.is_foreground == is_foreground) {
+        let [r, g, b] = entry.rgb;
+        let mut swatch = vec![
+            Span::raw(" "),
+            Span::styled("      ", Style::default().bg(entry.color())),
+            Span::styled(format!(" #{:02x}{:02x}{:02x}", r, g, b), Style::default().fg(theme::TEXT_PRIMARY)),
+        ];
+        if !entry.typed.is_empty() {
+            swatch.push(Span::styled(format!("  › {}▏", entry.typed), Style::default().fg(theme::ACCENT_PRIMARY)));
+        }
+        let mut channels = vec![Span::raw(" ")];
+        for (i, (name, value)) in [("R", r), ("G", g), ("B", b)].into_iter().enumerate() {
+            let style = if i == entry.channel {
+                Style::default().fg(theme::ACCENT_PRIMARY).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme::TEXT_SECONDARY)
+            };
+            channels.push(Span::styled(format!("{} {:3}  ", name, value), style));
+        }
+        if let Some((h, s, l)) = rgb_to_hsl(entry.color()) {
+            channels.push(Span::styled(
+                format!("HSL {:.0}° {:.0}% {:.0}%", h, s * 100.0, l * 100.0),
+                Style::default().fg(theme::TEXT_MUTED),
+            ));
+        }
+        text = vec![Line::from(swatch), Line::from(channels)];
+    }
 
     let picker = Paragraph::new(text)
         .style(Style::default().bg(theme::BG_PRIMARY))
         .block(
             Block::default()
                 .title(Span::styled(
-                    format!(" {} ", title),
+                    if is_focused {
+                        // The highlighted swatch's name with its hex value, or the hex of a
+                        // truecolor that isn't in the palette
+                        let entries = &app.palette.entries;
+                        let in_palette = entries.iter().any(|(color, _, _)| *color == current_color);
+                        let label = match entries.get(selected_index) {
+                            _ if !in_palette => color_display_string(current_color),
+                            Some((color, _, _)) if *color != Color::Reset => color_display_string(*color),
+                            _ => app.palette.color_name(selected_index, is_foreground).to_string(),
+                        };
+                        format!(" {} · {} ", title, label)
+                    } else {
+                        format!(" {} ", title)
+                    },
                     Style::default()
                         .fg(if is_focused { theme::ACCENT_PRIMARY } else { theme::TEXT_SECONDARY })
                         .add_modifier(Modifier::BOLD),
@@ -435,44 +886,105 @@ fn render_formatting_panel(frame: &mut Frame, app: &App, area: Rect) {
         theme::BORDER_DEFAULT
     };
 
-    // Helper to create decoration indicator
-    let make_indicator = |key: &str, label: &str, active: bool| -> Span {
-        let style = if active {
+    // Each indicator in three widths: labeled, just the key, and the bare letter.
+    // "~" when the selection has it on some chars only
+    let make_indicator = |key: &str, label: &str, active: bool, mixed: bool| -> [Span<'static>; 3] {
+        let style = if mixed {
+            Style::default().fg(theme::ACCENT_SECONDARY)
+        } else if active {
             Style::default().fg(theme::ACCENT_PRIMARY).add_modifier(Modifier::BOLD)
         } else {
             Style::default().fg(theme::TEXT_MUTED)
         };
-        Span::styled(format!("[{}]{} ", key, if active { "✓" } else { label }), style)
+        let mark = if mixed { "~" } else { "" };
+        let label = if mixed { "~" } else if active { "✓" } else { label };
+        [
+            Span::styled(format!("[{}]{} ", key, label), style),
+            Span::styled(format!("[{}]{} ", key, mark), style),
+            Span::styled(format!("{}{} ", key, mark), style),
+        ]
     };
+    let touched = app.touched;
 
     // Dim indicator with levels
+    let dim_mixed = !touched.dim
+        && app.selection.is_some_and(|(start, end)| {
+            let chars = app.text.iter().take(end + 1).skip(start).filter(|c| c.ch != '\n');
+            let levels: Vec<u8> = chars.map(|c| c.style.dim_level).collect();
+            levels.windows(2).any(|pair| pair[0] != pair[1])
+        });
     let dim_display = match app.current_dim {
+        _ if dim_mixed => "~",
         0 => "░",
         1 => "▒",
         2 => "▓",
         3 => "█",
         _ => "░",
     };
+    let dim_style = if app.current_dim > 0 {
+        Style::default().fg(theme::ACCENT_SECONDARY)
+    } else {
+        Style::default().fg(theme::TEXT_MUTED)
+    };
+    let export_style = Style::default().fg(theme::SUCCESS);
 
-    let lines = vec![
-        Line::from(vec![
-            make_indicator("B", "old", app.current_bold),
-            make_indicator("I", "talic", app.current_italic),
-            make_indicator("U", "nder", app.current_underline),
-        ]),
-        Line::from(vec![
-            make_indicator("S", "trike", app.current_strikethrough),
-            Span::styled(
-                format!("[M]Dim{} ", dim_display),
-                if app.current_dim > 0 {
-                    Style::default().fg(theme::ACCENT_SECONDARY)
-                } else {
-                    Style::default().fg(theme::TEXT_MUTED)
-                },
-            ),
-            Span::styled("[E]xport", Style::default().fg(theme::SUCCESS)),
-        ]),
+    let mut indicators = vec![
+        make_indicator("B", "old", app.current_bold, app.is_mixed(touched.bold, |s| s.bold)),
+        make_indicator("I", "talic", app.current_italic, app.is_mixed(touched.italic, |s| s.italic)),
+        make_indicator(
+            "U",
+            "nder",
+            app.current_underline == UnderlineStyle::Single,
+            app.is_mixed(touched.underline, |s| s.underline == UnderlineStyle::Single),
+        ),
+        make_indicator(
+            "W",
+            "Dbl",
+            app.current_underline == UnderlineStyle::Double,
+            app.is_mixed(touched.underline, |s| s.underline == UnderlineStyle::Double),
+        ),
+        make_indicator("O", "ver", app.current_overline, app.is_mixed(touched.overline, |s| s.overline)),
+        make_indicator("K", "Blink", app.current_blink, app.is_mixed(touched.blink, |s| s.blink)),
+        make_indicator("V", "Rev", app.current_reverse, app.is_mixed(touched.reverse, |s| s.reverse)),
+        make_indicator(
+            "S",
+            "trike",
+            app.current_strikethrough,
+            app.is_mixed(touched.strikethrough, |s| s.strikethrough),
+        ),
+        make_indicator("H", "ide", app.current_hidden, app.is_mixed(touched.hidden, |s| s.hidden)),
+        [
+            Span::styled(format!("[M]Dim{} ", dim_display), dim_style),
+            Span::styled(format!("[M]{} ", dim_display), dim_style),
+            Span::styled(format!("M{} ", dim_display), dim_style),
+        ],
+        [
+            Span::styled("[E]xport ", export_style),
+            Span::styled("[E] ", export_style),
+            Span::styled("E ", export_style),
+        ],
     ];
+    // Flag hard-to-read color pairs first, so the warning is never the part cut off; C fixes them
+    let contrast = contrast_ratio(app.current_fg, app.current_bg);
+    if contrast < MIN_CONTRAST {
+        let warning_style = Style::default().fg(theme::ERROR).add_modifier(Modifier::BOLD);
+        indicators.insert(
+            0,
+            [
+                Span::styled(format!("⚠ [C] {:.1}:1 ", contrast), warning_style),
+                Span::styled(format!("⚠[C]{:.1} ", contrast), warning_style),
+                Span::styled("⚠C ", warning_style),
+            ],
+        );
+    }
+
+    // Use the widest labels that still fit the panel
+    let width = area.width.saturating_sub(2) as usize;
+    let rows = area.height.saturating_sub(2).max(1) as usize;
+    let lines = (0..3)
+        .map(|tier| flow_spans(indicators.iter().map(|forms| forms[tier].clone()), width))
+        .find(|lines| lines.len() <= rows)
+        .unwrap_or_else(|| flow_spans(indicators.iter().map(|forms| forms[2].clone()), width));
 
     let panel = Paragraph::new(lines)
         .style(Style::default().bg(theme::BG_PRIMARY))
@@ -493,26 +1005,281 @@ fn render_formatting_panel(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(panel, area);
 }
 
-fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
-    let help_text = match app.active_panel {
-        Panel::Editor => match app.mode {
-            Mode::Normal => "i:insert │ v:select │ e:export │ hjkl/arrows:move │ Ctrl+Q:quit",
-            Mode::Typing => "Esc:normal │ arrows:move │ Enter:newline │ Backspace:delete",
-            Mode::Selecting => "hjkl/arrows:extend │ Enter:apply │ Esc:cancel",
+/// Lay spans out left to right, starting a new line when the next one doesn't fit in `width`
+fn flow_spans(spans: impl Iterator<Item = Span<'static>>, width: usize) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::default()];
+    let mut line_width = 0;
+    for span in spans {
+        // A trailing space may run past the edge
+        if line_width > 0 && line_width + span.content.trim_end().width() > width {
+            lines.push(Line::default());
+            line_width = 0;
+        }
+        line_width += span.content.width();
+        lines.last_mut().unwrap().spans.push(span);
+    }
+    lines
+}
+
+/// Keybinding help for the given panel and mode as (keys, action) pairs.
+/// The second value is how many leading entries fit in the collapsed footer.
+fn help_entries(panel: Panel, mode: Mode) -> (&'static [(&'static str, &'static str)], usize) {
+    match panel {
+        Panel::Editor => match mode {
+            Mode::Normal => (
+                &[
+                    ("i", "insert"),
+                    ("v", "select"),
+                    ("V", "select, keep style"),
+                    ("b", "brush"),
+                    ("e", "export"),
+                    ("hjkl/arrows", "move"),
+                    ("Ctrl+Q", "quit"),
+                    ("a", "append"),
+                    ("o/O", "open line"),
+                    ("s", "stripe"),
+                    ("c", "copy text"),
+                    ("p", "paste yank"),
+                    ("Y/P", "copy/paint style"),
+                    ("\"", "presets"),
+                    ("C/U", "strip colors/formatting"),
+                    ("E", "export format"),
+                    ("x", "raw ANSI"),
+                    (".", "apply & advance"),
+                    ("</>", "cooler/warmer"),
+                    ("Del/Bksp", "delete"),
+                    ("/", "search"),
+                    ("n", "next match"),
+                    ("m", "style matches"),
+                    ("Ma/Mb", "set marks"),
+                    ("Mi", "interpolate marks"),
+                    ("=", "align lines"),
+                    ("L", "sort/reverse lines"),
+                    ("0/$", "line ends"),
+                    (":", "go to line/transform"),
+                    ("#", "insert sequence"),
+                    ("f/g/d", "panels"),
+                    ("r", "reset"),
+                    ("Ctrl+E", "RON"),
+                    ("Ctrl+P", "PowerShell"),
+                    ("Ctrl+R", "Rust"),
+                    ("Ctrl+S", "export size"),
+                    ("Ctrl+G", "rendered size"),
+                    ("Ctrl+U", "edit source"),
+                    ("Ctrl+A", "pixel grid"),
+                    ("Ctrl+K", "palette"),
+                    ("Ctrl+O", "export encoding"),
+                    ("Ctrl+V", "import"),
+                    ("Alt+V", "insert import"),
+                    ("Ctrl+T", "grid"),
+                    ("Ctrl+N", "terminal bg"),
+                    ("Ctrl+L", "line focus"),
+                    ("Ctrl+W", "cursor wrap"),
+                    ("Ctrl+B", "wrap markers"),
+                    ("Ctrl+D", "duplicate lines"),
+                    ("Ctrl+F", "last panel"),
+                    ("Ctrl+↑↓", "resize editor"),
+                    ("Ctrl+Y", "recent files"),
+                    ("Tab", "next panel"),
+                ],
+                6,
+            ),
+            Mode::Typing => (
+                &[
+                    ("Esc", "normal"),
+                    ("arrows", "move"),
+                    ("Enter", "newline"),
+                    ("Backspace", "delete"),
+                    ("Delete", "delete forward"),
+                    ("Home/End", "line ends"),
+                    ("Ctrl+V", "import"),
+                ],
+                4,
+            ),
+            Mode::Selecting => (
+                &[
+                    ("hjkl/arrows", "extend"),
+                    ("Enter", "apply"),
+                    ("Esc", "cancel & revert"),
+                    ("s", "stripe"),
+                    ("c", "copy text"),
+                    ("e", "export region"),
+                    ("y/Y", "yank/append"),
+                    ("P", "paint style"),
+                    ("\"", "presets"),
+                    ("w", "rainbow"),
+                    ("C/U", "strip colors/formatting"),
+                    ("Del/Bksp", "delete"),
+                    ("0/$", "line ends"),
+                    (":", "extend to line"),
+                    ("f/g/d", "panels"),
+                    ("r", "reset style"),
+                    ("Ctrl+H", "highlight mode"),
+                    ("Tab", "next panel"),
+                ],
+                3,
+            ),
         },
-        Panel::FgColor | Panel::BgColor => "0-9,a-g:select │ ←→↑↓:nav │ Enter:apply │ Esc:editor",
-        Panel::Formatting => "B/I/U/S/M:toggle │ E:export │ Esc:editor",
+        Panel::FgColor => (
+            &[
+                ("0-9,a-g", "select"),
+                ("←→↑↓", "nav"),
+                ("Enter", "apply"),
+                ("Ctrl+X", "swap"),
+                ("Esc", "editor"),
+                ("Ctrl+F", "last panel"),
+                ("t", "theme from FG"),
+                ("p", "live/on Enter"),
+                ("#", "RGB entry"),
+                ("+/-", "lighter/darker"),
+                ("x", "swap on text"),
+                ("r", "reset style"),
+                ("Tab/Shift+Tab", "panels"),
+                ("F1", "less help"),
+            ],
+            5,
+        ),
+        // No theme key here, it only derives from the foreground
+        Panel::BgColor => (
+            &[
+                ("0-9,a-g", "select"),
+                ("←→↑↓", "nav"),
+                ("Enter", "apply"),
+                ("Ctrl+X", "swap"),
+                ("Esc", "editor"),
+                ("Ctrl+F", "last panel"),
+                ("p", "live/on Enter"),
+                ("#", "RGB entry"),
+                ("+/-", "lighter/darker"),
+                ("x", "swap on text"),
+                ("r", "reset style"),
+                ("Tab/Shift+Tab", "panels"),
+                ("F1", "less help"),
+            ],
+            5,
+        ),
+        Panel::Formatting => (
+            &[
+                ("B/I/U/W/O/S/K/V/H/M", "toggle"),
+                ("E", "export"),
+                ("X", "escape style"),
+                ("C", "fix contrast"),
+                ("Esc", "editor"),
+                ("1-0", "toggle"),
+                ("Enter", "finish selection"),
+                ("r", "reset style"),
+                ("Tab/Shift+Tab", "panels"),
+                ("F1", "less help"),
+            ],
+            3,
+        ),
+    }
+}
+
+/// Shorten `text` to at most `max` terminal columns, ending in an ellipsis when cut.
+/// Cuts only on character boundaries, and wide characters count as two columns.
+fn truncate_with_ellipsis(text: &str, max: usize) -> String {
+    if text.width() <= max {
+        return text.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let width = ch.width().unwrap_or(0);
+        if used + width > max - 1 {
+            break;
+        }
+        truncated.push(ch);
+        used += width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Keybinding hints for the footer, collapsed or expanded
+fn help_text(app: &App) -> String {
+    let (entries, collapsed_len) = help_entries(app.active_panel, app.mode);
+    let shown = if app.help_expanded {
+        entries
+    } else {
+        &entries[..collapsed_len]
     };
+    let toggle_hint = if app.help_expanded { "F1:less" } else { "F1:more" };
+    std::iter::once(toggle_hint.to_string())
+        .chain(shown.iter().map(|(keys, action)| format!("{}:{}", keys, action)))
+        .collect::<Vec<_>>()
+        .join(" │ ")
+}
 
-    let mut spans = vec![
-        Span::styled(" ", Style::default()),
-        Span::styled(help_text, Style::default().fg(theme::TEXT_MUTED)),
-    ];
+/// Rows the expanded footer may take, however much or little help there is
+const HELP_ROWS: std::ops::RangeInclusive<u16> = 2..=6;
+
+/// Rows the expanded footer needs when word-wrapped to `width`
+fn expanded_help_height(app: &App, width: u16) -> u16 {
+    let width = (width as usize).saturating_sub(3).max(1);
+    let mut rows = 1;
+    let mut line_len = 0;
+    for word in help_text(app).split(' ') {
+        let len = word.width();
+        if line_len > 0 && line_len + 1 + len > width {
+            rows += 1;
+            line_len = len;
+        } else {
+            line_len += if line_len > 0 { len + 1 } else { len };
+        }
+    }
+    rows.clamp(*HELP_ROWS.start(), *HELP_ROWS.end())
+}
+
+fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let help_text = help_text(app);
+
+    // An open prompt replaces the help line
+    if let Some(ref prompt) = app.prompt {
+        let line = Line::from(vec![
+            Span::styled(" ", Style::default()),
+            Span::styled(
+                format!("{}: ", prompt.kind.label()),
+                Style::default().fg(theme::ACCENT_SECONDARY),
+            ),
+            Span::styled(prompt.input.clone(), Style::default().fg(theme::TEXT_PRIMARY)),
+            Span::styled("▌", Style::default().fg(theme::ACCENT_PRIMARY)),
+            Span::styled("  Enter:ok │ Esc:cancel", Style::default().fg(theme::TEXT_MUTED)),
+        ]);
+        let status = Paragraph::new(line).style(Style::default().bg(theme::BG_PRIMARY));
+        frame.render_widget(status, area);
+        return;
+    }
+
+    // Leading space, then the status message takes priority over the help hints
+    let budget = (area.width as usize * area.height as usize).saturating_sub(1);
+    let separator = " │ ";
+    let message = app
+        .status_message
+        .as_deref()
+        .map(|msg| truncate_with_ellipsis(msg, budget));
+    let help_budget = match message {
+        Some(ref msg) => budget.saturating_sub(msg.width() + separator.width()),
+        None => budget,
+    };
+
+    let mut spans = vec![Span::styled(" ", Style::default())];
+    if help_budget > 0 {
+        spans.push(Span::styled(
+            truncate_with_ellipsis(&help_text, help_budget),
+            Style::default().fg(theme::TEXT_MUTED),
+        ));
+    }
 
     // Add status message if present
-    if let Some(ref msg) = app.status_message {
-        spans.push(Span::styled(" │ ", Style::default().fg(theme::BORDER_DEFAULT)));
-        
+    if let Some(msg) = message {
+        if help_budget > 0 {
+            spans.push(Span::styled(separator, Style::default().fg(theme::BORDER_DEFAULT)));
+        }
+
         let msg_style = if msg.starts_with('✓') {
             Style::default().fg(theme::SUCCESS)
         } else if msg.starts_with('✗') {
@@ -520,12 +1287,360 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         } else {
             Style::default().fg(theme::ACCENT_SECONDARY)
         };
-        
-        spans.push(Span::styled(msg.clone(), msg_style));
+
+        spans.push(Span::styled(msg, msg_style));
     }
 
-    let status = Paragraph::new(Line::from(spans))
+    let mut status = Paragraph::new(Line::from(spans))
         .style(Style::default().bg(theme::BG_PRIMARY));
+    if app.help_expanded {
+        status = status.wrap(Wrap { trim: true });
+    }
 
     frame.render_widget(status, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{CharStyle, StyledChar};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    /// Render the full UI into a test buffer and return its rows
+    fn render_rows(app: &App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| render(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_help_footer_collapsed() {
+        let app = App::new();
+        let rows = render_rows(&app, 120, 30);
+        let footer = &rows[28];
+        assert!(footer.contains("F1:more │ i:insert │ v:select"));
+        assert!(!footer.contains("Ctrl+E:RON"));
+        assert!(rows[27].trim().is_empty());
+    }
+
+    #[test]
+    fn test_help_footer_expanded() {
+        let app = App {
+            help_expanded: true,
+            ..App::new()
+        };
+        let rows = render_rows(&app, 160, 30);
+        // Footer rows sit above the bottom border, however many the help needs
+        let height = expanded_help_height(&app, 160) as usize;
+        let footer = rows[29 - height..29].concat();
+        assert!(footer.contains("F1:less │ i:insert"));
+        assert!(footer.contains("Ctrl+E:RON"));
+        assert!(footer.contains("Ctrl+V:import"));
+    }
+
+    #[test]
+    fn test_help_entries_follow_context() {
+        let (entries, collapsed_len) = help_entries(Panel::Formatting, Mode::Normal);
+        assert_eq!(entries[0], ("B/I/U/W/O/S/K/V/H/M", "toggle"));
+        assert!(collapsed_len <= entries.len());
+    }
+
+    #[test]
+    fn test_theme_key_only_in_fg_help() {
+        let has_theme = |panel| help_entries(panel, Mode::Normal).0.iter().any(|(key, _)| *key == "t");
+        assert!(has_theme(Panel::FgColor));
+        assert!(!has_theme(Panel::BgColor));
+    }
+
+    /// Find the first cell showing `symbol`
+    fn find_cell(buffer: &ratatui::buffer::Buffer, symbol: &str) -> ratatui::buffer::Cell {
+        buffer
+            .content()
+            .iter()
+            .find(|cell| cell.symbol() == symbol)
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_transparency_checkerboard() {
+        let mut app = App::from_text("§¶\nµ¤");
+        app.text[1].style.bg = ratatui::style::Color::Blue;
+        app.active_panel = Panel::FgColor;
+        app.show_transparency = true;
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+
+        assert_eq!(find_cell(buffer, "§").bg, theme::BG_SECONDARY);
+        assert_eq!(find_cell(buffer, "¶").bg, ratatui::style::Color::Blue);
+        assert_eq!(find_cell(buffer, "µ").bg, theme::BG_PRIMARY);
+        assert_eq!(find_cell(buffer, "¤").bg, theme::BG_SECONDARY);
+    }
+
+    #[test]
+    fn test_transparency_keeps_cursor_and_selection() {
+        let mut app = App::from_text("§¶µ");
+        app.cursor_pos = 0;
+        app.selection = Some((1, 1));
+        app.show_transparency = true;
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+
+        assert_eq!(find_cell(buffer, "§").bg, theme::ACCENT_PRIMARY);
+        let selected = find_cell(buffer, "¶");
+        assert!(selected.modifier.contains(Modifier::REVERSED));
+        assert_eq!(selected.bg, theme::BG_PRIMARY);
+        assert_eq!(find_cell(buffer, "µ").bg, theme::BG_SECONDARY);
+
+        // Export is unaffected by the visual aid
+        assert!(app.text.iter().all(|c| c.style.bg == ratatui::style::Color::Reset));
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis_multibyte() {
+        assert_eq!(truncate_with_ellipsis("✗ Fehler: Größe", 8), "✗ Fehle…");
+        assert_eq!(truncate_with_ellipsis("ünïcödé", 7), "ünïcödé");
+        assert_eq!(truncate_with_ellipsis("ünïcödé", 1), "…");
+        assert_eq!(truncate_with_ellipsis("ünïcödé", 0), "");
+        // Wide characters take two columns each
+        assert_eq!(truncate_with_ellipsis("日本語テキスト", 6), "日本…");
+        assert_eq!(truncate_with_ellipsis("日本語", 6), "日本語");
+    }
+
+    #[test]
+    fn test_status_message_truncated_before_help() {
+        let mut app = App::new();
+        app.set_status("✗ Import failed: ÄÖÜ – ungültige Sequenz in /tmp/very/long/path");
+
+        let mut terminal = Terminal::new(TestBackend::new(30, 1)).unwrap();
+        terminal
+            .draw(|frame| render_status_bar(frame, &app, frame.area()))
+            .unwrap();
+        let row: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        // Help hints give way entirely; the message fills the line and ends in an ellipsis
+        assert_eq!(row, " ✗ Import failed: ÄÖÜ – ungül…");
+    }
+
+    #[test]
+    fn test_scrolled_editor_shows_cursor_line() {
+        let text: String = (0..40).map(|i| format!("line{:02}\n", i)).collect();
+        let mut app = App::from_text(&text);
+        app.ensure_cursor_visible(editor_viewport_height(Rect::new(0, 0, 100, 30), &app));
+        let rows = render_rows(&app, 100, 30).join("\n");
+        assert!(rows.contains("line39"));
+        assert!(!rows.contains("line00"));
+    }
+
+    #[test]
+    fn test_line_focus_dims_other_lines() {
+        let mut app = App::from_text("§§\n¶¶\nµµ");
+        app.cursor_pos = 4; // second line
+        app.active_panel = Panel::FgColor;
+        app.line_focus = true;
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+
+        assert!(find_cell(buffer, "§").modifier.contains(Modifier::DIM));
+        assert!(!find_cell(buffer, "¶").modifier.contains(Modifier::DIM));
+        assert!(find_cell(buffer, "µ").modifier.contains(Modifier::DIM));
+        // Buffer is untouched
+        assert!(app.text.iter().all(|c| c.style.dim_level == 0));
+    }
+
+    #[test]
+    fn test_wrap_markers_only_on_soft_wraps() {
+        let text = format!("{}\n{}", "§".repeat(50), "¶".repeat(5));
+        let mut app = App::from_text(&text);
+        app.show_wrap_markers = true;
+
+        let rows = render_rows(&app, 40, 40);
+        let wrapped_rows: Vec<&String> = rows.iter().filter(|r| r.contains('§')).collect();
+        assert_eq!(wrapped_rows.len(), 2);
+        assert!(wrapped_rows[0].contains("§\\│"));
+        assert!(!wrapped_rows[1].contains('\\'));
+        let short_row = rows.iter().find(|r| r.contains('¶')).unwrap();
+        assert!(!short_row.contains('\\'));
+
+        // Markers are opt-in
+        app.show_wrap_markers = false;
+        let rows = render_rows(&app, 40, 40);
+        assert!(rows.iter().all(|r| !r.contains('\\')));
+    }
+
+    #[test]
+    fn test_dim_levels_render_distinct_shades() {
+        let dim = |ch, dim_level| {
+            StyledChar::with_style(ch, CharStyle { fg: Color::White, dim_level, ..Default::default() })
+        };
+        let mut app = App::from_styled(vec![dim('§', 1), dim('¶', 3), dim('µ', 0)]);
+        app.active_panel = Panel::FgColor;
+        app.text.push(StyledChar::with_style('ß', CharStyle { dim_level: 2, ..Default::default() }));
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+
+        let brightness = |symbol| match find_cell(buffer, symbol).fg {
+            Color::Rgb(r, g, b) => r as u32 + g as u32 + b as u32,
+            other => panic!("expected an RGB fg, got {:?}", other),
+        };
+        assert!(brightness("¶") < brightness("§"));
+        assert_eq!(find_cell(buffer, "µ").fg, Color::White);
+        // Default fg can't be blended
+        assert!(find_cell(buffer, "ß").modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn test_terminal_bg_preview_drops_theme_background() {
+        let mut app = App::from_text("§");
+        app.text.push(StyledChar::with_style('¶', CharStyle { bg: Color::Blue, ..Default::default() }));
+        app.active_panel = Panel::FgColor;
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        assert_eq!(find_cell(terminal.backend().buffer(), "§").bg, theme::BG_PRIMARY);
+
+        app.terminal_bg_preview = true;
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_eq!(find_cell(buffer, "§").bg, Color::Reset);
+        assert_eq!(find_cell(buffer, "¶").bg, Color::Blue);
+        // The editor border keeps the app theme
+        assert_eq!(find_cell(buffer, "╭").bg, theme::BG_PRIMARY);
+    }
+
+    #[test]
+    fn test_editor_extra_rows_move_split() {
+        let size = Rect::new(0, 0, 100, 40);
+        let mut app = App::new();
+        let base = main_layout(size, &app);
+
+        app.editor_extra_rows = -3;
+        let shrunk = main_layout(size, &app);
+        assert_eq!(shrunk.editor.height, base.editor.height - 3);
+        assert_eq!(shrunk.controls.height, base.controls.height + 3);
+
+        // Growing the editor stops once the controls are at their minimum
+        app.editor_extra_rows = 10;
+        let grown = main_layout(size, &app);
+        assert_eq!(grown.controls.height, MIN_CONTROLS_HEIGHT as u16);
+        assert_eq!(grown.editor.height, base.editor.height + 1);
+    }
+
+    #[test]
+    fn test_split_bounds_follow_layout() {
+        let mut app = App::new();
+        assert_eq!(editor_rows_bounds(Rect::new(0, 0, 100, 40), &app).1, 1);
+
+        // Stacked panels each keep their minimum height
+        let narrow = Rect::new(0, 0, 60, 40);
+        app.editor_rows_bounds = editor_rows_bounds(narrow, &app);
+        assert_eq!(app.editor_rows_bounds.1, 3);
+        for _ in 0..10 {
+            app.resize_editor(1);
+        }
+        assert_eq!(app.editor_extra_rows, 3);
+        assert_eq!(main_layout(narrow, &app).controls.height, 3 * MIN_CONTROLS_HEIGHT as u16);
+
+        // No dead presses to undo: the first shrink moves the split back
+        app.resize_editor(-1);
+        assert_eq!(app.editor_extra_rows, 2);
+
+        // The controls only grow while the editor keeps its minimum height
+        let (min, _) = app.editor_rows_bounds;
+        app.editor_extra_rows = min;
+        // The editor's minimum of 4 rows, less its top margin
+        assert_eq!(main_layout(narrow, &app).editor.height, 3);
+    }
+
+    #[test]
+    fn test_reverse_and_hidden_render() {
+        let reverse = CharStyle { reverse: true, ..Default::default() };
+        let mut app = App::from_styled(vec![
+            StyledChar::with_style('§', reverse.clone()),
+            StyledChar::with_style('¶', reverse),
+            StyledChar::with_style('µ', CharStyle { hidden: true, ..Default::default() }),
+        ]);
+        app.active_panel = Panel::FgColor;
+        app.selection = Some((1, 1));
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert!(find_cell(buffer, "§").modifier.contains(Modifier::REVERSED));
+        // Selected reverse-video text is reversed twice, i.e. shown normally
+        assert!(!find_cell(buffer, "¶").modifier.contains(Modifier::REVERSED));
+        assert!(!buffer.content().iter().any(|cell| cell.symbol() == "µ"));
+    }
+
+    #[test]
+    fn test_rgb_entry_shows_swatch_and_hex() {
+        let mut app = App::new();
+        app.active_panel = Panel::FgColor;
+        app.open_rgb_entry(true);
+        app.rgb_entry.as_mut().unwrap().rgb = [255, 100, 0];
+        let rows = render_rows(&app, 120, 30);
+        assert!(rows.iter().any(|row| row.contains("#ff6400") && row.contains("      ")));
+        assert!(rows.iter().any(|row| row.contains("R 255  G 100  B   0")));
+        assert!(rows.iter().any(|row| row.contains("HSL 24° 100% 50%")));
+    }
+
+    #[test]
+    fn test_mixed_selection_shows_tilde() {
+        let mut app = App::from_text("ab");
+        app.text[0].style.bold = true;
+        app.selection = Some((0, 1));
+        let rows = render_rows(&app, 200, 30);
+        assert!(rows.iter().any(|row| row.contains("[B]~") && row.contains("[I]talic")));
+
+        app.touched.bold = true;
+        let rows = render_rows(&app, 200, 30);
+        assert!(!rows.iter().any(|row| row.contains("[B]~")));
+    }
+
+    #[test]
+    fn test_formatting_indicators_fit_panel() {
+        let app = App::new();
+        for width in [80, 120] {
+            let rows = render_rows(&app, width, 30).join("\n");
+            for key in ["B", "I", "U", "W", "O", "K", "V", "S", "H", "M", "E"] {
+                assert!(rows.contains(&format!("[{}]", key)), "[{}] cut off at {} columns", key, width);
+            }
+        }
+    }
+
+    #[test]
+    fn test_contrast_warning_visible_at_80_columns() {
+        let mut app = App::new();
+        app.current_fg = ratatui::style::Color::Black;
+        app.current_bg = ratatui::style::Color::Black;
+        let rows = render_rows(&app, 80, 30);
+        assert!(rows.iter().any(|row| row.contains("│⚠ [C] 1.0:1 [B]old")));
+
+        // Still shown, in short form, with the decorations panel squeezed to a single row
+        app.editor_rows_bounds = editor_rows_bounds(Rect::new(0, 0, 80, 30), &app);
+        for _ in 0..3 {
+            app.resize_editor(1);
+        }
+        let rows = render_rows(&app, 80, 30);
+        assert!(rows.iter().any(|row| row.contains("│⚠[C]1.0 [B]")));
+    }
+}